@@ -0,0 +1,30 @@
+use crate::commands::AppState;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the idle timer is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Event emitted to the frontend when the idle timer wipes the vault key
+pub const VAULT_LOCKED_EVENT: &str = "vault-locked";
+
+/// Start the background idle timer
+///
+/// Runs for the lifetime of the app and locks the vault regardless of what the
+/// frontend does, so a stalled or tampered webview cannot keep it open.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppState>();
+            if state.lock_if_idle() {
+                if let Err(e) = app.emit(VAULT_LOCKED_EVENT, ()) {
+                    eprintln!("Failed to emit {} event: {}", VAULT_LOCKED_EVENT, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Hex digits of the hash prefix that names a range file
+const RANGE_PREFIX_LEN: usize = 5;
+
+#[derive(Error, Debug)]
+pub enum BreachError {
+    #[error("Breach dataset not found: {0}")]
+    DatasetNotFound(String),
+    #[error("Failed to read breach dataset: {0}")]
+    Io(#[from] io::Error),
+    #[error("Breach dataset is corrupt: the line at byte {0} has no valid count")]
+    MalformedLine(u64),
+}
+
+/// A vault entry whose password appears in the breach dataset
+#[derive(Debug, Clone, Serialize)]
+pub struct BreachedPassword {
+    pub entry_id: String,
+    /// Times the password was seen in breaches
+    pub count: u64,
+}
+
+/// A local copy of the Have I Been Pwned SHA-1 password hashes
+///
+/// Either one file of `HASH:COUNT` lines sorted by hash, or a directory of
+/// range files named after the first five hex digits of the hash, each
+/// holding sorted `SUFFIX:COUNT` lines. Both are searched by bisecting on
+/// byte offsets, so a lookup reads a few lines rather than the whole file.
+pub enum BreachDataset {
+    SortedFile(SortedHashFile),
+    RangeDirectory(PathBuf),
+}
+
+impl BreachDataset {
+    pub fn open(path: &Path) -> Result<Self, BreachError> {
+        if path.is_dir() {
+            Ok(BreachDataset::RangeDirectory(path.to_path_buf()))
+        } else if path.is_file() {
+            Ok(BreachDataset::SortedFile(SortedHashFile::open(path)?))
+        } else {
+            Err(BreachError::DatasetNotFound(path.display().to_string()))
+        }
+    }
+
+    /// Breach count of `password`, or None if it is not in the dataset
+    pub fn breach_count(&mut self, password: &str) -> Result<Option<u64>, BreachError> {
+        let hash = sha1_hex(password);
+        match self {
+            BreachDataset::SortedFile(file) => file.find(&hash),
+            BreachDataset::RangeDirectory(dir) => {
+                let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+                let candidates = [
+                    dir.join(prefix),
+                    dir.join(format!("{prefix}.txt")),
+                    dir.join(prefix.to_lowercase()),
+                    dir.join(format!("{}.txt", prefix.to_lowercase())),
+                ];
+                match candidates.iter().find(|path| path.is_file()) {
+                    Some(path) => SortedHashFile::open(path)?.find(suffix),
+                    // No range file means no hash with this prefix was breached
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+/// Uppercase hex SHA-1, the form the dataset uses
+pub fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+/// A file of `KEY:COUNT` lines sorted by key
+pub struct SortedHashFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl SortedHashFile {
+    fn open(path: &Path) -> Result<Self, BreachError> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(SortedHashFile {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// Count stored for `key`, found by bisecting on byte offsets
+    ///
+    /// The search keeps the invariant that the matching line, if any,
+    /// starts in `lo..hi`.
+    fn find(&mut self, key: &str) -> Result<Option<u64>, BreachError> {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let Some((start, next, line)) = self.line_at_or_after(mid)? else {
+                hi = mid;
+                continue;
+            };
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+
+            let (line_key, count) = line.split_once(':').unwrap_or((line.as_str(), ""));
+            match compare_keys(line_key, key) {
+                Ordering::Less => lo = next,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => {
+                    let count = count.trim().parse().map_err(|_| BreachError::MalformedLine(start))?;
+                    return Ok(Some(count));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The first line starting at or after `offset`, with its start offset
+    /// and the offset just past it
+    fn line_at_or_after(&mut self, offset: u64) -> io::Result<Option<(u64, u64, String)>> {
+        let mut start = offset;
+        if offset > 0 {
+            // Back up one byte to tell whether `offset` begins a line
+            self.reader.seek(SeekFrom::Start(offset - 1))?;
+            let mut skipped = Vec::new();
+            let read = self.reader.read_until(b'\n', &mut skipped)?;
+            start = offset - 1 + read as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        if start >= self.len {
+            return Ok(None);
+        }
+
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, start + read as u64, line.trim().to_string())))
+    }
+}
+
+fn compare_keys(line_key: &str, key: &str) -> Ordering {
+    let line_key = line_key.trim().bytes().map(|b| b.to_ascii_uppercase());
+    line_key.cmp(key.bytes().map(|b| b.to_ascii_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PASSWORD_HASH: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+    #[test]
+    fn test_sha1_hex() {
+        assert_eq!(sha1_hex("password"), PASSWORD_HASH);
+    }
+
+    #[test]
+    fn test_sorted_file_lookup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+
+        let mut hashes: Vec<(String, u64)> = (0..500)
+            .map(|i| (sha1_hex(&format!("filler-{i}")), i + 1))
+            .collect();
+        hashes.push((PASSWORD_HASH.to_string(), 9_545_824));
+        hashes.push((sha1_hex("123456"), 37_359_195));
+        hashes.sort();
+        let contents: String = hashes.iter().map(|(hash, count)| format!("{hash}:{count}\r\n")).collect();
+        std::fs::write(&path, contents).unwrap();
+
+        let mut dataset = BreachDataset::open(&path).unwrap();
+        assert_eq!(dataset.breach_count("password").unwrap(), Some(9_545_824));
+        assert_eq!(dataset.breach_count("123456").unwrap(), Some(37_359_195));
+        assert_eq!(dataset.breach_count("filler-0").unwrap(), Some(1));
+        assert_eq!(dataset.breach_count("filler-499").unwrap(), Some(500));
+        assert_eq!(dataset.breach_count("not in the dataset").unwrap(), None);
+
+        // The first and last lines are reachable too
+        let BreachDataset::SortedFile(file) = &mut dataset else { panic!("expected a sorted file") };
+        for (hash, count) in [&hashes[0], &hashes[hashes.len() - 1]] {
+            assert_eq!(file.find(hash).unwrap(), Some(*count));
+        }
+    }
+
+    #[test]
+    fn test_range_directory_lookup() {
+        let dir = tempdir().unwrap();
+        let (prefix, suffix) = PASSWORD_HASH.split_at(RANGE_PREFIX_LEN);
+        std::fs::write(
+            dir.path().join(format!("{prefix}.txt")),
+            format!("0018A45C4D1DEF81644B54AB7F969B88D65:1\n{suffix}:9545824\nFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF:2\n"),
+        )
+        .unwrap();
+
+        let mut dataset = BreachDataset::open(dir.path()).unwrap();
+        assert_eq!(dataset.breach_count("password").unwrap(), Some(9_545_824));
+        // No range file for this prefix
+        assert_eq!(dataset.breach_count("123456").unwrap(), None);
+    }
+
+    #[test]
+    fn test_malformed_count() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pwned.txt");
+        let line = "0018A45C4D1DEF81644B54AB7F969B88D65000A:1\n";
+        std::fs::write(&path, format!("{line}{PASSWORD_HASH}:lots\n")).unwrap();
+
+        let mut dataset = BreachDataset::open(&path).unwrap();
+        assert!(matches!(
+            dataset.breach_count("password"),
+            Err(BreachError::MalformedLine(offset)) if offset == line.len() as u64
+        ));
+    }
+
+    #[test]
+    fn test_missing_dataset() {
+        let dir = tempdir().unwrap();
+        assert!(matches!(
+            BreachDataset::open(&dir.path().join("missing")),
+            Err(BreachError::DatasetNotFound(_))
+        ));
+    }
+}
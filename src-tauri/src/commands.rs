@@ -1,62 +1,630 @@
-use crate::crypto::CryptoService;
-use crate::db::{CreatePasswordEntry, Database, PasswordEntry, UpdatePasswordEntry};
+use crate::breach::{BreachDataset, BreachError, BreachedPassword};
+use crate::crypto::{CryptoService, KdfParams, SecretKey};
+use crate::db::{
+    Attachment, AutoLockSettings, Category, CategoryDeletion, CreateCategory, CreatePasswordEntry, CustomField, CustomFieldType,
+    Database, DatabaseError, EntryHistory, HealthSettings, LockoutSettings, PasswordEntry, SavedPasswordPolicy,
+    TrashSettings, UpdateCategory, UpdatePasswordEntry, MAX_ATTACHMENT_SIZE,
+};
+use crate::export;
+use crate::generator::{Passphrase, PassphraseOptions, PasswordGenerator, PasswordPolicy, PolicyError};
+use crate::health::{self, HealthReport};
+use crate::items::{ItemData, ItemError, ItemType};
+use crate::strength::{self, StrengthEstimate};
+use crate::totp::{Totp, TotpCode, TotpError};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::State;
+use zeroize::Zeroizing;
 
+/// Target time for one key derivation when a vault is created or upgraded
+const TARGET_UNLOCK_TIME: Duration = Duration::from_millis(500);
+
+/// Lowest allowed wipe threshold, so a few typos cannot erase the vault
+const MIN_WIPE_AFTER_FAILURES: u32 = 5;
+
+/// Lowest strength score accepted for a new master password
+const MIN_MASTER_PASSWORD_SCORE: u8 = 3;
+
+/// Words tied to the app that an attacker would try in a master password
+const MASTER_PASSWORD_INPUTS: [&str; 4] = ["passvault", "passwordvault", "vault", "master"];
+
+/// Error returned to the frontend
+///
+/// `code` identifies errors the UI reacts to, such as `too_many_attempts`,
+/// whose `retry_after_secs` says how long to wait before the next unlock.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppError {
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
 }
 
-impl From<crate::db::DatabaseError> for AppError {
-    fn from(err: crate::db::DatabaseError) -> Self {
+impl AppError {
+    fn new(message: impl Into<String>) -> Self {
         AppError {
-            message: err.to_string(),
+            message: message.into(),
+            code: None,
+            retry_after_secs: None,
+        }
+    }
+
+    fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+}
+
+impl From<DatabaseError> for AppError {
+    fn from(err: DatabaseError) -> Self {
+        let error = AppError::new(err.to_string());
+
+        match err {
+            DatabaseError::InvalidMasterPassword => error.with_code("invalid_master_password"),
+            DatabaseError::TooManyAttempts { retry_after_secs } => AppError {
+                retry_after_secs: Some(retry_after_secs),
+                ..error.with_code("too_many_attempts")
+            },
+            DatabaseError::VaultWiped => error.with_code("vault_wiped"),
+            DatabaseError::InvalidRecoveryCode => error.with_code("invalid_recovery_code"),
+            DatabaseError::CategoryNotFound => error.with_code("category_not_found"),
+            DatabaseError::DuplicateCategory(_) => error.with_code("duplicate_category"),
+            DatabaseError::AttachmentNotFound => error.with_code("attachment_not_found"),
+            DatabaseError::AttachmentTooLarge { .. } => error.with_code("attachment_too_large"),
+            _ => error,
         }
     }
 }
 
 impl From<crate::crypto::CryptoError> for AppError {
     fn from(err: crate::crypto::CryptoError) -> Self {
-        AppError {
-            message: err.to_string(),
-        }
+        AppError::new(err.to_string())
+    }
+}
+
+impl From<BreachError> for AppError {
+    fn from(err: BreachError) -> Self {
+        AppError::new(err.to_string())
+    }
+}
+
+impl From<TotpError> for AppError {
+    fn from(err: TotpError) -> Self {
+        AppError::new(err.to_string()).with_code("invalid_totp")
+    }
+}
+
+impl From<ItemError> for AppError {
+    fn from(err: ItemError) -> Self {
+        AppError::new(err.to_string()).with_code("invalid_item")
+    }
+}
+
+impl From<PolicyError> for AppError {
+    fn from(err: PolicyError) -> Self {
+        AppError::new(err.to_string())
     }
 }
 
 pub struct AppState {
     pub db: Database,
-    pub encryption_key: Mutex<Option<[u8; 32]>>,
+    pub encryption_key: Mutex<Option<SecretKey>>,
+    pub last_activity: Mutex<Instant>,
+    pub auto_lock: Mutex<AutoLockSettings>,
+}
+
+impl AppState {
+    /// The auto-lock settings are encrypted, so they are read at unlock
+    pub fn new(db: Database) -> Self {
+        AppState {
+            db,
+            encryption_key: Mutex::new(None),
+            last_activity: Mutex::new(Instant::now()),
+            auto_lock: Mutex::new(AutoLockSettings::default()),
+        }
+    }
+
+    /// Copy of the vault key for one command; the copy is wiped when dropped
+    ///
+    /// Every authenticated command goes through here, so this also resets the
+    /// idle timer.
+    fn vault_key(&self) -> Result<SecretKey, AppError> {
+        let key = self
+            .encryption_key
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| AppError::new("Not authenticated"))?;
+
+        *self.last_activity.lock().unwrap() = Instant::now();
+        Ok(key)
+    }
+
+    /// Keep the vault key after a successful unlock, load the auto-lock
+    /// settings it decrypts and start the idle timer
+    ///
+    /// Settings that do not decrypt are replaced by the defaults, which lock
+    /// the vault, rather than keeping the user out of it.
+    async fn store_key(&self, key: SecretKey) {
+        let auto_lock = self.db.get_auto_lock_settings(&key).await.unwrap_or_else(|e| {
+            eprintln!("Failed to load auto-lock settings, using the defaults: {}", e);
+            AutoLockSettings::default()
+        });
+
+        *self.auto_lock.lock().unwrap() = auto_lock;
+        *self.encryption_key.lock().unwrap() = Some(key);
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Wipe the vault key if auto-lock is enabled and the idle period has
+    /// passed; returns whether the vault was locked by this call
+    pub fn lock_if_idle(&self) -> bool {
+        self.lock_if_idle_at(Instant::now())
+    }
+
+    /// `lock_if_idle` as of `now`
+    fn lock_if_idle_at(&self, now: Instant) -> bool {
+        let settings = self.auto_lock.lock().unwrap().clone();
+        if !settings.enabled {
+            return false;
+        }
+
+        let idle_limit = Duration::from_secs(u64::from(settings.idle_minutes) * 60);
+        if now.saturating_duration_since(*self.last_activity.lock().unwrap()) < idle_limit {
+            return false;
+        }
+
+        // Dropping the key wipes it from memory
+        self.encryption_key.lock().unwrap().take().is_some()
+    }
+
+    /// Combine the master password with the key file, if the vault uses one
+    async fn master_secret(
+        &self,
+        password: &str,
+        key_file_path: Option<&str>,
+    ) -> Result<Zeroizing<String>, AppError> {
+        let requires_key_file = self.db.requires_key_file().await?;
+
+        match (requires_key_file, key_file_path) {
+            (true, Some(path)) => {
+                let key_file = CryptoService::load_key_file(Path::new(path))?;
+                Ok(CryptoService::composite_password(password, Some(&key_file)))
+            }
+            (true, None) => Err(AppError::new("This vault requires its key file").with_code("key_file_required")),
+            (false, Some(_)) => Err(AppError::new("This vault does not use a key file")),
+            (false, None) => Ok(CryptoService::composite_password(password, None)),
+        }
+    }
+}
+
+/// Result of creating a vault; the recovery code is only ever shown here
+#[derive(Debug, Serialize)]
+pub struct SetupResult {
+    pub recovery_code: Option<Zeroizing<String>>,
+}
+
+/// Result of saving an entry
+///
+/// `strength` is set when the save changed a password, judged against the
+/// entry's own title, username and URL.
+#[derive(Debug, Serialize)]
+pub struct SavedEntry {
+    pub id: String,
+    pub strength: Option<StrengthEstimate>,
+    /// `weak_password` when the saved password is easy to guess
+    pub warning: Option<&'static str>,
+}
+
+impl SavedEntry {
+    fn new(id: String, strength: Option<StrengthEstimate>) -> Self {
+        let warning = strength
+            .as_ref()
+            .filter(|estimate| estimate.score <= strength::WEAK_SCORE)
+            .map(|_| "weak_password");
+        Self { id, strength, warning }
+    }
+}
+
+/// Strength of an entry's password, judged against the entry's own details;
+/// `None` for item types without a password, or when it is left empty
+fn entry_password_strength(
+    item_type: ItemType,
+    password: &str,
+    title: &str,
+    username: &str,
+    url: Option<&str>,
+) -> Option<StrengthEstimate> {
+    if !item_type.has_password() || password.is_empty() {
+        return None;
+    }
+    let inputs = [title.to_string(), username.to_string(), url.unwrap_or_default().to_string()];
+    Some(strength::estimate_strength(password, &inputs))
+}
+
+/// Reject a new master password that is too easy to guess
+fn check_master_password_strength(password: &str) -> Result<(), AppError> {
+    let inputs: Vec<String> = MASTER_PASSWORD_INPUTS.iter().map(|input| input.to_string()).collect();
+    let estimate = strength::estimate_strength(password, &inputs);
+    if estimate.score >= MIN_MASTER_PASSWORD_SCORE {
+        return Ok(());
+    }
+
+    let mut message = "Master password is too weak".to_string();
+    if let Some(reason) = estimate.warning.or_else(|| estimate.suggestions.into_iter().next()) {
+        message = format!("{message}: {reason}");
+    }
+    Err(AppError::new(message).with_code("weak_password"))
+}
+
+/// Create a new file next to `path` to be renamed over it once complete,
+/// so a failed write leaves whatever was there untouched
+fn create_temp_file(path: &Path) -> Result<(PathBuf, std::fs::File), AppError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::new("Choose where to save the file"))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.part", name.to_string_lossy(), uuid::Uuid::new_v4()));
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .map_err(|e| AppError::new(format!("Could not create {}: {}", path.display(), e)))?;
+
+    Ok((temp_path, file))
+}
+
+/// Calibrate Argon2 on a blocking thread; it runs for about `target` on purpose
+async fn calibrate_kdf(target: Duration) -> Result<KdfParams, AppError> {
+    tauri::async_runtime::spawn_blocking(move || CryptoService::calibrate_kdf(target))
+        .await
+        .map_err(|e| AppError::new(e.to_string()))?
+        .map_err(AppError::from)
+}
+
+/// Reject a TOTP secret codes cannot be generated from; blank means none
+fn validate_totp(totp: Option<&str>) -> Result<(), AppError> {
+    match totp.filter(|totp| !totp.trim().is_empty()) {
+        Some(totp) => Totp::parse(totp).map(|_| ()).map_err(AppError::from),
+        None => Ok(()),
+    }
+}
+
+/// Check an item's type-specific fields; logins have none
+fn validate_item_data(item_data: Option<&ItemData>) -> Result<(), AppError> {
+    item_data.map_or(Ok(()), |data| data.validate().map_err(AppError::from))
+}
+
+/// Require a name on every custom field, and a value of the right form
+/// on boolean and date fields
+fn validate_custom_fields(fields: &[CustomField]) -> Result<(), AppError> {
+    for field in fields {
+        let invalid = |message: String| Err(AppError::new(message).with_code("invalid_custom_field"));
+        if field.name.trim().is_empty() {
+            return invalid("Custom fields need a name".to_string());
+        }
+        match field.field_type {
+            CustomFieldType::Boolean if field.value != "true" && field.value != "false" => {
+                return invalid(format!("{} must be true or false", field.name));
+            }
+            CustomFieldType::Date
+                if !field.value.is_empty()
+                    && chrono::NaiveDate::parse_from_str(&field.value, "%Y-%m-%d").is_err() =>
+            {
+                return invalid(format!("{} must be a date in YYYY-MM-DD form", field.name));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn setup_master_password(
-    password: String,
+    password: Zeroizing<String>,
+    key_file_path: Option<String>,
+    create_recovery_code: bool,
     state: State<'_, AppState>,
-) -> Result<bool, AppError> {
-    state.db.set_master_password(&password).await?;
+) -> Result<SetupResult, AppError> {
+    check_master_password_strength(&password)?;
+
+    let key_file = key_file_path
+        .map(|path| CryptoService::load_key_file(Path::new(&path)))
+        .transpose()?;
+    let secret = CryptoService::composite_password(&password, key_file.as_ref());
+
+    let params = calibrate_kdf(TARGET_UNLOCK_TIME).await?;
+    state.db.set_master_password(&secret, &params, key_file.is_some()).await?;
     
-    let key = state.db.verify_master_password(&password).await?;
-    *state.encryption_key.lock().unwrap() = Some(key);
+    let key = state.db.verify_master_password(&secret).await?;
+    let recovery_code = if create_recovery_code {
+        Some(state.db.create_recovery_code(&key).await?)
+    } else {
+        None
+    };
+    state.store_key(key).await;
     
-    Ok(true)
+    Ok(SetupResult { recovery_code })
 }
 
 #[tauri::command]
 pub async fn verify_master_password(
-    password: String,
+    password: Zeroizing<String>,
+    key_file_path: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<bool, AppError> {
-    match state.db.verify_master_password(&password).await {
+    let secret = state.master_secret(&password, key_file_path.as_deref()).await?;
+
+    match state.db.verify_master_password(&secret).await {
         Ok(key) => {
-            *state.encryption_key.lock().unwrap() = Some(key);
+            state.store_key(key).await;
             Ok(true)
         }
-        Err(_) => Ok(false),
+        Err(DatabaseError::InvalidMasterPassword) => Ok(false),
+        Err(e) => Err(e.into()),
     }
 }
 
+#[tauri::command]
+pub async fn change_master_password(
+    old_password: Zeroizing<String>,
+    new_password: Zeroizing<String>,
+    key_file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_master_password_strength(&new_password)?;
+
+    // The key file requirement carries over to the new password
+    let old_secret = state.master_secret(&old_password, key_file_path.as_deref()).await?;
+    let new_secret = state.master_secret(&new_password, key_file_path.as_deref()).await?;
+
+    let key = state.db.change_master_password(&old_secret, &new_secret).await?;
+    state.store_key(key).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn recover_with_key(
+    recovery_code: Zeroizing<String>,
+    new_password: Zeroizing<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_master_password_strength(&new_password)?;
+
+    let key = state.db.recover_with_code(&recovery_code, &new_password).await?;
+    state.store_key(key).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn has_recovery_key(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.db.has_recovery_code().await?)
+}
+
+/// Replace the recovery code; asks for the master password again so an
+/// unattended unlocked session cannot mint a code for later use
+#[tauri::command]
+pub async fn regenerate_recovery_key(
+    password: Zeroizing<String>,
+    key_file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Zeroizing<String>, AppError> {
+    let secret = state.master_secret(&password, key_file_path.as_deref()).await?;
+    let key = state.db.verify_master_password(&secret).await?;
+    Ok(state.db.create_recovery_code(&key).await?)
+}
+
+#[tauri::command]
+pub async fn revoke_recovery_key(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    state.db.revoke_recovery_code().await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn requires_key_file(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.db.requires_key_file().await?)
+}
+
+#[tauri::command]
+pub async fn has_plaintext_metadata(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.db.has_plaintext_metadata().await?)
+}
+
+/// Require a key file besides the master password from now on
+#[tauri::command]
+pub async fn add_key_file(
+    password: Zeroizing<String>,
+    key_file_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if state.db.requires_key_file().await? {
+        return Err(AppError::new("This vault already uses a key file"));
+    }
+
+    let key_file = CryptoService::load_key_file(Path::new(&key_file_path))?;
+    let secret = CryptoService::composite_password(&password, Some(&key_file));
+
+    let key = state.db.set_key_file_requirement(&password, &secret, true).await?;
+    state.store_key(key).await;
+
+    Ok(())
+}
+
+/// Go back to unlocking with the master password alone
+#[tauri::command]
+pub async fn remove_key_file(
+    password: Zeroizing<String>,
+    key_file_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let secret = state.master_secret(&password, Some(&key_file_path)).await?;
+
+    let key = state.db.set_key_file_requirement(&secret, &password, false).await?;
+    state.store_key(key).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_kdf_params(state: State<'_, AppState>) -> Result<KdfParams, AppError> {
+    Ok(state.db.get_kdf_params().await?)
+}
+
+#[tauri::command]
+pub async fn upgrade_kdf_params(
+    password: Zeroizing<String>,
+    key_file_path: Option<String>,
+    target_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<KdfParams, AppError> {
+    let secret = state.master_secret(&password, key_file_path.as_deref()).await?;
+    let target = target_ms.map(Duration::from_millis).unwrap_or(TARGET_UNLOCK_TIME);
+    let params = calibrate_kdf(target).await?;
+    
+    let key = state.db.upgrade_kdf_params(&secret, &params).await?;
+    state.store_key(key).await;
+
+    Ok(params)
+}
+
+#[tauri::command]
+pub async fn get_auto_lock_settings(state: State<'_, AppState>) -> Result<AutoLockSettings, AppError> {
+    Ok(state.auto_lock.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_auto_lock_settings(
+    settings: AutoLockSettings,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let key = state.vault_key()?;
+
+    if settings.idle_minutes == 0 {
+        return Err(AppError::new("Auto-lock time must be at least one minute"));
+    }
+
+    state.db.set_auto_lock_settings(&settings, &key).await?;
+    *state.auto_lock.lock().unwrap() = settings;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_lockout_settings(state: State<'_, AppState>) -> Result<LockoutSettings, AppError> {
+    Ok(state.db.get_lockout_settings().await?)
+}
+
+#[tauri::command]
+pub async fn set_lockout_settings(
+    settings: LockoutSettings,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    if settings.wipe_after_failures.is_some_and(|limit| limit < MIN_WIPE_AFTER_FAILURES) {
+        return Err(AppError::new(format!(
+            "Wipe threshold must be at least {} failed attempts",
+            MIN_WIPE_AFTER_FAILURES
+        )));
+    }
+
+    state.db.set_lockout_settings(&settings).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_health_settings(state: State<'_, AppState>) -> Result<HealthSettings, AppError> {
+    Ok(state.db.get_health_settings().await?)
+}
+
+#[tauri::command]
+pub async fn set_health_settings(
+    settings: HealthSettings,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    if settings.max_password_age_days == 0 {
+        return Err(AppError::new("Maximum password age must be at least 1 day"));
+    }
+
+    state.db.set_health_settings(&settings).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_trash_settings(state: State<'_, AppState>) -> Result<TrashSettings, AppError> {
+    Ok(state.db.get_trash_settings().await?)
+}
+
+#[tauri::command]
+pub async fn set_trash_settings(
+    settings: TrashSettings,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    if settings.retention_days == 0 {
+        return Err(AppError::new("Trash retention must be at least 1 day"));
+    }
+
+    state.db.set_trash_settings(&settings).await?;
+    Ok(())
+}
+
+/// Report reused, weak and old passwords and entries without a URL
+#[tauri::command]
+pub async fn vault_health_report(state: State<'_, AppState>) -> Result<HealthReport, AppError> {
+    let key = state.vault_key()?;
+
+    let entries = state.db.get_all_entries(&key).await?;
+    let settings = state.db.get_health_settings().await?;
+    Ok(health::build_report(&entries, &settings, chrono::Utc::now()))
+}
+
+/// Look every vault password up in a local Have I Been Pwned dataset,
+/// either the sorted hash file or a directory of range files
+#[tauri::command]
+pub async fn check_breached_passwords(
+    dataset_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<BreachedPassword>, AppError> {
+    let key = state.vault_key()?;
+    let entries = state.db.get_all_entries(&key).await?;
+
+    // Every lookup seeks and reads the dataset, so the loop runs on a
+    // blocking thread
+    tauri::async_runtime::spawn_blocking(move || find_breached_passwords(Path::new(&dataset_path), &entries))
+        .await
+        .map_err(|e| AppError::new(e.to_string()))?
+}
+
+/// Entries whose password appears in the dataset at `dataset_path`
+fn find_breached_passwords(dataset_path: &Path, entries: &[PasswordEntry]) -> Result<Vec<BreachedPassword>, AppError> {
+    let mut dataset = BreachDataset::open(dataset_path)?;
+
+    let mut breached = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.item_type.has_password() && !entry.password.is_empty())
+    {
+        if let Some(count) = dataset.breach_count(&entry.password)? {
+            breached.push(BreachedPassword {
+                entry_id: entry.id.clone(),
+                count,
+            });
+        }
+    }
+    Ok(breached)
+}
+
 #[tauri::command]
 pub async fn has_master_password(state: State<'_, AppState>) -> Result<bool, AppError> {
     Ok(state.db.has_master_password().await?)
@@ -66,30 +634,40 @@ pub async fn has_master_password(state: State<'_, AppState>) -> Result<bool, App
 pub async fn create_password_entry(
     entry: CreatePasswordEntry,
     state: State<'_, AppState>,
-) -> Result<String, AppError> {
-    let key = state.encryption_key.lock().unwrap()
-        .ok_or_else(|| AppError { message: "Not authenticated".to_string() })?;
+) -> Result<SavedEntry, AppError> {
+    let key = state.vault_key()?;
+    validate_totp(entry.totp.as_deref())?;
+    validate_custom_fields(&entry.custom_fields)?;
+    validate_item_data(entry.item_data.as_ref())?;
     
+    let item_type = entry.item_data.as_ref().map_or(ItemType::Login, ItemData::item_type);
+    let strength = entry_password_strength(item_type, &entry.password, &entry.title, &entry.username, entry.url.as_deref());
     let id = state.db.create_entry(entry, &key).await?;
-    Ok(id)
+    Ok(SavedEntry::new(id, strength))
 }
 
 #[tauri::command]
 pub async fn get_all_entries(state: State<'_, AppState>) -> Result<Vec<PasswordEntry>, AppError> {
-    let key = state.encryption_key.lock().unwrap()
-        .ok_or_else(|| AppError { message: "Not authenticated".to_string() })?;
+    let key = state.vault_key()?;
     
     let entries = state.db.get_all_entries(&key).await?;
     Ok(entries)
 }
 
+#[tauri::command]
+pub async fn get_favorite_entries(state: State<'_, AppState>) -> Result<Vec<PasswordEntry>, AppError> {
+    let key = state.vault_key()?;
+
+    let entries = state.db.get_favorite_entries(&key).await?;
+    Ok(entries)
+}
+
 #[tauri::command]
 pub async fn get_entry_by_id(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<PasswordEntry, AppError> {
-    let key = state.encryption_key.lock().unwrap()
-        .ok_or_else(|| AppError { message: "Not authenticated".to_string() })?;
+    let key = state.vault_key()?;
     
     let entry = state.db.get_entry_by_id(&id, &key).await?;
     Ok(entry)
@@ -100,30 +678,232 @@ pub async fn update_password_entry(
     id: String,
     update: UpdatePasswordEntry,
     state: State<'_, AppState>,
-) -> Result<(), AppError> {
-    let key = state.encryption_key.lock().unwrap()
-        .ok_or_else(|| AppError { message: "Not authenticated".to_string() })?;
+) -> Result<SavedEntry, AppError> {
+    let key = state.vault_key()?;
+    validate_totp(update.totp.as_deref())?;
+    validate_custom_fields(update.custom_fields.as_deref().unwrap_or_default())?;
+    validate_item_data(update.item_data.as_ref().and_then(Option::as_ref))?;
     
+    let password_changed = update.password.is_some();
     state.db.update_entry(&id, update, &key).await?;
+    if !password_changed {
+        return Ok(SavedEntry::new(id, None));
+    }
+
+    // Judge the password against the entry as saved, not only the fields
+    // this update touched
+    let entry = state.db.get_entry_by_id(&id, &key).await?;
+    let strength = entry_password_strength(entry.item_type, &entry.password, &entry.title, &entry.username, entry.url.as_deref());
+    Ok(SavedEntry::new(id, strength))
+}
+
+/// Previous usernames and passwords of an entry, newest first
+#[tauri::command]
+pub async fn get_entry_history(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<EntryHistory>, AppError> {
+    let key = state.vault_key()?;
+
+    Ok(state.db.get_entry_history(&id, &key).await?)
+}
+
+#[tauri::command]
+pub async fn restore_entry_version(
+    id: String,
+    history_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let key = state.vault_key()?;
+
+    state.db.restore_entry_version(&id, &history_id, &key).await?;
     Ok(())
 }
 
+/// Drop all but the `keep` most recent history values of every entry
+#[tauri::command]
+pub async fn purge_entry_history(
+    keep: u32,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    state.vault_key()?;
+
+    Ok(state.db.purge_entry_history(keep).await?)
+}
+
+/// Current TOTP code of an entry and the seconds until it changes
+#[tauri::command]
+pub async fn get_totp_code(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<TotpCode, AppError> {
+    let key = state.vault_key()?;
+
+    let entry = state.db.get_entry_by_id(&id, &key).await?;
+    let secret = entry
+        .totp
+        .as_deref()
+        .filter(|totp| !totp.trim().is_empty())
+        .ok_or_else(|| AppError::new("Entry has no TOTP secret").with_code("no_totp"))?;
+    Ok(Totp::parse(secret)?.generate_current())
+}
+
+/// Move an entry to the trash
 #[tauri::command]
 pub async fn delete_password_entry(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<(), AppError> {
+    state.vault_key()?;
+
     state.db.delete_entry(&id).await?;
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_trashed_entries(state: State<'_, AppState>) -> Result<Vec<PasswordEntry>, AppError> {
+    let key = state.vault_key()?;
+
+    Ok(state.db.get_trashed_entries(&key).await?)
+}
+
+#[tauri::command]
+pub async fn restore_entry(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    state.db.restore_entry(&id).await?;
+    Ok(())
+}
+
+/// Permanently delete every entry in the trash
+#[tauri::command]
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<u64, AppError> {
+    state.vault_key()?;
+
+    Ok(state.db.empty_trash().await?)
+}
+
+/// Encrypt the file at `path` into the vault as an attachment of an entry
+#[tauri::command]
+pub async fn add_attachment(
+    entry_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Attachment, AppError> {
+    let key = state.vault_key()?;
+
+    let path = Path::new(&path);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| AppError::new("Choose a file to attach"))?;
+    let file = std::fs::File::open(path)
+        .map_err(|e| AppError::new(format!("Could not open {}: {}", path.display(), e)))?;
+    // Refuse early when the size is known; the database also stops reading
+    // once a file passes the cap
+    if file.metadata().is_ok_and(|metadata| metadata.len() > MAX_ATTACHMENT_SIZE) {
+        return Err(DatabaseError::AttachmentTooLarge { max_bytes: MAX_ATTACHMENT_SIZE }.into());
+    }
+
+    Ok(state.db.add_attachment(&entry_id, &name, file, &key).await?)
+}
+
+/// An entry's attachments, without their contents
+#[tauri::command]
+pub async fn get_attachments(
+    entry_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Attachment>, AppError> {
+    let key = state.vault_key()?;
+
+    Ok(state.db.get_attachments(&entry_id, &key).await?)
+}
+
+/// Decrypt an attachment to a file at `path`, replacing any file there
+#[tauri::command]
+pub async fn export_attachment(
+    id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let key = state.vault_key()?;
+
+    // Fail before touching the destination when there is nothing to export
+    state.db.get_attachment(&id, &key).await?;
+
+    let path = Path::new(&path);
+    let (temp_path, mut file) = create_temp_file(path)?;
+
+    let exported = match state.db.export_attachment(&id, &mut file, &key).await {
+        Ok(_) => file
+            .sync_all()
+            .and_then(|()| std::fs::rename(&temp_path, path))
+            .map_err(|e| AppError::new(format!("Could not write {}: {}", path.display(), e))),
+        Err(e) => Err(e.into()),
+    };
+    if exported.is_err() {
+        // Do not leave a partial file behind
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    exported
+}
+
+/// Write every entry, decrypted, to a JSON file at `path`, replacing any
+/// file there, and return how many were written
+///
+/// The file is not encrypted, so the master password is asked for again.
+#[tauri::command]
+pub async fn export_vault(
+    password: Zeroizing<String>,
+    key_file_path: Option<String>,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    state.vault_key()?;
+
+    let secret = state.master_secret(&password, key_file_path.as_deref()).await?;
+    let key = state.db.verify_master_password(&secret).await?;
+
+    let entries = state.db.get_all_entries(&key).await?;
+    let contents = Zeroizing::new(
+        serde_json::to_vec_pretty(&export::build_export(&entries, chrono::Utc::now()))
+            .map_err(|e| AppError::new(e.to_string()))?,
+    );
+
+    let path = Path::new(&path);
+    let (temp_path, mut file) = create_temp_file(path)?;
+    let written = file
+        .write_all(&contents)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| std::fs::rename(&temp_path, path))
+        .map_err(|e| AppError::new(format!("Could not write {}: {}", path.display(), e)));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    written?;
+
+    Ok(entries.len())
+}
+
+#[tauri::command]
+pub async fn delete_attachment(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    Ok(state.db.delete_attachment(&id).await?)
+}
+
 #[tauri::command]
 pub async fn search_entries(
     query: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PasswordEntry>, AppError> {
-    let key = state.encryption_key.lock().unwrap()
-        .ok_or_else(|| AppError { message: "Not authenticated".to_string() })?;
+    let key = state.vault_key()?;
     
     let entries = state.db.search_entries(&query, &key).await?;
     Ok(entries)
@@ -134,8 +914,227 @@ pub fn generate_password(length: usize, include_symbols: bool) -> String {
     CryptoService::generate_password(length, include_symbols)
 }
 
+#[tauri::command]
+pub fn generate_password_with_policy(policy: PasswordPolicy) -> Result<String, AppError> {
+    Ok(PasswordGenerator::generate(&policy)?)
+}
+
+#[tauri::command]
+pub fn estimate_strength(password: Zeroizing<String>, user_inputs: Vec<String>) -> StrengthEstimate {
+    strength::estimate_strength(&password, &user_inputs)
+}
+
+#[tauri::command]
+pub fn generate_passphrase(options: PassphraseOptions) -> Result<Passphrase, AppError> {
+    Ok(PasswordGenerator::generate_passphrase(&options)?)
+}
+
+#[tauri::command]
+pub async fn save_password_policy(
+    name: String,
+    policy: PasswordPolicy,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let key = state.vault_key()?;
+
+    // Refuse to save a policy that can never produce a password
+    PasswordGenerator::generate(&policy)?;
+
+    Ok(state.db.create_password_policy(&name, &policy, &key).await?)
+}
+
+#[tauri::command]
+pub async fn get_password_policies(
+    state: State<'_, AppState>,
+) -> Result<Vec<SavedPasswordPolicy>, AppError> {
+    let key = state.vault_key()?;
+
+    Ok(state.db.get_password_policies(&key).await?)
+}
+
+#[tauri::command]
+pub async fn delete_password_policy(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    state.db.delete_password_policy(&id).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_categories(state: State<'_, AppState>) -> Result<Vec<Category>, AppError> {
+    let key = state.vault_key()?;
+
+    Ok(state.db.get_categories(&key).await?)
+}
+
+#[tauri::command]
+pub async fn create_category(
+    category: CreateCategory,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let key = state.vault_key()?;
+
+    if category.name.trim().is_empty() {
+        return Err(AppError::new("Category name is required"));
+    }
+
+    Ok(state.db.create_category(&category, &key).await?)
+}
+
+#[tauri::command]
+pub async fn update_category(
+    id: String,
+    update: UpdateCategory,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let key = state.vault_key()?;
+
+    if update.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(AppError::new("Category name is required"));
+    }
+
+    state.db.update_category(&id, &update, &key).await?;
+    Ok(())
+}
+
+/// Delete a category; its entries move to another category or are left
+/// uncategorized, as the caller chooses
+#[tauri::command]
+pub async fn delete_category(
+    id: String,
+    deletion: CategoryDeletion,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.vault_key()?;
+
+    state.db.delete_category(&id, &deletion).await?;
+    Ok(())
+}
+
 #[tauri::command]
 pub fn logout(state: State<'_, AppState>) -> Result<(), AppError> {
+    // Dropping the key wipes it from memory
     *state.encryption_key.lock().unwrap() = None;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// An unlocked vault in a new file; the directory must outlive the state
+    async fn unlocked_state(dir: &Path) -> AppState {
+        let db = Database::new(dir.join("test.db").to_str().unwrap()).await.unwrap();
+        db.set_master_password("test_master_password", &KdfParams::default(), false).await.unwrap();
+        let key = db.verify_master_password("test_master_password").await.unwrap();
+
+        let state = AppState::new(db);
+        state.store_key(key).await;
+        state
+    }
+
+    fn is_unlocked(state: &AppState) -> bool {
+        state.encryption_key.lock().unwrap().is_some()
+    }
+
+    #[tokio::test]
+    async fn test_lock_after_idle_limit() {
+        let temp_dir = tempdir().unwrap();
+        let state = unlocked_state(temp_dir.path()).await;
+        let idle_limit = Duration::from_secs(15 * 60);
+
+        let start = Instant::now();
+        *state.last_activity.lock().unwrap() = start;
+        assert!(!state.lock_if_idle_at(start + idle_limit - Duration::from_secs(1)));
+        assert!(is_unlocked(&state));
+
+        assert!(state.lock_if_idle_at(start + idle_limit));
+        assert!(!is_unlocked(&state));
+        assert!(state.vault_key().is_err());
+
+        // Only the call that wiped the key reports it
+        assert!(!state.lock_if_idle_at(start + idle_limit * 2));
+    }
+
+    #[tokio::test]
+    async fn test_vault_key_resets_idle_timer() {
+        let temp_dir = tempdir().unwrap();
+        let state = unlocked_state(temp_dir.path()).await;
+        let idle_limit = Duration::from_secs(15 * 60);
+
+        let start = Instant::now();
+        *state.last_activity.lock().unwrap() = start;
+        state.vault_key().unwrap();
+        assert!(*state.last_activity.lock().unwrap() >= start);
+
+        // The limit now counts from the command, not from `start`
+        assert!(!state.lock_if_idle_at(start + idle_limit));
+        assert!(is_unlocked(&state));
+    }
+
+    #[tokio::test]
+    async fn test_auto_lock_disabled() {
+        let temp_dir = tempdir().unwrap();
+        let state = unlocked_state(temp_dir.path()).await;
+        let key = state.vault_key().unwrap();
+        let settings = AutoLockSettings { enabled: false, idle_minutes: 5 };
+        state.db.set_auto_lock_settings(&settings, &key).await.unwrap();
+
+        // Saved settings take effect at the next unlock
+        state.store_key(key).await;
+        assert_eq!(*state.auto_lock.lock().unwrap(), settings);
+
+        let start = Instant::now();
+        *state.last_activity.lock().unwrap() = start;
+        assert!(!state.lock_if_idle_at(start + Duration::from_secs(24 * 60 * 60)));
+        assert!(is_unlocked(&state));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_auto_lock_settings_use_defaults() {
+        let temp_dir = tempdir().unwrap();
+        let state = unlocked_state(temp_dir.path()).await;
+        let key = state.vault_key().unwrap();
+
+        // A plaintext row, as written by hand, is not trusted
+        let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", temp_dir.path().join("test.db").display()))
+            .await
+            .unwrap();
+        sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES ('auto_lock', ?)")
+            .bind(r#"{"enabled":false,"idle_minutes":5}"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        state.store_key(key).await;
+
+        assert_eq!(*state.auto_lock.lock().unwrap(), AutoLockSettings::default());
+    }
+
+    #[test]
+    fn test_saved_entry_flags_weak_password() {
+        let estimate = entry_password_strength(ItemType::Login, "password1", "Bank", "alice", None);
+        let saved = SavedEntry::new("bank".to_string(), estimate);
+        assert!(saved.strength.unwrap().score <= strength::WEAK_SCORE);
+        assert_eq!(saved.warning, Some("weak_password"));
+
+        let estimate = entry_password_strength(ItemType::Wifi, "Xk9#mQ2$vL7!pR4&", "Home", "", None);
+        let saved = SavedEntry::new("home".to_string(), estimate);
+        assert!(saved.strength.is_some());
+        assert_eq!(saved.warning, None);
+
+        // The entry's own details make a password easier to guess
+        let estimate = entry_password_strength(ItemType::Login, "examplebank", "Bank", "alice", Some("https://examplebank.com")).unwrap();
+        assert!(estimate.score <= strength::WEAK_SCORE);
+    }
+
+    #[test]
+    fn test_saved_entry_without_password() {
+        assert!(entry_password_strength(ItemType::SecureNote, "password1", "Note", "", None).is_none());
+        assert!(entry_password_strength(ItemType::Login, "", "Bank", "alice", None).is_none());
+        assert_eq!(SavedEntry::new("note".to_string(), None).warning, None);
+    }
+}
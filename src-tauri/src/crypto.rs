@@ -1,5 +1,5 @@
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Key, Nonce,
 };
 use argon2::{
@@ -10,12 +10,20 @@ use argon2::{
         PasswordVerifier, 
         SaltString
     },
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 use base64::{engine::general_purpose, Engine as _};
-use rand::RngCore;
+use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Once;
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
 
 #[derive(Error, Debug)]
 pub enum CryptoError {
@@ -27,21 +35,167 @@ pub enum CryptoError {
     HashingError(String),
     #[error("Base64 decode error: {0}")]
     Base64Error(#[from] base64::DecodeError),
+    #[error("Key file error: {0}")]
+    KeyFileError(String),
+}
+
+/// Current envelope format: records cipher and key id, and binds a context as AAD
+pub const ENVELOPE_VERSION: u32 = 2;
+/// Envelopes written before versioning: bare `{ciphertext, nonce}` without AAD
+pub const LEGACY_ENVELOPE_VERSION: u32 = 1;
+/// Cipher recorded in every current envelope
+pub const CIPHER_AES_256_GCM: &str = "AES-256-GCM";
+
+/// Length of an AES-GCM nonce
+const NONCE_LEN: usize = 12;
+
+fn legacy_envelope_version() -> u32 {
+    LEGACY_ENVELOPE_VERSION
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedData {
+    #[serde(rename = "v", default = "legacy_envelope_version")]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
     pub ciphertext: String,
     pub nonce: String,
 }
 
+impl EncryptedData {
+    /// Whether this envelope predates versioning and must be re-encrypted
+    pub fn is_legacy(&self) -> bool {
+        self.version < ENVELOPE_VERSION
+    }
+}
+
+/// Argon2 cost parameters stored alongside each vault's salt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// Matches `Argon2::default()`, which vaults used before parameters were stored
+    fn default() -> Self {
+        KdfParams {
+            algorithm: Algorithm::Argon2id.as_str().to_string(),
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Memory cost used when calibrating, in KiB (64 MiB)
+    pub const CALIBRATION_MEMORY_KIB: u32 = 64 * 1024;
+    /// Upper bound on iterations picked by calibration
+    pub const MAX_ITERATIONS: u32 = 64;
+
+    /// Build an Argon2 instance for these parameters
+    fn argon2(&self) -> Result<Argon2<'static>, CryptoError> {
+        let algorithm = Algorithm::new(&self.algorithm)
+            .map_err(|e| CryptoError::HashingError(e.to_string()))?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| CryptoError::HashingError(e.to_string()))?;
+        
+        Ok(Argon2::new(algorithm, Version::V0x13, params))
+    }
+}
+
+/// A 256-bit key that is wiped from memory when dropped
+///
+/// The bytes live on the heap so moving the key never leaves a copy behind,
+/// and the page holding them is locked with `mlock` where the platform allows.
+pub struct SecretKey(Box<[u8; 32]>);
+
+impl SecretKey {
+    /// Allocate a zeroed key and lock its page in memory
+    fn zeroed() -> Self {
+        let key = SecretKey(Box::new([0u8; 32]));
+        lock_memory(key.0.as_ptr(), key.0.len());
+        key
+    }
+
+    /// Copy key bytes into a new locked allocation
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let mut key = Self::zeroed();
+        key.0.copy_from_slice(bytes);
+        key
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Clone for SecretKey {
+    fn clone(&self) -> Self {
+        Self::from_bytes(&self.0)
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey([REDACTED])")
+    }
+}
+
+/// Keep the page holding `ptr` out of swap
+///
+/// A failure (e.g. `RLIMIT_MEMLOCK`) does not stop the key from being used;
+/// it is logged the first time only, since every key would hit it.
+/// Pages are not unlocked on drop: `mlock` is not reference counted, so
+/// unlocking one key could expose another key sharing the same page.
+#[cfg(unix)]
+fn lock_memory(ptr: *const u8, len: usize) {
+    static WARN_ONCE: Once = Once::new();
+
+    let result = unsafe { libc::mlock(ptr as *const libc::c_void, len) };
+    if result != 0 {
+        let error = io::Error::last_os_error();
+        WARN_ONCE.call_once(|| eprintln!("Failed to lock key memory, keys may be swapped to disk: {}", error));
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_memory(_ptr: *const u8, _len: usize) {}
+
+/// SHA-256 of a key file's contents, the second unlock factor
+pub struct KeyFile(Zeroizing<[u8; 32]>);
+
+impl fmt::Debug for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyFile([REDACTED])")
+    }
+}
+
+/// Random bytes in a recovery code
+const RECOVERY_CODE_BYTES: usize = 16;
+
+/// AAD context for the wrapped vault key
+const VAULT_KEY_AAD: &[u8] = b"vault-key";
+
 pub struct CryptoService;
 
 impl CryptoService {
     /// Hash a password using Argon2
-    pub fn hash_password(password: &str) -> Result<String, CryptoError> {
+    pub fn hash_password(password: &str, params: &KdfParams) -> Result<String, CryptoError> {
         let salt = SaltString::generate(&mut ArgonOsRng);
-        let argon2 = Argon2::default();
+        let argon2 = params.argon2()?;
         
         argon2
             .hash_password(password.as_bytes(), &salt)
@@ -50,6 +204,8 @@ impl CryptoService {
     }
 
     /// Verify a password against its hash
+    ///
+    /// The hash string carries its own Argon2 parameters.
     pub fn verify_password(password: &str, hash: &str) -> Result<bool, CryptoError> {
         let parsed_hash = PasswordHash::new(hash)
             .map_err(|e| CryptoError::HashingError(e.to_string()))?;
@@ -59,20 +215,58 @@ impl CryptoService {
     }
 
     /// Derive an encryption key from a password using Argon2
-    pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<[u8; 32], CryptoError> {
+    pub fn derive_key_from_password(
+        password: &str,
+        salt: &[u8],
+        params: &KdfParams,
+    ) -> Result<SecretKey, CryptoError> {
         let salt_string = SaltString::encode_b64(salt)
             .map_err(|e| CryptoError::HashingError(e.to_string()))?;
         
-        let argon2 = Argon2::default();
-        let mut key = [0u8; 32];
+        let argon2 = params.argon2()?;
+        let mut key = SecretKey::zeroed();
         
         argon2
-            .hash_password_into(password.as_bytes(), salt_string.as_salt().as_str().as_bytes(), &mut key)
+            .hash_password_into(password.as_bytes(), salt_string.as_salt().as_str().as_bytes(), &mut key.0[..])
             .map_err(|e| CryptoError::HashingError(e.to_string()))?;
         
         Ok(key)
     }
 
+    /// Pick Argon2 parameters that take roughly `target` to derive a key on this machine
+    ///
+    /// Memory is fixed at `KdfParams::CALIBRATION_MEMORY_KIB` and iterations are
+    /// raised until a derivation takes at least `target`.
+    pub fn calibrate_kdf(target: Duration) -> Result<KdfParams, CryptoError> {
+        Self::calibrate_kdf_from(
+            KdfParams {
+                memory_kib: KdfParams::CALIBRATION_MEMORY_KIB,
+                ..KdfParams::default()
+            },
+            target,
+        )
+    }
+
+    fn calibrate_kdf_from(start: KdfParams, target: Duration) -> Result<KdfParams, CryptoError> {
+        let salt = Self::generate_salt();
+        let mut params = start;
+
+        loop {
+            let started = Instant::now();
+            Self::derive_key_from_password("calibration", &salt, &params)?;
+            let elapsed = started.elapsed();
+
+            if elapsed >= target || params.iterations >= KdfParams::MAX_ITERATIONS {
+                return Ok(params);
+            }
+
+            // Scale iterations by the remaining ratio; cost grows linearly with them
+            let ratio = target.as_secs_f64() / elapsed.as_secs_f64().max(0.001);
+            let next = (params.iterations as f64 * ratio).ceil() as u32;
+            params.iterations = next.clamp(params.iterations + 1, KdfParams::MAX_ITERATIONS);
+        }
+    }
+
     /// Generate a random salt
     pub fn generate_salt() -> [u8; 16] {
         let mut salt = [0u8; 16];
@@ -80,42 +274,290 @@ impl CryptoService {
         salt
     }
 
-    /// Encrypt data using AES-256-GCM
-    pub fn encrypt(data: &str, key: &[u8; 32]) -> Result<EncryptedData, CryptoError> {
-        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    /// Generate a random 256-bit vault key
+    pub fn generate_vault_key() -> SecretKey {
+        let mut key = SecretKey::zeroed();
+        OsRng.fill_bytes(&mut key.0[..]);
+        key
+    }
+
+    /// Wrap a vault key with a key-encryption key using AES-256-GCM
+    pub fn wrap_key(key: &SecretKey, kek: &SecretKey) -> Result<EncryptedData, CryptoError> {
+        Self::encrypt_bytes(key.as_bytes(), kek, VAULT_KEY_AAD)
+    }
+
+    /// Unwrap a vault key previously wrapped with `wrap_key`
+    ///
+    /// Keys wrapped before envelopes were versioned are still accepted so the
+    /// caller can re-wrap them.
+    pub fn unwrap_key(wrapped: &EncryptedData, kek: &SecretKey) -> Result<SecretKey, CryptoError> {
+        let bytes = if wrapped.is_legacy() {
+            Self::decrypt_legacy_bytes(wrapped, kek)?
+        } else {
+            Self::decrypt_bytes(wrapped, kek, VAULT_KEY_AAD)?
+        };
         
-        let ciphertext = cipher
-            .encrypt(&nonce, data.as_bytes())
-            .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+        let bytes: &[u8; 32] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| CryptoError::DecryptionError("Invalid wrapped key length".to_string()))?;
+        
+        Ok(SecretKey::from_bytes(bytes))
+    }
+
+    /// Generate a recovery code: 128 random bits as eight dash-separated
+    /// groups of four hex digits
+    pub fn generate_recovery_code() -> Zeroizing<String> {
+        let mut bytes = Zeroizing::new([0u8; RECOVERY_CODE_BYTES]);
+        OsRng.fill_bytes(&mut bytes[..]);
+
+        // Sized up front so the string never reallocates and leaves copies behind
+        let mut code = Zeroizing::new(String::with_capacity(RECOVERY_CODE_BYTES * 2 + 7));
+        for (i, pair) in bytes.chunks(2).enumerate() {
+            if i > 0 {
+                code.push('-');
+            }
+            let _ = write!(code, "{:02X}{:02X}", pair[0], pair[1]);
+        }
+
+        code
+    }
+
+    /// Derive the key-encryption key for a recovery code
+    ///
+    /// The code already carries 128 bits of entropy, so a plain hash is
+    /// enough; no password-style stretching is needed. Dashes, whitespace and
+    /// case are ignored. Returns `None` if the code is malformed.
+    pub fn derive_recovery_key(code: &str) -> Option<SecretKey> {
+        let digits: Zeroizing<String> = Zeroizing::new(
+            code.chars()
+                .filter(|c| *c != '-' && !c.is_whitespace())
+                .map(|c| c.to_ascii_uppercase())
+                .collect(),
+        );
+        if digits.len() != RECOVERY_CODE_BYTES * 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let digest = Sha256::new()
+            .chain_update(b"passvault-recovery-key")
+            .chain_update(digits.as_bytes())
+            .finalize();
+
+        let mut key = SecretKey::zeroed();
+        key.0.copy_from_slice(&digest);
+        Some(key)
+    }
+
+    /// Hash a key file; any file works, and its whole contents count
+    pub fn load_key_file(path: &Path) -> Result<KeyFile, CryptoError> {
+        let file = File::open(path)
+            .map_err(|e| CryptoError::KeyFileError(format!("{}: {}", path.display(), e)))?;
+
+        Self::hash_key_file(file)
+    }
+
+    /// Hash key file contents from any reader
+    pub fn hash_key_file(mut reader: impl Read) -> Result<KeyFile, CryptoError> {
+        let mut hasher = Sha256::new();
+        let size = io::copy(&mut reader, &mut hasher)
+            .map_err(|e| CryptoError::KeyFileError(e.to_string()))?;
+        if size == 0 {
+            return Err(CryptoError::KeyFileError("Key file is empty".to_string()));
+        }
+
+        let mut digest = Zeroizing::new([0u8; 32]);
+        digest.copy_from_slice(&hasher.finalize());
+        Ok(KeyFile(digest))
+    }
+
+    /// Combine the master password with an optional key file
+    ///
+    /// The result stands in for the password everywhere it is hashed or
+    /// stretched, so neither factor alone verifies or unwraps anything.
+    /// Without a key file the password is used unchanged, which keeps
+    /// existing vaults valid.
+    pub fn composite_password(password: &str, key_file: Option<&KeyFile>) -> Zeroizing<String> {
+        let Some(key_file) = key_file else {
+            return Zeroizing::new(password.to_string());
+        };
+
+        let digest = Sha256::new()
+            .chain_update(b"passvault-composite-key")
+            .chain_update(Sha256::digest(password.as_bytes()))
+            .chain_update(key_file.0.as_slice())
+            .finalize();
+
+        let mut composite = Zeroizing::new(String::with_capacity(digest.len() * 2));
+        for b in digest.iter() {
+            let _ = write!(composite, "{:02x}", b);
+        }
+
+        composite
+    }
+
+    /// Identify a key without revealing it: truncated SHA-256 over a domain tag and the key
+    pub fn key_id(key: &SecretKey) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"passvault-key-id")
+            .chain_update(key.as_bytes())
+            .finalize();
+        
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Encrypt data using AES-256-GCM, binding `aad` to the ciphertext
+    pub fn encrypt(data: &str, key: &SecretKey, aad: &[u8]) -> Result<EncryptedData, CryptoError> {
+        Self::encrypt_bytes(data.as_bytes(), key, aad)
+    }
+
+    /// Decrypt data using AES-256-GCM; `aad` must match the value used to encrypt
+    pub fn decrypt(
+        encrypted_data: &EncryptedData,
+        key: &SecretKey,
+        aad: &[u8],
+    ) -> Result<Zeroizing<String>, CryptoError> {
+        let plaintext = Self::decrypt_bytes(encrypted_data, key, aad)?;
+        Self::bytes_to_string(plaintext)
+    }
+
+    /// Decrypt an envelope written before versioning, which carries no AAD
+    ///
+    /// Only migration code should call this: accepting legacy envelopes on
+    /// normal reads would let an attacker strip the AAD binding.
+    pub fn decrypt_legacy(encrypted_data: &EncryptedData, key: &SecretKey) -> Result<Zeroizing<String>, CryptoError> {
+        let plaintext = Self::decrypt_legacy_bytes(encrypted_data, key)?;
+        Self::bytes_to_string(plaintext)
+    }
+
+    fn bytes_to_string(mut plaintext: Zeroizing<Vec<u8>>) -> Result<Zeroizing<String>, CryptoError> {
+        match String::from_utf8(std::mem::take(&mut *plaintext)) {
+            Ok(text) => Ok(Zeroizing::new(text)),
+            Err(e) => {
+                let message = format!("Invalid UTF-8: {}", e.utf8_error());
+                e.into_bytes().zeroize();
+                Err(CryptoError::DecryptionError(message))
+            }
+        }
+    }
+
+    /// Full AAD for a current envelope: the header fields plus the caller's context
+    fn envelope_aad(key_id: &str, aad: &[u8]) -> Vec<u8> {
+        let mut full = format!("passvault:v{}:{}:{}:", ENVELOPE_VERSION, CIPHER_AES_256_GCM, key_id).into_bytes();
+        full.extend_from_slice(aad);
+        full
+    }
+
+    /// Encrypt binary data into a JSON envelope
+    pub fn encrypt_bytes(data: &[u8], key: &SecretKey, aad: &[u8]) -> Result<EncryptedData, CryptoError> {
+        let key_id = Self::key_id(key);
+        let (nonce, ciphertext) = Self::seal(data, key, &Self::envelope_aad(&key_id, aad))?;
 
         Ok(EncryptedData {
+            version: ENVELOPE_VERSION,
+            cipher: Some(CIPHER_AES_256_GCM.to_string()),
+            key_id: Some(key_id),
             ciphertext: general_purpose::STANDARD.encode(&ciphertext),
-            nonce: general_purpose::STANDARD.encode(&nonce),
+            nonce: general_purpose::STANDARD.encode(nonce),
         })
     }
 
-    /// Decrypt data using AES-256-GCM
-    pub fn decrypt(encrypted_data: &EncryptedData, key: &[u8; 32]) -> Result<String, CryptoError> {
-        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
-        
+    /// Decrypt binary data; `aad` must match the value used to encrypt
+    pub fn decrypt_bytes(
+        encrypted_data: &EncryptedData,
+        key: &SecretKey,
+        aad: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        if encrypted_data.version != ENVELOPE_VERSION {
+            return Err(CryptoError::DecryptionError(format!(
+                "Unsupported envelope version {}",
+                encrypted_data.version
+            )));
+        }
+        if encrypted_data.cipher.as_deref() != Some(CIPHER_AES_256_GCM) {
+            return Err(CryptoError::DecryptionError("Unsupported cipher".to_string()));
+        }
+
+        let key_id = Self::key_id(key);
+        if encrypted_data.key_id.as_deref() != Some(key_id.as_str()) {
+            return Err(CryptoError::DecryptionError("Data was encrypted with a different key".to_string()));
+        }
+
+        let full_aad = Self::envelope_aad(&key_id, aad);
+        Self::open(encrypted_data, key, &full_aad)
+    }
+
+    /// Encrypt binary data, such as a chunk of an attachment, into a compact
+    /// envelope: the version byte, the nonce, then the ciphertext
+    ///
+    /// Unlike `encrypt_bytes` nothing is base64 encoded. The cipher and key
+    /// id are not stored but are still bound through the AAD, so the blob
+    /// only opens under the same key.
+    pub fn encrypt_blob(data: &[u8], key: &SecretKey, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (nonce, ciphertext) = Self::seal(data, key, &Self::envelope_aad(&Self::key_id(key), aad))?;
+
+        let mut blob = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        blob.push(ENVELOPE_VERSION as u8);
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+        Ok(blob)
+    }
+
+    /// Decrypt a blob written by `encrypt_blob`; `aad` must match the value
+    /// used to encrypt
+    pub fn decrypt_blob(blob: &[u8], key: &SecretKey, aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let Some((&version, rest)) = blob.split_first() else {
+            return Err(CryptoError::DecryptionError("Empty envelope".to_string()));
+        };
+        if u32::from(version) != ENVELOPE_VERSION {
+            return Err(CryptoError::DecryptionError(format!("Unsupported envelope version {}", version)));
+        }
+        if rest.len() < NONCE_LEN {
+            return Err(CryptoError::DecryptionError("Invalid nonce length".to_string()));
+        }
+
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        Self::open_raw(nonce, ciphertext, key, &Self::envelope_aad(&Self::key_id(key), aad))
+    }
+
+    /// Encrypt under a fresh nonce, returning the nonce and the ciphertext
+    fn seal(data: &[u8], key: &SecretKey, aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>), CryptoError> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_bytes()));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: data, aad })
+            .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+        Ok((nonce.to_vec(), ciphertext))
+    }
+
+    fn decrypt_legacy_bytes(encrypted_data: &EncryptedData, key: &SecretKey) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        if !encrypted_data.is_legacy() {
+            return Err(CryptoError::DecryptionError("Envelope is not a legacy envelope".to_string()));
+        }
+
+        Self::open(encrypted_data, key, &[])
+    }
+
+    fn open(encrypted_data: &EncryptedData, key: &SecretKey, aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
         let ciphertext = general_purpose::STANDARD
             .decode(&encrypted_data.ciphertext)?;
         let nonce_bytes = general_purpose::STANDARD
             .decode(&encrypted_data.nonce)?;
-        
-        if nonce_bytes.len() != 12 {
+
+        Self::open_raw(&nonce_bytes, &ciphertext, key, aad)
+    }
+
+    fn open_raw(nonce: &[u8], ciphertext: &[u8], key: &SecretKey, aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        if nonce.len() != NONCE_LEN {
             return Err(CryptoError::DecryptionError("Invalid nonce length".to_string()));
         }
-        
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext.as_ref())
-            .map_err(|e| CryptoError::DecryptionError(e.to_string()))?;
 
-        String::from_utf8(plaintext)
-            .map_err(|e| CryptoError::DecryptionError(format!("Invalid UTF-8: {}", e)))
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_bytes()));
+        cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map(Zeroizing::new)
+            .map_err(|e| CryptoError::DecryptionError(e.to_string()))
     }
 
     /// Generate a secure random password
@@ -134,12 +576,14 @@ impl CryptoService {
             charset.push_str(symbols);
         }
         
+        // gen_range samples uniformly; a plain modulo would favour the first
+        // 2^32 % len characters of the charset
         let charset_bytes = charset.as_bytes();
-        let mut password = String::new();
+        let mut password = String::with_capacity(length);
         let mut rng = OsRng;
         
         for _ in 0..length {
-            let idx = (rng.next_u32() as usize) % charset_bytes.len();
+            let idx = rng.gen_range(0..charset_bytes.len());
             password.push(charset_bytes[idx] as char);
         }
         
@@ -154,7 +598,7 @@ mod tests {
     #[test]
     fn test_password_hashing() {
         let password = "test_password_123";
-        let hash = CryptoService::hash_password(password).unwrap();
+        let hash = CryptoService::hash_password(password, &KdfParams::default()).unwrap();
         
         assert!(CryptoService::verify_password(password, &hash).unwrap());
         assert!(!CryptoService::verify_password("wrong_password", &hash).unwrap());
@@ -163,12 +607,101 @@ mod tests {
     #[test]
     fn test_encryption_decryption() {
         let data = "sensitive_password_data";
-        let key = [1u8; 32]; // Test key
+        let key = SecretKey::from_bytes(&[1u8; 32]); // Test key
+        
+        let encrypted = CryptoService::encrypt(data, &key, b"entry-1:password").unwrap();
+        let decrypted = CryptoService::decrypt(&encrypted, &key, b"entry-1:password").unwrap();
         
-        let encrypted = CryptoService::encrypt(data, &key).unwrap();
-        let decrypted = CryptoService::decrypt(&encrypted, &key).unwrap();
+        assert_eq!(data, decrypted.as_str());
+        assert_eq!(encrypted.version, ENVELOPE_VERSION);
+        assert_eq!(encrypted.key_id, Some(CryptoService::key_id(&key)));
+    }
+
+    #[test]
+    fn test_aad_binding() {
+        let key = SecretKey::from_bytes(&[1u8; 32]);
+        let encrypted = CryptoService::encrypt("secret", &key, b"entry-1:password").unwrap();
         
-        assert_eq!(data, decrypted);
+        assert!(CryptoService::decrypt(&encrypted, &key, b"entry-2:password").is_err());
+        assert!(CryptoService::decrypt(&encrypted, &key, b"entry-1:username").is_err());
+        assert!(CryptoService::decrypt(&encrypted, &SecretKey::from_bytes(&[2u8; 32]), b"entry-1:password").is_err());
+
+        // Legacy decryption must not accept current envelopes
+        assert!(CryptoService::decrypt_legacy(&encrypted, &key).is_err());
+    }
+
+    #[test]
+    fn test_blob_envelope() {
+        let key = SecretKey::from_bytes(&[1u8; 32]);
+        let data = [7u8; 1000];
+        let blob = CryptoService::encrypt_blob(&data, &key, b"chunk-0").unwrap();
+
+        // Version byte, nonce and GCM tag are the only overhead
+        assert_eq!(blob.len(), 1 + NONCE_LEN + data.len() + 16);
+        assert_eq!(blob[0] as u32, ENVELOPE_VERSION);
+        assert_eq!(&CryptoService::decrypt_blob(&blob, &key, b"chunk-0").unwrap()[..], &data[..]);
+
+        assert!(CryptoService::decrypt_blob(&blob, &key, b"chunk-1").is_err());
+        assert!(CryptoService::decrypt_blob(&blob, &SecretKey::from_bytes(&[2u8; 32]), b"chunk-0").is_err());
+        assert!(CryptoService::decrypt_blob(&blob[..NONCE_LEN], &key, b"chunk-0").is_err());
+        assert!(CryptoService::decrypt_blob(&[], &key, b"chunk-0").is_err());
+
+        let mut other_version = blob.clone();
+        other_version[0] = LEGACY_ENVELOPE_VERSION as u8;
+        assert!(CryptoService::decrypt_blob(&other_version, &key, b"chunk-0").is_err());
+    }
+
+    #[test]
+    fn test_legacy_envelope() {
+        let legacy: EncryptedData =
+            serde_json::from_str(r#"{"ciphertext":"AAAA","nonce":"AAAAAAAAAAAAAAAA"}"#).unwrap();
+        
+        assert_eq!(legacy.version, LEGACY_ENVELOPE_VERSION);
+        assert!(legacy.is_legacy());
+        assert!(CryptoService::decrypt(&legacy, &SecretKey::from_bytes(&[1u8; 32]), b"").is_err());
+    }
+
+    #[test]
+    fn test_key_wrapping() {
+        let vault_key = CryptoService::generate_vault_key();
+        let kek = SecretKey::from_bytes(&[2u8; 32]);
+        
+        let wrapped = CryptoService::wrap_key(&vault_key, &kek).unwrap();
+        assert_eq!(CryptoService::unwrap_key(&wrapped, &kek).unwrap().as_bytes(), vault_key.as_bytes());
+        assert!(CryptoService::unwrap_key(&wrapped, &SecretKey::from_bytes(&[3u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_recovery_code() {
+        let code = CryptoService::generate_recovery_code();
+        assert_eq!(code.len(), 39);
+        assert_eq!(code.split('-').count(), 8);
+        assert_ne!(*code, *CryptoService::generate_recovery_code());
+
+        let key = CryptoService::derive_recovery_key(&code).unwrap();
+
+        // Typed back in lowercase or without dashes, the code gives the same key
+        let retyped = code.replace('-', " ").to_lowercase();
+        assert_eq!(CryptoService::derive_recovery_key(&retyped).unwrap().as_bytes(), key.as_bytes());
+
+        assert!(CryptoService::derive_recovery_key("not-a-code").is_none());
+        assert!(CryptoService::derive_recovery_key(&code[..34]).is_none());
+    }
+
+    #[test]
+    fn test_key_file_composite() {
+        let key_file = CryptoService::hash_key_file(&b"contents of a key file"[..]).unwrap();
+        let other_file = CryptoService::hash_key_file(&b"contents of another file"[..]).unwrap();
+
+        assert_eq!(*CryptoService::composite_password("password", None), "password");
+
+        let composite = CryptoService::composite_password("password", Some(&key_file));
+        assert_ne!(*composite, "password");
+        assert_eq!(*composite, *CryptoService::composite_password("password", Some(&key_file)));
+        assert_ne!(*composite, *CryptoService::composite_password("password", Some(&other_file)));
+        assert_ne!(*composite, *CryptoService::composite_password("other", Some(&key_file)));
+
+        assert!(CryptoService::hash_key_file(&b""[..]).is_err());
     }
 
     #[test]
@@ -176,10 +709,35 @@ mod tests {
         let password = "master_password";
         let salt = CryptoService::generate_salt();
         
-        let key1 = CryptoService::derive_key_from_password(password, &salt).unwrap();
-        let key2 = CryptoService::derive_key_from_password(password, &salt).unwrap();
+        let key1 = CryptoService::derive_key_from_password(password, &salt, &KdfParams::default()).unwrap();
+        let key2 = CryptoService::derive_key_from_password(password, &salt, &KdfParams::default()).unwrap();
+        
+        assert_eq!(key1.as_bytes(), key2.as_bytes());
+
+        let stronger = KdfParams { iterations: 3, ..KdfParams::default() };
+        let key3 = CryptoService::derive_key_from_password(password, &salt, &stronger).unwrap();
         
-        assert_eq!(key1, key2);
+        assert_ne!(key1.as_bytes(), key3.as_bytes());
+    }
+
+    #[test]
+    fn test_kdf_calibration() {
+        let start = KdfParams { memory_kib: 256, iterations: 1, ..KdfParams::default() };
+        
+        let params = CryptoService::calibrate_kdf_from(start.clone(), Duration::ZERO).unwrap();
+        assert_eq!(params, start);
+
+        let params = CryptoService::calibrate_kdf_from(start, Duration::from_secs(3600)).unwrap();
+        assert_eq!(params.iterations, KdfParams::MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_secret_key_redacts_debug() {
+        let key = SecretKey::from_bytes(&[7u8; 32]);
+        let copy = key.clone();
+        
+        assert_eq!(copy.as_bytes(), key.as_bytes());
+        assert_eq!(format!("{:?}", key), "SecretKey([REDACTED])");
     }
 
     #[test]
@@ -190,4 +748,24 @@ mod tests {
         let password_no_symbols = CryptoService::generate_password(12, false);
         assert_eq!(password_no_symbols.len(), 12);
     }
+
+    #[test]
+    fn test_password_generation_is_uniform() {
+        // 88 characters with symbols, ~1000 draws each
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..100 {
+            for c in CryptoService::generate_password(880, true).chars() {
+                *counts.entry(c).or_insert(0u32) += 1;
+            }
+        }
+        assert_eq!(counts.len(), 88);
+
+        let expected = 88_000.0 / 88.0;
+        let chi_square: f64 = counts
+            .values()
+            .map(|&observed| (observed as f64 - expected).powi(2) / expected)
+            .sum();
+        // 87 degrees of freedom; 150 is far beyond the 99.99th percentile
+        assert!(chi_square < 150.0, "chi-square {chi_square} suggests a biased charset");
+    }
 }
@@ -1,11 +1,18 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, Row, SqlitePool as Pool};
-use std::path::Path;
+use sqlx::{sqlite::SqlitePool, Row, SqliteConnection, SqlitePool as Pool};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 use base64::Engine;
-use crate::crypto::{CryptoService, EncryptedData};
+use crate::crypto::{CryptoService, EncryptedData, KdfParams, SecretKey};
+use crate::items::{ItemData, ItemType};
+use crate::generator::PasswordPolicy;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
@@ -23,19 +30,60 @@ pub enum DatabaseError {
     EntryNotFound,
     #[error("Invalid master password")]
     InvalidMasterPassword,
+    #[error("Too many failed attempts, try again in {retry_after_secs} seconds")]
+    TooManyAttempts { retry_after_secs: u64 },
+    #[error("Vault wiped after too many failed attempts")]
+    VaultWiped,
+    #[error("Invalid recovery code")]
+    InvalidRecoveryCode,
+    #[error("Category not found")]
+    CategoryNotFound,
+    #[error("A category named \"{0}\" already exists")]
+    DuplicateCategory(String),
+    #[error("Vault file uses schema version {0}, which is newer than this version of PassVault supports")]
+    UnsupportedSchemaVersion(i64),
+    #[error("Attachment not found")]
+    AttachmentNotFound,
+    #[error("Attachments can be at most {} MiB", max_bytes / (1024 * 1024))]
+    AttachmentTooLarge { max_bytes: u64 },
+    #[error("Attachment is incomplete or damaged")]
+    CorruptAttachment,
+    #[error("File error: {0}")]
+    FileError(#[from] std::io::Error),
+    #[error("Background task failed: {0}")]
+    BackgroundTaskFailed(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A decrypted entry; its plaintext fields are wiped when it is dropped
+///
+/// Logins use the built-in fields alone; other item types carry their own
+/// fields in `item_data`, which is stored encrypted like the rest, so the
+/// kind of item is not visible in the file either.
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct PasswordEntry {
     pub id: String,
+    #[zeroize(skip)]
+    pub item_type: ItemType,
+    pub item_data: Option<ItemData>,
     pub title: String,
     pub username: String,
     pub password: String,
     pub url: Option<String>,
     pub notes: Option<String>,
+    pub category_id: Option<String>,
+    /// Name of the referenced category, resolved when the entry is read
     pub category: Option<String>,
+    /// `otpauth://` URI or base32 TOTP secret
+    pub totp: Option<String>,
+    pub is_favorite: bool,
+    pub custom_fields: Vec<CustomField>,
+    #[zeroize(skip)]
     pub created_at: DateTime<Utc>,
+    #[zeroize(skip)]
     pub updated_at: DateTime<Utc>,
+    /// When the entry was moved to the trash
+    #[zeroize(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,24 +99,118 @@ pub struct EncryptedPasswordEntry {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct CreatePasswordEntry {
     pub title: String,
     pub username: String,
     pub password: String,
     pub url: Option<String>,
     pub notes: Option<String>,
-    pub category: Option<String>,
+    pub category_id: Option<String>,
+    pub totp: Option<String>,
+    #[serde(default)]
+    pub is_favorite: bool,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    /// Absent for logins
+    #[serde(default)]
+    pub item_data: Option<ItemData>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct UpdatePasswordEntry {
     pub title: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
     pub url: Option<String>,
     pub notes: Option<String>,
-    pub category: Option<String>,
+    /// An empty string removes the entry from its category
+    pub category_id: Option<String>,
+    pub totp: Option<String>,
+    pub is_favorite: Option<bool>,
+    /// Replaces the whole list when given
+    pub custom_fields: Option<Vec<CustomField>>,
+    /// `Some(None)`, sent as `null`, turns the item back into a login
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "double_option")]
+    pub item_data: Option<Option<ItemData>>,
+}
+
+/// Deserialize a field that may be left out, giving `None`, or set to
+/// `null`, giving `Some(None)`
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A named value beyond the built-in fields, such as a PIN or security answer
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    #[zeroize(skip)]
+    pub field_type: CustomFieldType,
+}
+
+/// How a custom field's value is shown and edited
+///
+/// Booleans are stored as `true` or `false` and dates as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    /// Masked until revealed, and never matched by search
+    Hidden,
+    Boolean,
+    Url,
+    Date,
+}
+
+impl CustomFieldType {
+    fn as_str(self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Hidden => "hidden",
+            CustomFieldType::Boolean => "boolean",
+            CustomFieldType::Url => "url",
+            CustomFieldType::Date => "date",
+        }
+    }
+}
+
+/// A custom field ready to be written by `insert_custom_fields`
+struct EncryptedCustomField {
+    id: String,
+    position: i64,
+    field_type: CustomFieldType,
+    name_encrypted: String,
+    value_encrypted: String,
+}
+
+/// A file kept with an entry; only its name is decrypted here, the
+/// contents are read back with `export_attachment`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub entry_id: String,
+    pub name: String,
+    /// In bytes
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A username and password an entry held before an edit replaced them
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct EntryHistory {
+    pub id: String,
+    pub entry_id: String,
+    pub username: String,
+    pub password: String,
+    /// When these values were replaced
+    #[zeroize(skip)]
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,11 +218,204 @@ pub struct MasterPassword {
     pub id: i32,
     pub password_hash: String,
     pub salt: String,
+    pub wrapped_key: Option<String>,
+    pub kdf_params: KdfParams,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A category entries refer to by id; only the name is encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCategory {
+    pub name: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCategory {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// What happens to a deleted category's entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum CategoryDeletion {
+    /// Move the entries to another category
+    Reassign { category_id: String },
+    /// Leave the entries uncategorized
+    Clear,
+}
+
+/// A generator policy saved under a name; both are stored encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPasswordPolicy {
+    pub id: String,
+    pub name: String,
+    pub policy: PasswordPolicy,
     pub created_at: DateTime<Utc>,
 }
 
+/// Idle timeout enforced by the backend; the vault key is wiped after
+/// `idle_minutes` without an authenticated command
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AutoLockSettings {
+    pub enabled: bool,
+    pub idle_minutes: u32,
+}
+
+impl Default for AutoLockSettings {
+    fn default() -> Self {
+        AutoLockSettings {
+            enabled: true,
+            idle_minutes: 15,
+        }
+    }
+}
+
+/// Brute-force policy for the master password; with `wipe_after_failures`
+/// set, the vault is erased once that many consecutive unlocks have failed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LockoutSettings {
+    pub wipe_after_failures: Option<u32>,
+}
+
+/// Thresholds for the vault health report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthSettings {
+    /// Passwords not changed for longer than this are reported as old
+    pub max_password_age_days: u32,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        HealthSettings {
+            max_password_age_days: 365,
+        }
+    }
+}
+
+/// How long deleted entries stay in the trash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashSettings {
+    /// Entries trashed longer ago than this are purged at the next unlock
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        TrashSettings { retention_days: 30 }
+    }
+}
+
+/// Failed unlock attempts allowed before the backoff starts
+const FREE_UNLOCK_ATTEMPTS: u32 = 3;
+
+/// First backoff delay, doubled with every further failure
+const BASE_LOCKOUT_SECS: u64 = 5;
+
+/// Longest wait imposed between two unlock attempts
+const MAX_LOCKOUT_SECS: u64 = 60 * 60;
+
+/// Password hash, salt and wrapped vault key derived for one master password
+struct KeyRecord {
+    password_hash: String,
+    salt: String,
+    wrapped_key: String,
+}
+
+impl KeyRecord {
+    fn new(password: &str, vault_key: &SecretKey, params: &KdfParams) -> Result<Self, DatabaseError> {
+        let salt = CryptoService::generate_salt();
+        let kek = CryptoService::derive_key_from_password(password, &salt, params)?;
+        let wrapped = CryptoService::wrap_key(vault_key, &kek)?;
+
+        Ok(KeyRecord {
+            password_hash: CryptoService::hash_password(password, params)?,
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            wrapped_key: serde_json::to_string(&wrapped)?,
+        })
+    }
+
+    /// `new` on a blocking thread, since it runs Argon2 twice
+    async fn derive(password: &str, vault_key: &SecretKey, params: &KdfParams) -> Result<Self, DatabaseError> {
+        let password = Zeroizing::new(password.to_string());
+        let vault_key = vault_key.clone();
+        let params = params.clone();
+        run_blocking(move || Self::new(&password, &vault_key, &params)).await
+    }
+}
+
+/// Run blocking work, such as deliberately slow key derivation or file
+/// reads, off the async runtime's workers
+async fn run_blocking<T, F>(task: F) -> Result<T, DatabaseError>
+where
+    F: FnOnce() -> Result<T, DatabaseError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| DatabaseError::BackgroundTaskFailed(e.to_string()))?
+}
+
+/// Largest attachment accepted, in bytes
+pub const MAX_ATTACHMENT_SIZE: u64 = 25 * 1024 * 1024;
+
+/// Attachments are encrypted this many bytes at a time, so a file is never
+/// held in memory whole
+const ATTACHMENT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Encrypted chunks waiting to be inserted while the next ones are read
+const ATTACHMENT_CHUNKS_IN_FLIGHT: usize = 4;
+
+/// Schema version this build writes, kept in SQLite's `user_version`
+const SCHEMA_VERSION: i64 = 17;
+
+/// Columns read into a `PasswordEntry`
+///
+/// Listed explicitly rather than `SELECT *`: a pooled connection that has not
+/// yet seen a migration's `DROP COLUMN` would otherwise report stale columns.
+/// The category name comes from `ENTRY_TABLES`' join.
+const ENTRY_COLUMNS: &str = "password_entries.id, title_encrypted, username_encrypted, password_encrypted, \
+    url_encrypted, notes_encrypted, category_id, categories.name_encrypted AS category_name_encrypted, \
+    totp_encrypted, item_data_encrypted, is_favorite, password_entries.created_at, password_entries.updated_at, \
+    deleted_at";
+
+/// Tables `ENTRY_COLUMNS` are selected from
+const ENTRY_TABLES: &str = "password_entries LEFT JOIN categories ON categories.id = password_entries.category_id";
+
+/// Encrypted columns of `password_entries`, named `<field>_encrypted`
+const ENCRYPTED_ENTRY_FIELDS: [&str; 7] = ["title", "username", "password", "url", "notes", "totp", "item_data"];
+
+/// Categories a new vault starts with: name, color and icon
+const DEFAULT_CATEGORIES: [(&str, &str, &str); 8] = [
+    ("Social Media", "#3b82f6", "👥"),
+    ("Banking", "#10b981", "🏦"),
+    ("Email", "#f59e0b", "📧"),
+    ("Work", "#8b5cf6", "💼"),
+    ("Shopping", "#ef4444", "🛒"),
+    ("Entertainment", "#ec4899", "🎬"),
+    ("Utilities", "#6b7280", "⚡"),
+    ("Other", "#64748b", "📁"),
+];
+
 pub struct Database {
     pool: Pool,
+    /// Location of the file, which migration backups are named after
+    path: PathBuf,
+    /// Serializes unlock attempts so concurrent guesses cannot all slip in
+    /// before the failure counter is updated
+    unlock_guard: tokio::sync::Mutex<()>,
 }
 
 impl Database {
@@ -99,52 +434,461 @@ impl Database {
         
         let pool = SqlitePool::connect(&database_url).await?;
         
-        let db = Database { pool };
-        db.create_tables().await?;
+        let db = Database {
+            pool,
+            path: PathBuf::from(database_path),
+            unlock_guard: tokio::sync::Mutex::new(()),
+        };
+        db.migrate_schema(database_path).await?;
         
         Ok(db)
     }
 
-    /// Create necessary database tables
-    async fn create_tables(&self) -> Result<(), DatabaseError> {
-        // Create master_passwords table
-        sqlx::query(
+    /// Bring the file up to `SCHEMA_VERSION`
+    ///
+    /// A new file gets the current schema directly. An existing one is first
+    /// copied next to itself, then upgraded one migration at a time, each in
+    /// a transaction with its version bump, so a failure leaves the file at
+    /// the last version that completed. Changes that need the vault key run
+    /// at unlock instead, and the copy is deleted once they have.
+    async fn migrate_schema(&self, database_path: &str) -> Result<(), DatabaseError> {
+        let version = self.schema_version().await?;
+        if version == SCHEMA_VERSION {
+            return Ok(());
+        }
+        if version > SCHEMA_VERSION {
+            return Err(DatabaseError::UnsupportedSchemaVersion(version));
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        let is_new = version == 0 && !Self::has_table(&mut conn, "master_passwords").await?;
+        drop(conn);
+
+        if is_new {
+            let mut tx = self.pool.begin().await?;
+            Self::create_schema(&mut tx).await?;
+            Self::set_schema_version(&mut tx, SCHEMA_VERSION).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        self.backup_before_migration(database_path, version).await?;
+
+        for target in version + 1..=SCHEMA_VERSION {
+            let mut tx = self.pool.begin().await?;
+            let migrated = match Self::apply_migration(&mut tx, target).await {
+                Ok(()) => Self::set_schema_version(&mut tx, target).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = migrated {
+                tx.rollback().await?;
+                return Err(e);
+            }
+            tx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn schema_version(&self) -> Result<i64, DatabaseError> {
+        let row = sqlx::query("PRAGMA user_version").fetch_one(&self.pool).await?;
+        Ok(row.get(0))
+    }
+
+    async fn set_schema_version(conn: &mut SqliteConnection, version: i64) -> Result<(), DatabaseError> {
+        // PRAGMA arguments cannot be bound
+        sqlx::query(&format!("PRAGMA user_version = {}", version))
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Copy the file to `<path>.v<version>-<timestamp>.bak`
+    ///
+    /// `VACUUM INTO` writes a consistent snapshot through SQLite rather than
+    /// copying bytes that may be mid-write.
+    async fn backup_before_migration(&self, database_path: &str, version: i64) -> Result<(), DatabaseError> {
+        let backup_path = format!(
+            "{}.v{}-{}.bak",
+            database_path,
+            version,
+            Utc::now().format("%Y%m%d%H%M%S%3f")
+        );
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(&backup_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete every copy `backup_before_migration` has made of this file
+    ///
+    /// A copy may hold values that have since been encrypted, deleted or
+    /// re-keyed, so none is kept once the vault no longer needs it.
+    async fn remove_migration_backups(&self) -> Result<(), DatabaseError> {
+        let Some(file_name) = self.path.file_name() else {
+            return Ok(());
+        };
+        let prefix = format!("{}.v", file_name.to_string_lossy());
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with(&prefix) && name.ends_with(".bak") {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create every table at the current schema
+    async fn create_schema(conn: &mut SqliteConnection) -> Result<(), DatabaseError> {
+        for statement in [
             r#"
-            CREATE TABLE IF NOT EXISTS master_passwords (
+            CREATE TABLE master_passwords (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 password_hash TEXT NOT NULL,
                 salt TEXT NOT NULL,
+                wrapped_key TEXT,
+                kdf_algorithm TEXT,
+                kdf_memory_kib INTEGER,
+                kdf_iterations INTEGER,
+                kdf_parallelism INTEGER,
+                failed_attempts INTEGER NOT NULL DEFAULT 0,
+                locked_until TEXT,
+                recovery_wrapped_key TEXT,
+                requires_key_file INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE categories (
+                id TEXT PRIMARY KEY,
+                name_encrypted TEXT NOT NULL,
+                color TEXT,
+                icon TEXT,
                 created_at TEXT NOT NULL
             )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create password_entries table
-        sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS password_entries (
+            CREATE TABLE password_entries (
                 id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
+                title_encrypted TEXT NOT NULL,
                 username_encrypted TEXT NOT NULL,
                 password_encrypted TEXT NOT NULL,
                 url_encrypted TEXT,
                 notes_encrypted TEXT,
-                category TEXT,
+                category_id TEXT REFERENCES categories(id),
+                totp_encrypted TEXT,
+                item_data_encrypted TEXT,
+                is_favorite INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT
             )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
+            r#"
+            CREATE TABLE entry_history (
+                id TEXT PRIMARY KEY,
+                entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                username_encrypted TEXT NOT NULL,
+                password_encrypted TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            "CREATE INDEX entry_history_entry_id ON entry_history (entry_id)",
+            r#"
+            CREATE TABLE entry_fields (
+                id TEXT PRIMARY KEY,
+                entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                field_type TEXT NOT NULL,
+                name_encrypted TEXT NOT NULL,
+                value_encrypted TEXT NOT NULL
+            )
+            "#,
+            "CREATE INDEX entry_fields_entry_id ON entry_fields (entry_id)",
+            r#"
+            CREATE TABLE attachments (
+                id TEXT PRIMARY KEY,
+                entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                name_encrypted TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                chunk_count INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            "CREATE INDEX attachments_entry_id ON attachments (entry_id)",
+            r#"
+            CREATE TABLE attachment_chunks (
+                attachment_id TEXT NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+                chunk_index INTEGER NOT NULL,
+                data_encrypted BLOB NOT NULL,
+                PRIMARY KEY (attachment_id, chunk_index)
+            )
+            "#,
+            r#"
+            CREATE TABLE password_policies (
+                id TEXT PRIMARY KEY,
+                name_encrypted TEXT NOT NULL,
+                policy_encrypted TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )
+            "#,
+        ] {
+            sqlx::query(statement).execute(&mut *conn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Upgrade the file from `version - 1` to `version`
+    ///
+    /// Files from before versioning report 0 whatever their layout, so each
+    /// step only adds what is missing and is safe to run on a file that
+    /// already has it.
+    async fn apply_migration(conn: &mut SqliteConnection, version: i64) -> Result<(), DatabaseError> {
+        match version {
+            // The original layout, with plaintext titles and categories
+            1 => {
+                for statement in [
+                    r#"
+                    CREATE TABLE IF NOT EXISTS master_passwords (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        password_hash TEXT NOT NULL,
+                        salt TEXT NOT NULL,
+                        created_at TEXT NOT NULL
+                    )
+                    "#,
+                    r#"
+                    CREATE TABLE IF NOT EXISTS password_entries (
+                        id TEXT PRIMARY KEY,
+                        title TEXT NOT NULL,
+                        username_encrypted TEXT NOT NULL,
+                        password_encrypted TEXT NOT NULL,
+                        url_encrypted TEXT,
+                        notes_encrypted TEXT,
+                        category TEXT,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    )
+                    "#,
+                ] {
+                    sqlx::query(statement).execute(&mut *conn).await?;
+                }
+            }
+            // Vaults created before the key hierarchy have no wrapped_key column
+            2 => Self::add_column_if_missing(conn, "master_passwords", "wrapped_key", "TEXT").await?,
+            // Vaults created before KDF parameters were stored used Argon2 defaults
+            3 => {
+                for column in ["kdf_algorithm TEXT", "kdf_memory_kib INTEGER", "kdf_iterations INTEGER", "kdf_parallelism INTEGER"] {
+                    let (column, definition) = column.split_once(' ').unwrap();
+                    Self::add_column_if_missing(conn, "master_passwords", column, definition).await?;
+                }
+            }
+            // Entries created before metadata encryption keep plaintext title
+            // and category columns until the next unlock encrypts and drops
+            // them. A file whose categories were already moved to their own
+            // table must not get category_encrypted back.
+            4 => {
+                Self::add_column_if_missing(conn, "password_entries", "title_encrypted", "TEXT").await?;
+                if !Self::has_column(conn, "password_entries", "category_id").await? {
+                    Self::add_column_if_missing(conn, "password_entries", "category_encrypted", "TEXT").await?;
+                }
+            }
+            5 => {
+                sqlx::query("CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)")
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            // Brute-force protection state
+            6 => {
+                Self::add_column_if_missing(conn, "master_passwords", "failed_attempts", "INTEGER NOT NULL DEFAULT 0").await?;
+                Self::add_column_if_missing(conn, "master_passwords", "locked_until", "TEXT").await?;
+            }
+            // Vault key wrapped under the recovery code, if one has been issued
+            7 => Self::add_column_if_missing(conn, "master_passwords", "recovery_wrapped_key", "TEXT").await?,
+            // Whether the master password is combined with a key file
+            8 => {
+                Self::add_column_if_missing(conn, "master_passwords", "requires_key_file", "INTEGER NOT NULL DEFAULT 0")
+                    .await?
+            }
+            9 => {
+                sqlx::query(
+                    r#"
+                    CREATE TABLE IF NOT EXISTS password_policies (
+                        id TEXT PRIMARY KEY,
+                        name_encrypted TEXT NOT NULL,
+                        policy_encrypted TEXT NOT NULL,
+                        created_at TEXT NOT NULL
+                    )
+                    "#,
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+            10 => Self::add_column_if_missing(conn, "password_entries", "totp_encrypted", "TEXT").await?,
+            // Not encrypted, so favorites can be selected without decrypting every entry
+            11 => {
+                Self::add_column_if_missing(conn, "password_entries", "is_favorite", "INTEGER NOT NULL DEFAULT 0").await?
+            }
+            // Free-text categories stay in category_encrypted until the next
+            // unlock moves them into the categories table
+            12 => {
+                sqlx::query(
+                    r#"
+                    CREATE TABLE IF NOT EXISTS categories (
+                        id TEXT PRIMARY KEY,
+                        name_encrypted TEXT NOT NULL,
+                        color TEXT,
+                        icon TEXT,
+                        created_at TEXT NOT NULL
+                    )
+                    "#,
+                )
+                .execute(&mut *conn)
+                .await?;
+                Self::add_column_if_missing(conn, "password_entries", "category_id", "TEXT REFERENCES categories(id)")
+                    .await?;
+            }
+            // Usernames and passwords replaced by an edit
+            13 => {
+                for statement in [
+                    r#"
+                    CREATE TABLE IF NOT EXISTS entry_history (
+                        id TEXT PRIMARY KEY,
+                        entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                        username_encrypted TEXT NOT NULL,
+                        password_encrypted TEXT NOT NULL,
+                        created_at TEXT NOT NULL
+                    )
+                    "#,
+                    "CREATE INDEX IF NOT EXISTS entry_history_entry_id ON entry_history (entry_id)",
+                ] {
+                    sqlx::query(statement).execute(&mut *conn).await?;
+                }
+            }
+            // Set while an entry is in the trash
+            14 => Self::add_column_if_missing(conn, "password_entries", "deleted_at", "TEXT").await?,
+            // Custom fields, one row each so every value is encrypted on its own
+            15 => {
+                for statement in [
+                    r#"
+                    CREATE TABLE IF NOT EXISTS entry_fields (
+                        id TEXT PRIMARY KEY,
+                        entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                        position INTEGER NOT NULL,
+                        field_type TEXT NOT NULL,
+                        name_encrypted TEXT NOT NULL,
+                        value_encrypted TEXT NOT NULL
+                    )
+                    "#,
+                    "CREATE INDEX IF NOT EXISTS entry_fields_entry_id ON entry_fields (entry_id)",
+                ] {
+                    sqlx::query(statement).execute(&mut *conn).await?;
+                }
+            }
+            // Fields of non-login item types, as encrypted JSON
+            16 => Self::add_column_if_missing(conn, "password_entries", "item_data_encrypted", "TEXT").await?,
+            // Attachments, encrypted in fixed-size chunks
+            17 => {
+                for statement in [
+                    r#"
+                    CREATE TABLE IF NOT EXISTS attachments (
+                        id TEXT PRIMARY KEY,
+                        entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE,
+                        name_encrypted TEXT NOT NULL,
+                        size INTEGER NOT NULL,
+                        chunk_count INTEGER NOT NULL,
+                        created_at TEXT NOT NULL
+                    )
+                    "#,
+                    "CREATE INDEX IF NOT EXISTS attachments_entry_id ON attachments (entry_id)",
+                    r#"
+                    CREATE TABLE IF NOT EXISTS attachment_chunks (
+                        attachment_id TEXT NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+                        chunk_index INTEGER NOT NULL,
+                        data_encrypted BLOB NOT NULL,
+                        PRIMARY KEY (attachment_id, chunk_index)
+                    )
+                    "#,
+                ] {
+                    sqlx::query(statement).execute(&mut *conn).await?;
+                }
+            }
+            _ => unreachable!("no migration to schema version {}", version),
+        }
+
+        Ok(())
+    }
+
+    async fn has_table(conn: &mut SqliteConnection, table: &str) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let count: i64 = row.get("count");
+        Ok(count > 0)
+    }
+
+    /// Check whether a table has a column
+    async fn has_column(conn: &mut SqliteConnection, table: &str, column: &str) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let count: i64 = row.get("count");
+        Ok(count > 0)
+    }
+
+    async fn column_exists(&self, table: &str, column: &str) -> Result<bool, DatabaseError> {
+        let mut conn = self.pool.acquire().await?;
+        Self::has_column(&mut conn, table, column).await
+    }
+
+    /// Add a column to an existing table unless it is already present
+    async fn add_column_if_missing(
+        conn: &mut SqliteConnection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), DatabaseError> {
+        if !Self::has_column(conn, table, column).await? {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&mut *conn)
+                .await?;
+        }
 
         Ok(())
     }
 
     /// Set the master password (only if none exists)
-    pub async fn set_master_password(&self, password: &str) -> Result<(), DatabaseError> {
+    ///
+    /// With `requires_key_file`, `password` is the composite of the master
+    /// password and the key file.
+    pub async fn set_master_password(
+        &self,
+        password: &str,
+        params: &KdfParams,
+        requires_key_file: bool,
+    ) -> Result<(), DatabaseError> {
         // Check if master password already exists
         let existing = sqlx::query("SELECT COUNT(*) as count FROM master_passwords")
             .fetch_one(&self.pool)
@@ -155,105 +899,552 @@ impl Database {
             return Err(DatabaseError::ConnectionError(sqlx::Error::RowNotFound));
         }
 
-        let salt = CryptoService::generate_salt();
-        let password_hash = CryptoService::hash_password(password)?;
-        let salt_b64 = base64::engine::general_purpose::STANDARD.encode(&salt);
+        let vault_key = CryptoService::generate_vault_key();
+        let record = KeyRecord::derive(password, &vault_key, params).await?;
         let now = Utc::now();
 
         sqlx::query(
-            "INSERT INTO master_passwords (password_hash, salt, created_at) VALUES (?, ?, ?)"
+            r#"
+            INSERT INTO master_passwords
+            (password_hash, salt, wrapped_key, kdf_algorithm, kdf_memory_kib, kdf_iterations, kdf_parallelism,
+             requires_key_file, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
         )
-        .bind(&password_hash)
-        .bind(&salt_b64)
+        .bind(&record.password_hash)
+        .bind(&record.salt)
+        .bind(&record.wrapped_key)
+        .bind(&params.algorithm)
+        .bind(params.memory_kib)
+        .bind(params.iterations)
+        .bind(params.parallelism)
+        .bind(requires_key_file)
         .bind(now.to_rfc3339())
         .execute(&self.pool)
         .await?;
 
+        let mut tx = self.pool.begin().await?;
+        Self::insert_default_categories(&mut tx, &[], &vault_key).await?;
+        tx.commit().await?;
+
         Ok(())
     }
 
-    /// Verify the master password and return the encryption key
-    pub async fn verify_master_password(&self, password: &str) -> Result<[u8; 32], DatabaseError> {
-        let row = sqlx::query("SELECT password_hash, salt FROM master_passwords LIMIT 1")
-            .fetch_optional(&self.pool)
-            .await?;
+    /// Verify the master password and return the vault key
+    pub async fn verify_master_password(&self, password: &str) -> Result<SecretKey, DatabaseError> {
+        let (vault_key, _) = self.unlock(password).await?;
+        Ok(vault_key)
+    }
 
-        match row {
-            Some(row) => {
-                let password_hash: String = row.get("password_hash");
-                let salt_b64: String = row.get("salt");
-                
-                if !CryptoService::verify_password(password, &password_hash)? {
-                    return Err(DatabaseError::InvalidMasterPassword);
-                }
+    /// Change the master password by re-wrapping the vault key
+    ///
+    /// Entries stay encrypted under the same vault key, so only the
+    /// `master_passwords` row changes and the update is a single statement.
+    pub async fn change_master_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<SecretKey, DatabaseError> {
+        let (vault_key, params) = self.unlock(old_password).await?;
+        self.rewrap_vault_key(new_password, &vault_key, &params).await?;
 
-                let salt = base64::engine::general_purpose::STANDARD.decode(&salt_b64)?;
-                let key = CryptoService::derive_key_from_password(password, &salt)?;
-                
-                Ok(key)
-            }
-            None => Err(DatabaseError::EntryNotFound),
+        Ok(vault_key)
+    }
+
+    /// Re-wrap the vault key using new Argon2 parameters
+    pub async fn upgrade_kdf_params(
+        &self,
+        password: &str,
+        params: &KdfParams,
+    ) -> Result<SecretKey, DatabaseError> {
+        let (vault_key, _) = self.unlock(password).await?;
+        self.rewrap_vault_key(password, &vault_key, params).await?;
+
+        Ok(vault_key)
+    }
+
+    /// Issue a new recovery code for the vault, replacing any previous one
+    ///
+    /// Only the vault key wrapped under the code is stored; the code itself is
+    /// returned once for the user to write down.
+    pub async fn create_recovery_code(&self, vault_key: &SecretKey) -> Result<Zeroizing<String>, DatabaseError> {
+        let code = CryptoService::generate_recovery_code();
+        let kek = CryptoService::derive_recovery_key(&code)
+            .expect("generated recovery code is well-formed");
+        let wrapped = CryptoService::wrap_key(vault_key, &kek)?;
+
+        let result = sqlx::query("UPDATE master_passwords SET recovery_wrapped_key = ?")
+            .bind(serde_json::to_string(&wrapped)?)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::EntryNotFound);
         }
+
+        Ok(code)
     }
 
-    /// Check if master password is set
-    pub async fn has_master_password(&self) -> Result<bool, DatabaseError> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM master_passwords")
+    /// Invalidate the current recovery code
+    pub async fn revoke_recovery_code(&self) -> Result<(), DatabaseError> {
+        sqlx::query("UPDATE master_passwords SET recovery_wrapped_key = NULL")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Check if a recovery code has been issued
+    pub async fn has_recovery_code(&self) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM master_passwords WHERE recovery_wrapped_key IS NOT NULL")
             .fetch_one(&self.pool)
             .await?;
-        
+
         let count: i64 = row.get("count");
         Ok(count > 0)
     }
 
-    /// Create a new password entry
-    pub async fn create_entry(
-        &self,
+    /// Unwrap the vault key with a recovery code and set a new master password
+    ///
+    /// The recovery code stays valid, and any lockout from failed password
+    /// attempts is cleared. The key file requirement is dropped as well, since
+    /// the file may have been lost along with the password.
+    pub async fn recover_with_code(&self, code: &str, new_password: &str) -> Result<SecretKey, DatabaseError> {
+        let row = sqlx::query("SELECT recovery_wrapped_key, kdf_algorithm, kdf_memory_kib, kdf_iterations, kdf_parallelism FROM master_passwords LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(DatabaseError::EntryNotFound)?;
+
+        let wrapped_key: Option<String> = row.get("recovery_wrapped_key");
+        let wrapped: EncryptedData = serde_json::from_str(&wrapped_key.ok_or(DatabaseError::InvalidRecoveryCode)?)?;
+        let params = Self::row_to_kdf_params(&row);
+
+        let kek = CryptoService::derive_recovery_key(code).ok_or(DatabaseError::InvalidRecoveryCode)?;
+        let vault_key = CryptoService::unwrap_key(&wrapped, &kek).map_err(|_| DatabaseError::InvalidRecoveryCode)?;
+
+        self.rewrap_vault_key(new_password, &vault_key, &params).await?;
+        sqlx::query("UPDATE master_passwords SET failed_attempts = 0, locked_until = NULL, requires_key_file = 0")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(vault_key)
+    }
+
+    /// Check if unlocking needs a key file besides the master password
+    pub async fn requires_key_file(&self) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT requires_key_file FROM master_passwords LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some_and(|row| row.get::<bool, _>("requires_key_file")))
+    }
+
+    /// Whether titles and categories are still stored in plaintext
+    ///
+    /// True for a vault created before metadata encryption until its first
+    /// unlock, which encrypts the values and scrubs the old columns.
+    pub async fn has_plaintext_metadata(&self) -> Result<bool, DatabaseError> {
+        self.column_exists("password_entries", "title").await
+    }
+
+    /// Add or remove the key file requirement
+    ///
+    /// `current` unlocks the vault as it is now and `new` is what it will take
+    /// afterwards; one of them is a key-file composite. The re-wrapped key and
+    /// the flag are written together so they can never disagree.
+    pub async fn set_key_file_requirement(
+        &self,
+        current: &str,
+        new: &str,
+        requires_key_file: bool,
+    ) -> Result<SecretKey, DatabaseError> {
+        let (vault_key, params) = self.unlock(current).await?;
+        let record = KeyRecord::derive(new, &vault_key, &params).await?;
+
+        sqlx::query("UPDATE master_passwords SET password_hash = ?, salt = ?, wrapped_key = ?, requires_key_file = ?")
+            .bind(&record.password_hash)
+            .bind(&record.salt)
+            .bind(&record.wrapped_key)
+            .bind(requires_key_file)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(vault_key)
+    }
+
+    /// Get the Argon2 parameters of the current vault
+    pub async fn get_kdf_params(&self) -> Result<KdfParams, DatabaseError> {
+        let row = sqlx::query(
+            "SELECT kdf_algorithm, kdf_memory_kib, kdf_iterations, kdf_parallelism FROM master_passwords LIMIT 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Self::row_to_kdf_params(&row)),
+            None => Err(DatabaseError::EntryNotFound),
+        }
+    }
+
+    /// Check the master password and unwrap the vault key
+    ///
+    /// Every path that takes the master password comes through here, so the
+    /// failed-attempt backoff and wipe policy apply to all of them.
+    ///
+    /// Vaults created before the key hierarchy used the derived key directly.
+    /// That key becomes the vault key and is wrapped under a freshly salted
+    /// key-encryption key, so no entry has to be rewritten. Any envelope
+    /// written before versioning is upgraded here as well.
+    async fn unlock(&self, password: &str) -> Result<(SecretKey, KdfParams), DatabaseError> {
+        let _guard = self.unlock_guard.lock().await;
+
+        let row = sqlx::query("SELECT * FROM master_passwords LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let row = row.ok_or(DatabaseError::EntryNotFound)?;
+        let password_hash: String = row.get("password_hash");
+        let salt_b64: String = row.get("salt");
+        let wrapped_key: Option<String> = row.get("wrapped_key");
+        let failed_attempts: i64 = row.get("failed_attempts");
+        let locked_until: Option<String> = row.get("locked_until");
+        let params = Self::row_to_kdf_params(&row);
+
+        if let Some(locked_until) = locked_until {
+            let locked_until = DateTime::parse_from_rfc3339(&locked_until)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .with_timezone(&Utc);
+
+            let remaining = locked_until - Utc::now();
+            if remaining > chrono::Duration::zero() {
+                // Round up so the UI never tells the user to retry too early
+                let retry_after_secs = (remaining.num_milliseconds() as u64).div_ceil(1000);
+                return Err(DatabaseError::TooManyAttempts { retry_after_secs });
+            }
+        }
+        
+        let secret = Zeroizing::new(password.to_string());
+        let verified = run_blocking(move || Ok(CryptoService::verify_password(&secret, &password_hash)?)).await?;
+        if !verified {
+            self.record_failed_attempt(failed_attempts as u32 + 1).await?;
+            return Err(DatabaseError::InvalidMasterPassword);
+        }
+
+        if failed_attempts > 0 {
+            sqlx::query("UPDATE master_passwords SET failed_attempts = 0, locked_until = NULL")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        let salt = base64::engine::general_purpose::STANDARD.decode(&salt_b64)?;
+        let (secret, kdf_params) = (Zeroizing::new(password.to_string()), params.clone());
+        let kek = run_blocking(move || Ok(CryptoService::derive_key_from_password(&secret, &salt, &kdf_params)?)).await?;
+
+        let vault_key = match wrapped_key {
+            Some(wrapped_key) => {
+                let wrapped: EncryptedData = serde_json::from_str(&wrapped_key)?;
+                let vault_key = CryptoService::unwrap_key(&wrapped, &kek)?;
+
+                if wrapped.is_legacy() {
+                    self.rewrap_vault_key(password, &vault_key, &params).await?;
+                }
+
+                vault_key
+            }
+            None => {
+                self.rewrap_vault_key(password, &kek, &params).await?;
+                kek
+            }
+        };
+
+        self.migrate_plaintext_metadata(&vault_key).await?;
+        self.migrate_legacy_envelopes(&vault_key).await?;
+        self.migrate_entry_categories(&vault_key).await?;
+        self.remove_migration_backups().await?;
+        self.purge_expired_trash().await?;
+
+        Ok((vault_key, params))
+    }
+
+    /// Count a failed unlock and start its backoff, or wipe the vault if the
+    /// configured limit has been reached
+    async fn record_failed_attempt(&self, failed_attempts: u32) -> Result<(), DatabaseError> {
+        let lockout = self.get_lockout_settings().await?;
+        if lockout.wipe_after_failures.is_some_and(|limit| failed_attempts >= limit) {
+            self.wipe_vault().await?;
+            return Err(DatabaseError::VaultWiped);
+        }
+
+        let delay = Self::lockout_delay(failed_attempts);
+        let locked_until = (!delay.is_zero())
+            .then(|| Utc::now() + chrono::Duration::seconds(delay.as_secs() as i64));
+
+        sqlx::query("UPDATE master_passwords SET failed_attempts = ?, locked_until = ?")
+            .bind(failed_attempts)
+            .bind(locked_until.map(|t| t.to_rfc3339()))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Wait imposed after the given number of consecutive failures
+    fn lockout_delay(failed_attempts: u32) -> Duration {
+        if failed_attempts < FREE_UNLOCK_ATTEMPTS {
+            return Duration::ZERO;
+        }
+
+        let doublings = (failed_attempts - FREE_UNLOCK_ATTEMPTS).min(20);
+        Duration::from_secs((BASE_LOCKOUT_SECS << doublings).min(MAX_LOCKOUT_SECS))
+    }
+
+    /// Erase the master password, every entry and any migration backup
+    ///
+    /// Deleting rows does not overwrite them, so the wipe runs with
+    /// `secure_delete` and the file is then vacuumed and its write-ahead log
+    /// emptied; otherwise the wrapped key and ciphertext stay readable in
+    /// free pages.
+    async fn wipe_vault(&self) -> Result<(), DatabaseError> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA secure_delete = ON").execute(&mut *conn).await?;
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+
+        sqlx::query("DELETE FROM entry_history").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM entry_fields").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM attachment_chunks").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM attachments").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM password_entries").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM categories").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM password_policies").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM master_passwords").execute(&mut *tx).await?;
+        // Encrypted under the vault key, which no later vault can read
+        sqlx::query("DELETE FROM settings WHERE key = 'auto_lock'").execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        sqlx::query("PRAGMA secure_delete = OFF").execute(&mut *conn).await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+        drop(conn);
+
+        self.remove_migration_backups().await?;
+        Ok(())
+    }
+
+    /// Store the vault key wrapped under a new key derived from the password
+    async fn rewrap_vault_key(
+        &self,
+        password: &str,
+        vault_key: &SecretKey,
+        params: &KdfParams,
+    ) -> Result<(), DatabaseError> {
+        let record = KeyRecord::derive(password, vault_key, params).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE master_passwords
+            SET password_hash = ?, salt = ?, wrapped_key = ?,
+                kdf_algorithm = ?, kdf_memory_kib = ?, kdf_iterations = ?, kdf_parallelism = ?
+            "#,
+        )
+        .bind(&record.password_hash)
+        .bind(&record.salt)
+        .bind(&record.wrapped_key)
+        .bind(&params.algorithm)
+        .bind(params.memory_kib)
+        .bind(params.iterations)
+        .bind(params.parallelism)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Helper function to read KDF parameters, defaulting for vaults that predate them
+    fn row_to_kdf_params(row: &sqlx::sqlite::SqliteRow) -> KdfParams {
+        let algorithm: Option<String> = row.get("kdf_algorithm");
+        let defaults = KdfParams::default();
+
+        match algorithm {
+            Some(algorithm) => KdfParams {
+                algorithm,
+                memory_kib: row.get::<Option<u32>, _>("kdf_memory_kib").unwrap_or(defaults.memory_kib),
+                iterations: row.get::<Option<u32>, _>("kdf_iterations").unwrap_or(defaults.iterations),
+                parallelism: row.get::<Option<u32>, _>("kdf_parallelism").unwrap_or(defaults.parallelism),
+            },
+            None => defaults,
+        }
+    }
+
+    /// Check if master password is set
+    pub async fn has_master_password(&self) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM master_passwords")
+            .fetch_one(&self.pool)
+            .await?;
+        
+        let count: i64 = row.get("count");
+        Ok(count > 0)
+    }
+
+    /// Read a raw setting value
+    async fn get_setting(&self, key: &str) -> Result<Option<String>, DatabaseError> {
+        let row = sqlx::query("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("value")))
+    }
+
+    /// Insert or replace a raw setting value
+    async fn set_setting(&self, key: &str, value: &str) -> Result<(), DatabaseError> {
+        sqlx::query("INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the auto-lock settings, falling back to defaults if never saved
+    ///
+    /// They are encrypted so that turning auto-lock off takes the vault key;
+    /// editing the file cannot keep an unattended vault open.
+    pub async fn get_auto_lock_settings(&self, encryption_key: &SecretKey) -> Result<AutoLockSettings, DatabaseError> {
+        match self.get_setting("auto_lock").await? {
+            Some(value) => {
+                let value = Self::decrypt_field(&value, &Self::setting_aad("auto_lock"), encryption_key)?;
+                Ok(serde_json::from_str(&value)?)
+            }
+            None => Ok(AutoLockSettings::default()),
+        }
+    }
+
+    /// Save the auto-lock settings
+    pub async fn set_auto_lock_settings(
+        &self,
+        settings: &AutoLockSettings,
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        let value = serde_json::to_string(settings)?;
+        let value = Self::encrypt_field(&value, &Self::setting_aad("auto_lock"), encryption_key)?;
+        self.set_setting("auto_lock", &value).await
+    }
+
+    /// Get the brute-force policy, falling back to defaults if never saved
+    pub async fn get_lockout_settings(&self) -> Result<LockoutSettings, DatabaseError> {
+        match self.get_setting("lockout").await? {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Ok(LockoutSettings::default()),
+        }
+    }
+
+    /// Save the brute-force policy
+    pub async fn set_lockout_settings(&self, settings: &LockoutSettings) -> Result<(), DatabaseError> {
+        self.set_setting("lockout", &serde_json::to_string(settings)?).await
+    }
+
+    /// Load the health report thresholds, falling back to the defaults
+    pub async fn get_health_settings(&self) -> Result<HealthSettings, DatabaseError> {
+        match self.get_setting("health").await? {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Ok(HealthSettings::default()),
+        }
+    }
+
+    /// Save the health report thresholds
+    pub async fn set_health_settings(&self, settings: &HealthSettings) -> Result<(), DatabaseError> {
+        self.set_setting("health", &serde_json::to_string(settings)?).await
+    }
+
+    /// Get the trash retention, falling back to defaults if never saved
+    pub async fn get_trash_settings(&self) -> Result<TrashSettings, DatabaseError> {
+        match self.get_setting("trash").await? {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Ok(TrashSettings::default()),
+        }
+    }
+
+    /// Save the trash retention
+    pub async fn set_trash_settings(&self, settings: &TrashSettings) -> Result<(), DatabaseError> {
+        self.set_setting("trash", &serde_json::to_string(settings)?).await
+    }
+
+    /// Create a new password entry
+    pub async fn create_entry(
+        &self,
         entry: CreatePasswordEntry,
-        encryption_key: &[u8; 32],
+        encryption_key: &SecretKey,
     ) -> Result<String, DatabaseError> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
-        // Encrypt sensitive fields
-        let username_encrypted = CryptoService::encrypt(&entry.username, encryption_key)?;
-        let password_encrypted = CryptoService::encrypt(&entry.password, encryption_key)?;
-        let url_encrypted = if let Some(url) = &entry.url {
-            Some(CryptoService::encrypt(url, encryption_key)?)
-        } else {
-            None
-        };
-        let notes_encrypted = if let Some(notes) = &entry.notes {
-            Some(CryptoService::encrypt(notes, encryption_key)?)
-        } else {
-            None
-        };
+        // Encrypt every field, bound to this entry and field
+        let title_encrypted = Self::encrypt_field(&entry.title, &Self::entry_aad(&id, "title"), encryption_key)?;
+        let username_encrypted = Self::encrypt_field(&entry.username, &Self::entry_aad(&id, "username"), encryption_key)?;
+        let password_encrypted = Self::encrypt_field(&entry.password, &Self::entry_aad(&id, "password"), encryption_key)?;
+        let url_encrypted = entry
+            .url
+            .as_ref()
+            .map(|url| Self::encrypt_field(url, &Self::entry_aad(&id, "url"), encryption_key))
+            .transpose()?;
+        let notes_encrypted = entry
+            .notes
+            .as_ref()
+            .map(|notes| Self::encrypt_field(notes, &Self::entry_aad(&id, "notes"), encryption_key))
+            .transpose()?;
+        if let Some(category_id) = &entry.category_id {
+            self.ensure_category_exists(category_id).await?;
+        }
+        let totp_encrypted = entry
+            .totp
+            .as_ref()
+            .map(|totp| Self::encrypt_field(totp, &Self::entry_aad(&id, "totp"), encryption_key))
+            .transpose()?;
+        let item_data_encrypted = entry
+            .item_data
+            .as_ref()
+            .map(|data| Self::encrypt_item_data(&id, data, encryption_key))
+            .transpose()?;
+        let custom_fields = Self::encrypt_custom_fields(&id, &entry.custom_fields, encryption_key)?;
+
+        let mut tx = self.pool.begin().await?;
 
         sqlx::query(
             r#"
             INSERT INTO password_entries 
-            (id, title, username_encrypted, password_encrypted, url_encrypted, notes_encrypted, category, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, title_encrypted, username_encrypted, password_encrypted, url_encrypted, notes_encrypted, category_id, totp_encrypted, item_data_encrypted, is_favorite, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
-        .bind(&entry.title)
-        .bind(serde_json::to_string(&username_encrypted)?)
-        .bind(serde_json::to_string(&password_encrypted)?)
-        .bind(url_encrypted.as_ref().map(|u| serde_json::to_string(u)).transpose()?)
-        .bind(notes_encrypted.as_ref().map(|n| serde_json::to_string(n)).transpose()?)
-        .bind(&entry.category)
+        .bind(&title_encrypted)
+        .bind(&username_encrypted)
+        .bind(&password_encrypted)
+        .bind(&url_encrypted)
+        .bind(&notes_encrypted)
+        .bind(&entry.category_id)
+        .bind(&totp_encrypted)
+        .bind(&item_data_encrypted)
+        .bind(entry.is_favorite)
         .bind(now.to_rfc3339())
         .bind(now.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        Self::insert_custom_fields(&mut tx, &id, &custom_fields).await?;
+
+        tx.commit().await?;
+
         Ok(id)
     }
 
-    /// Get all password entries (decrypted)
-    pub async fn get_all_entries(&self, encryption_key: &[u8; 32]) -> Result<Vec<PasswordEntry>, DatabaseError> {
-        let rows = sqlx::query("SELECT * FROM password_entries ORDER BY title")
+    /// Get all password entries outside the trash (decrypted), sorted by title
+    ///
+    /// Titles are encrypted, so ordering happens after decryption.
+    pub async fn get_all_entries(&self, encryption_key: &SecretKey) -> Result<Vec<PasswordEntry>, DatabaseError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM {} WHERE deleted_at IS NULL", ENTRY_COLUMNS, ENTRY_TABLES))
             .fetch_all(&self.pool)
             .await?;
 
@@ -262,81 +1453,143 @@ impl Database {
             let entry = self.row_to_password_entry(row, encryption_key)?;
             entries.push(entry);
         }
+        self.load_custom_fields(&mut entries, encryption_key).await?;
+
+        entries.sort_by_cached_key(|entry| entry.title.to_lowercase());
+
+        Ok(entries)
+    }
+
+    /// Get the entries marked as favorites (decrypted), sorted by title
+    pub async fn get_favorite_entries(&self, encryption_key: &SecretKey) -> Result<Vec<PasswordEntry>, DatabaseError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM {} WHERE is_favorite = 1 AND deleted_at IS NULL", ENTRY_COLUMNS, ENTRY_TABLES))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries = rows
+            .into_iter()
+            .map(|row| self.row_to_password_entry(row, encryption_key))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.load_custom_fields(&mut entries, encryption_key).await?;
+
+        entries.sort_by_cached_key(|entry| entry.title.to_lowercase());
 
         Ok(entries)
     }
 
-    /// Get a password entry by ID
+    /// Get a password entry by ID; entries in the trash are not found
     pub async fn get_entry_by_id(
         &self,
         id: &str,
-        encryption_key: &[u8; 32],
+        encryption_key: &SecretKey,
     ) -> Result<PasswordEntry, DatabaseError> {
-        let row = sqlx::query("SELECT * FROM password_entries WHERE id = ?")
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM {} WHERE password_entries.id = ? AND deleted_at IS NULL",
+            ENTRY_COLUMNS, ENTRY_TABLES
+        ))
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
 
-        match row {
-            Some(row) => self.row_to_password_entry(row, encryption_key),
-            None => Err(DatabaseError::EntryNotFound),
-        }
+        let Some(row) = row else {
+            return Err(DatabaseError::EntryNotFound);
+        };
+
+        let mut entries = [self.row_to_password_entry(row, encryption_key)?];
+        self.load_custom_fields(&mut entries, encryption_key).await?;
+        let [entry] = entries;
+        Ok(entry)
     }
 
     /// Update a password entry
+    ///
+    /// If the username or password changes, the values being replaced are
+    /// kept in the entry's history, written in the same transaction.
     pub async fn update_entry(
         &self,
         id: &str,
         update: UpdatePasswordEntry,
-        encryption_key: &[u8; 32],
+        encryption_key: &SecretKey,
     ) -> Result<(), DatabaseError> {
         let now = Utc::now();
+
         let mut query_parts = Vec::new();
         let mut params: Vec<String> = Vec::new();
 
         if let Some(title) = &update.title {
-            query_parts.push("title = ?");
-            params.push(title.clone());
+            query_parts.push("title_encrypted = ?");
+            params.push(Self::encrypt_field(title, &Self::entry_aad(id, "title"), encryption_key)?);
         }
 
         if let Some(username) = &update.username {
-            let encrypted = CryptoService::encrypt(username, encryption_key)?;
             query_parts.push("username_encrypted = ?");
-            params.push(serde_json::to_string(&encrypted)?);
+            params.push(Self::encrypt_field(username, &Self::entry_aad(id, "username"), encryption_key)?);
         }
 
         if let Some(password) = &update.password {
-            let encrypted = CryptoService::encrypt(password, encryption_key)?;
             query_parts.push("password_encrypted = ?");
-            params.push(serde_json::to_string(&encrypted)?);
+            params.push(Self::encrypt_field(password, &Self::entry_aad(id, "password"), encryption_key)?);
         }
 
         if let Some(url) = &update.url {
-            let encrypted = CryptoService::encrypt(url, encryption_key)?;
             query_parts.push("url_encrypted = ?");
-            params.push(serde_json::to_string(&encrypted)?);
+            params.push(Self::encrypt_field(url, &Self::entry_aad(id, "url"), encryption_key)?);
         }
 
         if let Some(notes) = &update.notes {
-            let encrypted = CryptoService::encrypt(notes, encryption_key)?;
             query_parts.push("notes_encrypted = ?");
-            params.push(serde_json::to_string(&encrypted)?);
+            params.push(Self::encrypt_field(notes, &Self::entry_aad(id, "notes"), encryption_key)?);
         }
 
-        if let Some(category) = &update.category {
-            query_parts.push("category = ?");
-            params.push(category.clone());
+        match update.category_id.as_deref() {
+            Some("") => query_parts.push("category_id = NULL"),
+            Some(category_id) => {
+                self.ensure_category_exists(category_id).await?;
+                query_parts.push("category_id = ?");
+                params.push(category_id.to_string());
+            }
+            None => {}
         }
 
-        if query_parts.is_empty() {
+        if let Some(totp) = &update.totp {
+            query_parts.push("totp_encrypted = ?");
+            params.push(Self::encrypt_field(totp, &Self::entry_aad(id, "totp"), encryption_key)?);
+        }
+
+        match &update.item_data {
+            Some(Some(item_data)) => {
+                query_parts.push("item_data_encrypted = ?");
+                params.push(Self::encrypt_item_data(id, item_data, encryption_key)?);
+            }
+            Some(None) => query_parts.push("item_data_encrypted = NULL"),
+            None => {}
+        }
+
+        let custom_fields = update
+            .custom_fields
+            .as_ref()
+            .map(|fields| Self::encrypt_custom_fields(id, fields, encryption_key))
+            .transpose()?;
+
+        // Starring an entry is not an edit, so it leaves updated_at and the
+        // password age the health report uses alone
+        let edited = !query_parts.is_empty() || custom_fields.is_some();
+
+        if let Some(is_favorite) = update.is_favorite {
+            query_parts.push(if is_favorite { "is_favorite = 1" } else { "is_favorite = 0" });
+        }
+
+        if query_parts.is_empty() && !edited {
             return Ok(());
         }
 
-        query_parts.push("updated_at = ?");
-        params.push(now.to_rfc3339());
+        if edited {
+            query_parts.push("updated_at = ?");
+            params.push(now.to_rfc3339());
+        }
 
         let query_str = format!(
-            "UPDATE password_entries SET {} WHERE id = ?",
+            "UPDATE password_entries SET {} WHERE id = ? AND deleted_at IS NULL",
             query_parts.join(", ")
         );
 
@@ -346,149 +1599,2558 @@ impl Database {
         }
         query = query.bind(id);
 
-        let result = query.execute(&self.pool).await?;
-        
-        if result.rows_affected() == 0 {
-            return Err(DatabaseError::EntryNotFound);
+        let mut tx = self.pool.begin().await?;
+
+        let updated = async {
+            // Read in the transaction so a concurrent edit cannot slip in
+            // between and lose its values from the history
+            if update.username.is_some() || update.password.is_some() {
+                Self::record_history(&mut tx, id, &update, now, encryption_key).await?;
+            }
+
+            let result = query.execute(&mut *tx).await?;
+            if result.rows_affected() == 0 {
+                return Err(DatabaseError::EntryNotFound);
+            }
+
+            if let Some(custom_fields) = &custom_fields {
+                sqlx::query("DELETE FROM entry_fields WHERE entry_id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                Self::insert_custom_fields(&mut tx, id, custom_fields).await?;
+            }
+
+            Ok(())
         }
+        .await;
+        if let Err(e) = updated {
+            tx.rollback().await?;
+            return Err(e);
+        }
+
+        tx.commit().await?;
 
         Ok(())
     }
 
-    /// Delete a password entry
-    pub async fn delete_entry(&self, id: &str) -> Result<(), DatabaseError> {
-        let result = sqlx::query("DELETE FROM password_entries WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+    /// Keep an entry's current username and password in its history if
+    /// `update` changes either of them
+    async fn record_history(
+        conn: &mut SqliteConnection,
+        id: &str,
+        update: &UpdatePasswordEntry,
+        now: DateTime<Utc>,
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        let row = sqlx::query(
+            "SELECT username_encrypted, password_encrypted FROM password_entries WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(DatabaseError::EntryNotFound)?;
+        let username_encrypted: String = row.get("username_encrypted");
+        let password_encrypted: String = row.get("password_encrypted");
+        let username = Zeroizing::new(Self::decrypt_field(&username_encrypted, &Self::entry_aad(id, "username"), encryption_key)?);
+        let password = Zeroizing::new(Self::decrypt_field(&password_encrypted, &Self::entry_aad(id, "password"), encryption_key)?);
 
-        if result.rows_affected() == 0 {
-            return Err(DatabaseError::EntryNotFound);
+        let changed = |new: &Option<String>, old: &str| new.as_deref().is_some_and(|new| new != old);
+        if !changed(&update.username, &username) && !changed(&update.password, &password) {
+            return Ok(());
         }
 
+        let history_id = Uuid::new_v4().to_string();
+        let aad = |field| Self::history_aad(id, &history_id, field);
+        sqlx::query(
+            "INSERT INTO entry_history (id, entry_id, username_encrypted, password_encrypted, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&history_id)
+        .bind(id)
+        .bind(Self::encrypt_field(&username, &aad("username"), encryption_key)?)
+        .bind(Self::encrypt_field(&password, &aad("password"), encryption_key)?)
+        .bind(now.to_rfc3339())
+        .execute(&mut *conn)
+        .await?;
+
         Ok(())
     }
 
-    /// Search password entries by title or category
-    pub async fn search_entries(
+    /// Previous usernames and passwords of an entry (decrypted), newest first
+    pub async fn get_entry_history(
         &self,
-        query: &str,
-        encryption_key: &[u8; 32],
-    ) -> Result<Vec<PasswordEntry>, DatabaseError> {
-        let search_pattern = format!("%{}%", query);
+        entry_id: &str,
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<EntryHistory>, DatabaseError> {
+        self.ensure_entry_exists(entry_id).await?;
+
         let rows = sqlx::query(
-            "SELECT * FROM password_entries WHERE title LIKE ? OR category LIKE ? ORDER BY title"
+            r#"
+            SELECT id, username_encrypted, password_encrypted, created_at FROM entry_history
+            WHERE entry_id = ? ORDER BY created_at DESC, rowid DESC
+            "#,
         )
-        .bind(&search_pattern)
-        .bind(&search_pattern)
+        .bind(entry_id)
         .fetch_all(&self.pool)
         .await?;
 
-        let mut entries = Vec::new();
-        for row in rows {
-            let entry = self.row_to_password_entry(row, encryption_key)?;
-            entries.push(entry);
-        }
-
-        Ok(entries)
+        rows.into_iter()
+            .map(|row| self.row_to_entry_history(entry_id, row, encryption_key))
+            .collect()
     }
 
-    /// Helper function to convert database row to PasswordEntry
-    fn row_to_password_entry(
+    /// Put an old username and password back on their entry
+    ///
+    /// This is an ordinary edit, so the values being replaced go into the
+    /// history in turn and the restore can itself be undone.
+    pub async fn restore_entry_version(
         &self,
-        row: sqlx::sqlite::SqliteRow,
-        encryption_key: &[u8; 32],
-    ) -> Result<PasswordEntry, DatabaseError> {
-        let id: String = row.get("id");
-        let title: String = row.get("title");
-        let username_encrypted_str: String = row.get("username_encrypted");
-        let password_encrypted_str: String = row.get("password_encrypted");
-        let url_encrypted_str: Option<String> = row.get("url_encrypted");
-        let notes_encrypted_str: Option<String> = row.get("notes_encrypted");
-        let category: Option<String> = row.get("category");
-        let created_at_str: String = row.get("created_at");
-        let updated_at_str: String = row.get("updated_at");
+        entry_id: &str,
+        history_id: &str,
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        let row = sqlx::query(
+            "SELECT id, username_encrypted, password_encrypted, created_at FROM entry_history WHERE id = ? AND entry_id = ?",
+        )
+        .bind(history_id)
+        .bind(entry_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(DatabaseError::EntryNotFound)?;
+        let version = self.row_to_entry_history(entry_id, row, encryption_key)?;
 
-        // Decrypt fields
-        let username_encrypted: EncryptedData = serde_json::from_str(&username_encrypted_str)?;
-        let password_encrypted: EncryptedData = serde_json::from_str(&password_encrypted_str)?;
-        
-        let username = CryptoService::decrypt(&username_encrypted, encryption_key)?;
-        let password = CryptoService::decrypt(&password_encrypted, encryption_key)?;
-        
-        let url = if let Some(url_str) = url_encrypted_str {
-            let url_encrypted: EncryptedData = serde_json::from_str(&url_str)?;
-            Some(CryptoService::decrypt(&url_encrypted, encryption_key)?)
-        } else {
-            None
+        let update = UpdatePasswordEntry {
+            title: None,
+            username: Some(version.username.clone()),
+            password: Some(version.password.clone()),
+            url: None,
+            notes: None,
+            category_id: None,
+            totp: None,
+            is_favorite: None,
+            custom_fields: None,
+            item_data: None,
         };
+        self.update_entry(entry_id, update, encryption_key).await
+    }
 
-        let notes = if let Some(notes_str) = notes_encrypted_str {
-            let notes_encrypted: EncryptedData = serde_json::from_str(&notes_str)?;
-            Some(CryptoService::decrypt(&notes_encrypted, encryption_key)?)
-        } else {
-            None
-        };
+    /// Keep only the `keep` most recent history values of each entry,
+    /// returning how many were removed
+    pub async fn purge_entry_history(&self, keep: u32) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM entry_history WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (PARTITION BY entry_id ORDER BY created_at DESC, rowid DESC) AS position
+                    FROM entry_history
+                )
+                WHERE position > ?
+            )
+            "#,
+        )
+        .bind(keep)
+        .execute(&self.pool)
+        .await?;
 
-        Ok(PasswordEntry {
-            id,
-            title,
-            username,
-            password,
-            url,
-            notes,
-            category,
-            created_at: DateTime::parse_from_rfc3339(&created_at_str)
-                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
-                .with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&updated_at_str)
-                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
-                .with_timezone(&Utc),
-        })
+        Ok(result.rows_affected())
     }
-}
 
-#[cfg(test)]
+    /// Encrypt custom fields for `insert_custom_fields`, each under a new id
+    /// and in the order given
+    fn encrypt_custom_fields(
+        entry_id: &str,
+        fields: &[CustomField],
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<EncryptedCustomField>, DatabaseError> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(position, field)| {
+                let id = Uuid::new_v4().to_string();
+                let position = position as i64;
+                let aad = |column| Self::custom_field_aad(entry_id, &id, position, field.field_type, column);
+                Ok(EncryptedCustomField {
+                    name_encrypted: Self::encrypt_field(&field.name, &aad("name"), encryption_key)?,
+                    value_encrypted: Self::encrypt_field(&field.value, &aad("value"), encryption_key)?,
+                    field_type: field.field_type,
+                    position,
+                    id,
+                })
+            })
+            .collect()
+    }
+
+    /// Write an entry's custom fields
+    async fn insert_custom_fields(
+        conn: &mut SqliteConnection,
+        entry_id: &str,
+        fields: &[EncryptedCustomField],
+    ) -> Result<(), DatabaseError> {
+        for field in fields {
+            sqlx::query(
+                r#"
+                INSERT INTO entry_fields (id, entry_id, position, field_type, name_encrypted, value_encrypted)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&field.id)
+            .bind(entry_id)
+            .bind(field.position)
+            .bind(field.field_type.as_str())
+            .bind(&field.name_encrypted)
+            .bind(&field.value_encrypted)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Read and decrypt the custom fields of each entry, in order
+    async fn load_custom_fields(
+        &self,
+        entries: &mut [PasswordEntry],
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let placeholders = vec!["?"; entries.len()].join(", ");
+        let query_str = format!(
+            "SELECT id, entry_id, position, field_type, name_encrypted, value_encrypted FROM entry_fields \
+             WHERE entry_id IN ({}) ORDER BY position",
+            placeholders
+        );
+        let mut query = sqlx::query(&query_str);
+        for entry in entries.iter() {
+            query = query.bind(&entry.id);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+
+        let positions: HashMap<String, usize> =
+            entries.iter().enumerate().map(|(index, entry)| (entry.id.clone(), index)).collect();
+        for row in rows {
+            let id: String = row.get("id");
+            let entry_id: String = row.get("entry_id");
+            let position: i64 = row.get("position");
+            let field_type: CustomFieldType = serde_json::from_value(serde_json::Value::String(row.get("field_type")))?;
+            let name_encrypted: String = row.get("name_encrypted");
+            let value_encrypted: String = row.get("value_encrypted");
+
+            let aad = |column| Self::custom_field_aad(&entry_id, &id, position, field_type, column);
+            let field = CustomField {
+                name: Self::decrypt_field(&name_encrypted, &aad("name"), encryption_key)?,
+                value: Self::decrypt_field(&value_encrypted, &aad("value"), encryption_key)?,
+                field_type,
+            };
+            entries[positions[&entry_id]].custom_fields.push(field);
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_entry_exists(&self, id: &str) -> Result<(), DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM password_entries WHERE id = ?")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let count: i64 = row.get("count");
+        if count == 0 {
+            return Err(DatabaseError::EntryNotFound);
+        }
+        Ok(())
+    }
+
+    /// Move a password entry to the trash
+    pub async fn delete_entry(&self, id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query("UPDATE password_entries SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::EntryNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Get the entries in the trash (decrypted), most recently deleted first
+    pub async fn get_trashed_entries(&self, encryption_key: &SecretKey) -> Result<Vec<PasswordEntry>, DatabaseError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM {} WHERE deleted_at IS NOT NULL", ENTRY_COLUMNS, ENTRY_TABLES))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries = rows
+            .into_iter()
+            .map(|row| self.row_to_password_entry(row, encryption_key))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.load_custom_fields(&mut entries, encryption_key).await?;
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+
+        Ok(entries)
+    }
+
+    /// Take an entry back out of the trash
+    pub async fn restore_entry(&self, id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query("UPDATE password_entries SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::EntryNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Permanently delete every entry in the trash, returning how many were removed
+    pub async fn empty_trash(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query("DELETE FROM password_entries WHERE deleted_at IS NOT NULL")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Permanently delete entries trashed longer ago than the retention period
+    ///
+    /// Deletion times are compared after parsing rather than as text, since
+    /// RFC 3339 strings with different fractional digits do not sort.
+    async fn purge_expired_trash(&self) -> Result<u64, DatabaseError> {
+        let settings = self.get_trash_settings().await?;
+        let cutoff = Utc::now() - chrono::Duration::days(settings.retention_days as i64);
+
+        let rows = sqlx::query("SELECT id, deleted_at FROM password_entries WHERE deleted_at IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut purged = 0;
+        for row in rows {
+            let deleted_at: String = row.get("deleted_at");
+            let deleted_at = DateTime::parse_from_rfc3339(&deleted_at)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?;
+            if deleted_at < cutoff {
+                let id: String = row.get("id");
+                purged += sqlx::query("DELETE FROM password_entries WHERE id = ?")
+                    .bind(&id)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+            }
+        }
+
+        Ok(purged)
+    }
+
+    /// Encrypt a file into the vault as an attachment of an entry
+    ///
+    /// The file is read and encrypted one chunk at a time on a blocking
+    /// thread. Nothing is kept if it turns out larger than
+    /// `MAX_ATTACHMENT_SIZE`.
+    pub async fn add_attachment(
+        &self,
+        entry_id: &str,
+        name: &str,
+        reader: impl Read + Send + 'static,
+        encryption_key: &SecretKey,
+    ) -> Result<Attachment, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM password_entries WHERE id = ? AND deleted_at IS NULL")
+            .bind(entry_id)
+            .fetch_one(&self.pool)
+            .await?;
+        if row.get::<i64, _>("count") == 0 {
+            return Err(DatabaseError::EntryNotFound);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let name_encrypted = Self::encrypt_field(name, &Self::attachment_aad(entry_id, &id), encryption_key)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO attachments (id, entry_id, name_encrypted, size, chunk_count, created_at)
+            VALUES (?, ?, ?, 0, 0, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(entry_id)
+        .bind(&name_encrypted)
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        let (size, chunk_count) = match Self::insert_attachment_chunks(&mut tx, &id, reader, encryption_key).await {
+            Ok(written) => written,
+            Err(e) => {
+                tx.rollback().await?;
+                return Err(e);
+            }
+        };
+
+        sqlx::query("UPDATE attachments SET size = ?, chunk_count = ? WHERE id = ?")
+            .bind(size as i64)
+            .bind(chunk_count)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Attachment {
+            id,
+            entry_id: entry_id.to_string(),
+            name: name.to_string(),
+            size,
+            created_at: now,
+        })
+    }
+
+    /// Store a file's encrypted chunks, returning its size and chunk count
+    ///
+    /// Chunks are read and encrypted on a blocking thread and inserted here
+    /// as they arrive, so neither the file reads nor the whole file sit on
+    /// the async runtime.
+    async fn insert_attachment_chunks(
+        conn: &mut SqliteConnection,
+        attachment_id: &str,
+        reader: impl Read + Send + 'static,
+        encryption_key: &SecretKey,
+    ) -> Result<(u64, i64), DatabaseError> {
+        let (sender, mut receiver) = mpsc::channel(ATTACHMENT_CHUNKS_IN_FLIGHT);
+        let reading = {
+            let attachment_id = attachment_id.to_string();
+            let encryption_key = encryption_key.clone();
+            run_blocking(move || Self::encrypt_attachment_chunks(reader, &attachment_id, &encryption_key, sender))
+        };
+        let inserting = async {
+            let mut index = 0i64;
+            while let Some(chunk) = receiver.recv().await {
+                sqlx::query("INSERT INTO attachment_chunks (attachment_id, chunk_index, data_encrypted) VALUES (?, ?, ?)")
+                    .bind(attachment_id)
+                    .bind(index)
+                    .bind(chunk)
+                    .execute(&mut *conn)
+                    .await?;
+                index += 1;
+            }
+            Ok::<_, DatabaseError>(index)
+        };
+
+        // A failed insert drops the receiver, which stops the reader; report
+        // the insert's error rather than the reader's
+        let (size, chunk_count) = tokio::join!(reading, inserting);
+        let chunk_count = chunk_count?;
+        Ok((size?, chunk_count))
+    }
+
+    /// Read and encrypt a file one chunk at a time, sending each chunk to
+    /// `chunks` in order; returns the file's size
+    ///
+    /// Every chunk but the last is full. The last one, empty when the size is
+    /// a multiple of the chunk size, is encrypted as final so a file cut
+    /// short at a chunk boundary does not decrypt.
+    fn encrypt_attachment_chunks(
+        mut reader: impl Read,
+        attachment_id: &str,
+        encryption_key: &SecretKey,
+        chunks: mpsc::Sender<Vec<u8>>,
+    ) -> Result<u64, DatabaseError> {
+        let mut buffer = Zeroizing::new(vec![0u8; ATTACHMENT_CHUNK_SIZE]);
+        let mut size = 0u64;
+        let mut index = 0i64;
+
+        loop {
+            let read = Self::read_chunk(&mut reader, &mut buffer)?;
+            size += read as u64;
+            if size > MAX_ATTACHMENT_SIZE {
+                return Err(DatabaseError::AttachmentTooLarge { max_bytes: MAX_ATTACHMENT_SIZE });
+            }
+
+            let last = read < ATTACHMENT_CHUNK_SIZE;
+            let aad = Self::attachment_chunk_aad(attachment_id, index, last);
+            let chunk = CryptoService::encrypt_blob(&buffer[..read], encryption_key, &aad)?;
+
+            // Only a failed insert closes the channel, and it reports its own error
+            if chunks.blocking_send(chunk).is_err() || last {
+                return Ok(size);
+            }
+            index += 1;
+        }
+    }
+
+    /// Fill `buffer` from `reader`, stopping early only at the end of input
+    fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            match reader.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// List an entry's attachments, oldest first
+    pub async fn get_attachments(
+        &self,
+        entry_id: &str,
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<Attachment>, DatabaseError> {
+        self.ensure_entry_exists(entry_id).await?;
+
+        let rows = sqlx::query("SELECT id, entry_id, name_encrypted, size, created_at FROM attachments WHERE entry_id = ?")
+            .bind(entry_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        // Sorted after parsing; stored times do not all have the same precision
+        let mut attachments = rows
+            .iter()
+            .map(|row| Self::row_to_attachment(row, encryption_key))
+            .collect::<Result<Vec<_>, DatabaseError>>()?;
+        attachments.sort_by_key(|attachment| attachment.created_at);
+
+        Ok(attachments)
+    }
+
+    /// Look up one attachment, without its contents
+    pub async fn get_attachment(&self, id: &str, encryption_key: &SecretKey) -> Result<Attachment, DatabaseError> {
+        let row = sqlx::query("SELECT id, entry_id, name_encrypted, size, created_at FROM attachments WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(DatabaseError::AttachmentNotFound)?;
+
+        Self::row_to_attachment(&row, encryption_key)
+    }
+
+    fn row_to_attachment(row: &sqlx::sqlite::SqliteRow, encryption_key: &SecretKey) -> Result<Attachment, DatabaseError> {
+        let id: String = row.get("id");
+        let entry_id: String = row.get("entry_id");
+        let name_encrypted: String = row.get("name_encrypted");
+        let created_at: String = row.get("created_at");
+
+        Ok(Attachment {
+            name: Self::decrypt_field(&name_encrypted, &Self::attachment_aad(&entry_id, &id), encryption_key)?,
+            size: row.get::<i64, _>("size") as u64,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .with_timezone(&Utc),
+            entry_id,
+            id,
+        })
+    }
+
+    /// Decrypt an attachment into `writer` one chunk at a time, returning
+    /// the number of bytes written
+    pub async fn export_attachment(
+        &self,
+        id: &str,
+        mut writer: impl Write,
+        encryption_key: &SecretKey,
+    ) -> Result<u64, DatabaseError> {
+        let row = sqlx::query("SELECT size, chunk_count FROM attachments WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(DatabaseError::AttachmentNotFound)?;
+        let size = row.get::<i64, _>("size") as u64;
+        let chunk_count: i64 = row.get("chunk_count");
+
+        let mut written = 0u64;
+        for index in 0..chunk_count {
+            let row = sqlx::query("SELECT data_encrypted FROM attachment_chunks WHERE attachment_id = ? AND chunk_index = ?")
+                .bind(id)
+                .bind(index)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or(DatabaseError::CorruptAttachment)?;
+            let data_encrypted: Vec<u8> = row.get("data_encrypted");
+
+            let aad = Self::attachment_chunk_aad(id, index, index == chunk_count - 1);
+            let chunk = CryptoService::decrypt_blob(&data_encrypted, encryption_key, &aad)?;
+            writer.write_all(&chunk)?;
+            written += chunk.len() as u64;
+        }
+        writer.flush()?;
+
+        if chunk_count == 0 || written != size {
+            return Err(DatabaseError::CorruptAttachment);
+        }
+
+        Ok(written)
+    }
+
+    /// Permanently delete an attachment and its chunks
+    pub async fn delete_attachment(&self, id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::AttachmentNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Save a named generator policy
+    pub async fn create_password_policy(
+        &self,
+        name: &str,
+        policy: &PasswordPolicy,
+        encryption_key: &SecretKey,
+    ) -> Result<String, DatabaseError> {
+        let id = Uuid::new_v4().to_string();
+        let aad = |field| Self::field_aad("password_policies", &id, field);
+
+        let name_encrypted = Self::encrypt_field(name, &aad("name"), encryption_key)?;
+        let policy_encrypted = Self::encrypt_field(&serde_json::to_string(policy)?, &aad("policy"), encryption_key)?;
+
+        sqlx::query("INSERT INTO password_policies (id, name_encrypted, policy_encrypted, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&id)
+            .bind(&name_encrypted)
+            .bind(&policy_encrypted)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Get all saved generator policies, sorted by name
+    pub async fn get_password_policies(
+        &self,
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<SavedPasswordPolicy>, DatabaseError> {
+        let rows = sqlx::query("SELECT id, name_encrypted, policy_encrypted, created_at FROM password_policies")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut policies = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: String = row.get("id");
+            let name_encrypted: String = row.get("name_encrypted");
+            let policy_encrypted: String = row.get("policy_encrypted");
+            let created_at: String = row.get("created_at");
+
+            let aad = |field| Self::field_aad("password_policies", &id, field);
+            let name = Self::decrypt_field(&name_encrypted, &aad("name"), encryption_key)?;
+            let policy = Self::decrypt_field(&policy_encrypted, &aad("policy"), encryption_key)?;
+
+            policies.push(SavedPasswordPolicy {
+                name,
+                policy: serde_json::from_str(&policy)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                    .with_timezone(&Utc),
+                id,
+            });
+        }
+
+        policies.sort_by_key(|policy| policy.name.to_lowercase());
+        Ok(policies)
+    }
+
+    /// Delete a saved generator policy
+    pub async fn delete_password_policy(&self, id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query("DELETE FROM password_policies WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::EntryNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Create a category; names are unique, ignoring case
+    pub async fn create_category(
+        &self,
+        category: &CreateCategory,
+        encryption_key: &SecretKey,
+    ) -> Result<String, DatabaseError> {
+        let mut conn = self.pool.acquire().await?;
+
+        Self::ensure_unique_category_name(&mut conn, &category.name, None, encryption_key).await?;
+        Self::insert_category(
+            &mut conn,
+            &category.name,
+            category.color.as_deref(),
+            category.icon.as_deref(),
+            encryption_key,
+        )
+        .await
+    }
+
+    /// Get all categories (decrypted), sorted by name
+    pub async fn get_categories(&self, encryption_key: &SecretKey) -> Result<Vec<Category>, DatabaseError> {
+        let mut conn = self.pool.acquire().await?;
+        let mut categories = Self::read_categories(&mut conn, encryption_key).await?;
+
+        categories.sort_by_cached_key(|category| category.name.to_lowercase());
+        Ok(categories)
+    }
+
+    /// Rename or restyle a category
+    ///
+    /// Entries refer to the category by id, so a new name shows on every
+    /// entry without rewriting them.
+    pub async fn update_category(
+        &self,
+        id: &str,
+        update: &UpdateCategory,
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        self.ensure_category_exists(id).await?;
+
+        let mut query_parts = Vec::new();
+        let mut params: Vec<Option<String>> = Vec::new();
+
+        if let Some(name) = &update.name {
+            let mut conn = self.pool.acquire().await?;
+            Self::ensure_unique_category_name(&mut conn, name, Some(id), encryption_key).await?;
+            query_parts.push("name_encrypted = ?");
+            params.push(Some(Self::encrypt_field(name.trim(), &Self::category_aad(id), encryption_key)?));
+        }
+
+        // An empty color or icon falls back to the UI default
+        if let Some(color) = &update.color {
+            query_parts.push("color = ?");
+            params.push(Some(color.clone()).filter(|color| !color.is_empty()));
+        }
+
+        if let Some(icon) = &update.icon {
+            query_parts.push("icon = ?");
+            params.push(Some(icon.clone()).filter(|icon| !icon.is_empty()));
+        }
+
+        if query_parts.is_empty() {
+            return Ok(());
+        }
+
+        let query_str = format!("UPDATE categories SET {} WHERE id = ?", query_parts.join(", "));
+
+        let mut query = sqlx::query(&query_str);
+        for param in params {
+            query = query.bind(param);
+        }
+        query.bind(id).execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    /// Delete a category, first moving its entries as `deletion` says
+    ///
+    /// Both categories are checked before the transaction starts, so it
+    /// never ends early holding a lock.
+    pub async fn delete_category(&self, id: &str, deletion: &CategoryDeletion) -> Result<(), DatabaseError> {
+        self.ensure_category_exists(id).await?;
+
+        let target = match deletion {
+            CategoryDeletion::Reassign { category_id } if category_id == id => {
+                return Err(DatabaseError::CategoryNotFound);
+            }
+            CategoryDeletion::Reassign { category_id } => {
+                self.ensure_category_exists(category_id).await?;
+                Some(category_id)
+            }
+            CategoryDeletion::Clear => None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE password_entries SET category_id = ? WHERE category_id = ?")
+            .bind(target)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM categories WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn ensure_category_exists(&self, id: &str) -> Result<(), DatabaseError> {
+        sqlx::query("SELECT id FROM categories WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|_| ())
+            .ok_or(DatabaseError::CategoryNotFound)
+    }
+
+    /// Reject `name` if another category already uses it, ignoring case
+    ///
+    /// Names are encrypted, so every category is decrypted to compare.
+    async fn ensure_unique_category_name(
+        conn: &mut SqliteConnection,
+        name: &str,
+        except_id: Option<&str>,
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        let name = name.trim();
+        let taken = Self::read_categories(conn, encryption_key)
+            .await?
+            .iter()
+            .any(|category| Some(category.id.as_str()) != except_id && category.name.to_lowercase() == name.to_lowercase());
+
+        if taken {
+            return Err(DatabaseError::DuplicateCategory(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn read_categories(
+        conn: &mut SqliteConnection,
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<Category>, DatabaseError> {
+        let rows = sqlx::query("SELECT id, name_encrypted, color, icon, created_at FROM categories")
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let mut categories = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: String = row.get("id");
+            let name_encrypted: String = row.get("name_encrypted");
+            let created_at: String = row.get("created_at");
+
+            categories.push(Category {
+                name: Self::decrypt_field(&name_encrypted, &Self::category_aad(&id), encryption_key)?,
+                color: row.get("color"),
+                icon: row.get("icon"),
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                    .with_timezone(&Utc),
+                id,
+            });
+        }
+
+        Ok(categories)
+    }
+
+    async fn insert_category(
+        conn: &mut SqliteConnection,
+        name: &str,
+        color: Option<&str>,
+        icon: Option<&str>,
+        encryption_key: &SecretKey,
+    ) -> Result<String, DatabaseError> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query("INSERT INTO categories (id, name_encrypted, color, icon, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&id)
+            .bind(Self::encrypt_field(name.trim(), &Self::category_aad(&id), encryption_key)?)
+            .bind(color)
+            .bind(icon)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Add the default categories whose names are not in `existing`
+    /// (lowercase)
+    async fn insert_default_categories(
+        conn: &mut SqliteConnection,
+        existing: &[String],
+        encryption_key: &SecretKey,
+    ) -> Result<(), DatabaseError> {
+        for (name, color, icon) in DEFAULT_CATEGORIES {
+            if !existing.contains(&name.to_lowercase()) {
+                Self::insert_category(conn, name, Some(color), Some(icon), encryption_key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Search password entries by title, category or custom field
+    ///
+    /// All of these are encrypted, so matching is a case-insensitive substring
+    /// test over the decrypted entries rather than SQL `LIKE`. Custom field
+    /// names always match; values only when the field is not hidden.
+    pub async fn search_entries(
+        &self,
+        query: &str,
+        encryption_key: &SecretKey,
+    ) -> Result<Vec<PasswordEntry>, DatabaseError> {
+        let query = Zeroizing::new(query.to_lowercase());
+        let entries = self.get_all_entries(encryption_key).await?;
+        // Lowercased copies of decrypted values are wiped as soon as they are compared
+        let matches = |text: &str| Zeroizing::new(text.to_lowercase()).contains(query.as_str());
+
+        Ok(entries
+            .into_iter()
+            .filter(|entry| {
+                matches(&entry.title)
+                    || entry.category.as_deref().is_some_and(matches)
+                    || entry.custom_fields.iter().any(|field| {
+                        matches(&field.name)
+                            || (field.field_type != CustomFieldType::Hidden && matches(&field.value))
+                    })
+            })
+            .collect())
+    }
+
+    /// Encrypt titles and categories stored in plaintext before metadata encryption
+    ///
+    /// Each row's values move to the encrypted columns and the plaintext columns
+    /// are then dropped, all in one transaction. Dropping a column does not
+    /// overwrite its values, so the transaction runs with `secure_delete` and
+    /// the file is vacuumed and its write-ahead log emptied afterwards. Until
+    /// the first unlock after upgrading, the old values are still on disk.
+    async fn migrate_plaintext_metadata(&self, encryption_key: &SecretKey) -> Result<(), DatabaseError> {
+        if !self.column_exists("password_entries", "title").await? {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        // Zero the pages the old values are freed from
+        sqlx::query("PRAGMA secure_delete = ON").execute(&mut *conn).await?;
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+
+        let rows = sqlx::query("SELECT id, title, category FROM password_entries")
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for row in rows {
+            let id: String = row.get("id");
+            let title: String = row.get("title");
+            let category: Option<String> = row.get("category");
+
+            sqlx::query("UPDATE password_entries SET title_encrypted = ?, category_encrypted = ? WHERE id = ?")
+                .bind(Self::encrypt_field(&title, &Self::entry_aad(&id, "title"), encryption_key)?)
+                .bind(
+                    category
+                        .map(|category| Self::encrypt_field(&category, &Self::entry_aad(&id, "category"), encryption_key))
+                        .transpose()?,
+                )
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("ALTER TABLE password_entries DROP COLUMN title")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE password_entries DROP COLUMN category")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        sqlx::query("PRAGMA secure_delete = OFF").execute(&mut *conn).await?;
+        // Rebuild the file so no page keeps an old copy, then drop the log
+        // frames written while both columns were present
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+
+        Ok(())
+    }
+
+    /// Move free-text entry categories into the categories table
+    ///
+    /// Each distinct name, compared ignoring case, becomes one category and
+    /// its entries refer to it by id. The default categories are added
+    /// alongside, then the old column is dropped, all in one transaction.
+    async fn migrate_entry_categories(&self, encryption_key: &SecretKey) -> Result<(), DatabaseError> {
+        if !self.column_exists("password_entries", "category_encrypted").await? {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let mut known: HashMap<String, String> = Self::read_categories(&mut tx, encryption_key)
+            .await?
+            .into_iter()
+            .map(|category| (category.name.to_lowercase(), category.id))
+            .collect();
+
+        let rows = sqlx::query("SELECT id, category_encrypted FROM password_entries WHERE category_encrypted IS NOT NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for row in rows {
+            let id: String = row.get("id");
+            let stored: String = row.get("category_encrypted");
+
+            // This column is dropped rather than upgraded, so it may still
+            // hold an envelope from before AAD binding
+            let encrypted: EncryptedData = serde_json::from_str(&stored)?;
+            let name = if encrypted.is_legacy() {
+                CryptoService::decrypt_legacy(&encrypted, encryption_key)?
+            } else {
+                CryptoService::decrypt(&encrypted, encryption_key, &Self::entry_aad(&id, "category"))?
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+
+            let category_id = match known.get(&name.to_lowercase()) {
+                Some(category_id) => category_id.clone(),
+                None => {
+                    let category_id = Self::insert_category(&mut tx, name, None, None, encryption_key).await?;
+                    known.insert(name.to_lowercase(), category_id.clone());
+                    category_id
+                }
+            };
+
+            sqlx::query("UPDATE password_entries SET category_id = ? WHERE id = ?")
+                .bind(&category_id)
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let existing: Vec<String> = known.into_keys().collect();
+        Self::insert_default_categories(&mut tx, &existing, encryption_key).await?;
+
+        sqlx::query("ALTER TABLE password_entries DROP COLUMN category_encrypted")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt entry fields written before envelopes were versioned
+    ///
+    /// Legacy fields carry no AAD. Each one is decrypted and re-encrypted bound
+    /// to its entry id and field name, all in one transaction.
+    async fn migrate_legacy_envelopes(&self, encryption_key: &SecretKey) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query(&format!("SELECT {} FROM {}", ENTRY_COLUMNS, ENTRY_TABLES))
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for row in rows {
+            let id: String = row.get("id");
+
+            for field in ENCRYPTED_ENTRY_FIELDS {
+                let column = format!("{}_encrypted", field);
+                let value: Option<String> = row.get(column.as_str());
+                let Some(value) = value else { continue };
+
+                let encrypted: EncryptedData = serde_json::from_str(&value)?;
+                if !encrypted.is_legacy() {
+                    continue;
+                }
+
+                let plaintext = CryptoService::decrypt_legacy(&encrypted, encryption_key)?;
+                let upgraded = Self::encrypt_field(&plaintext, &Self::entry_aad(&id, field), encryption_key)?;
+
+                sqlx::query(&format!("UPDATE password_entries SET {} = ? WHERE id = ?", column))
+                    .bind(&upgraded)
+                    .bind(&id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Helper function to build the AAD binding a value to its table, row and field
+    fn field_aad(table: &str, id: &str, field: &str) -> Vec<u8> {
+        format!("{}:{}:{}", table, id, field).into_bytes()
+    }
+
+    /// Helper function to build the AAD for a category name
+    fn category_aad(id: &str) -> Vec<u8> {
+        Self::field_aad("categories", id, "name")
+    }
+
+    /// Helper function to build the AAD for an encrypted setting
+    fn setting_aad(key: &str) -> Vec<u8> {
+        Self::field_aad("settings", key, "value")
+    }
+
+    /// Helper function to build the AAD for a password entry field
+    fn entry_aad(id: &str, field: &str) -> Vec<u8> {
+        Self::field_aad("password_entries", id, field)
+    }
+
+    /// Helper function to build the AAD for a custom field, bound to its
+    /// entry, position and type as well, which are stored in the clear
+    fn custom_field_aad(entry_id: &str, id: &str, position: i64, field_type: CustomFieldType, column: &str) -> Vec<u8> {
+        let row = format!("{}/{}/{}/{}", entry_id, id, position, field_type.as_str());
+        Self::field_aad("entry_fields", &row, column)
+    }
+
+    /// Helper function to build the AAD for a history value, bound to its
+    /// entry as well so it cannot be moved to another one
+    fn history_aad(entry_id: &str, id: &str, field: &str) -> Vec<u8> {
+        Self::field_aad("entry_history", &format!("{}/{}", entry_id, id), field)
+    }
+
+    /// Helper function to build the AAD for an attachment's name, bound to
+    /// its entry as well so it cannot be moved to another one
+    fn attachment_aad(entry_id: &str, id: &str) -> Vec<u8> {
+        Self::field_aad("attachments", &format!("{}/{}", entry_id, id), "name")
+    }
+
+    /// Helper function to build the AAD for an attachment chunk, bound to its
+    /// position and to whether it is the last one
+    fn attachment_chunk_aad(attachment_id: &str, index: i64, last: bool) -> Vec<u8> {
+        let field = if last { "final" } else { "data" };
+        Self::field_aad("attachment_chunks", &format!("{}/{}", attachment_id, index), field)
+    }
+
+    /// Helper function to encrypt a value and serialize its envelope for storage
+    fn encrypt_field(value: &str, aad: &[u8], encryption_key: &SecretKey) -> Result<String, DatabaseError> {
+        let encrypted = CryptoService::encrypt(value, encryption_key, aad)?;
+        Ok(serde_json::to_string(&encrypted)?)
+    }
+
+    /// Helper function to encrypt an item's type-specific fields as JSON
+    fn encrypt_item_data(id: &str, data: &ItemData, encryption_key: &SecretKey) -> Result<String, DatabaseError> {
+        let json = Zeroizing::new(serde_json::to_string(data)?);
+        Self::encrypt_field(&json, &Self::entry_aad(id, "item_data"), encryption_key)
+    }
+
+    /// Helper function to parse a stored envelope and decrypt it
+    ///
+    /// The plaintext buffer is moved out, not copied, so it is wiped when the
+    /// `PasswordEntry` that takes it is dropped.
+    fn decrypt_field(stored: &str, aad: &[u8], encryption_key: &SecretKey) -> Result<String, DatabaseError> {
+        let encrypted: EncryptedData = serde_json::from_str(stored)?;
+        let mut plaintext = CryptoService::decrypt(&encrypted, encryption_key, aad)?;
+        Ok(std::mem::take(&mut *plaintext))
+    }
+
+    /// Helper function to convert database row to EntryHistory
+    fn row_to_entry_history(
+        &self,
+        entry_id: &str,
+        row: sqlx::sqlite::SqliteRow,
+        encryption_key: &SecretKey,
+    ) -> Result<EntryHistory, DatabaseError> {
+        let id: String = row.get("id");
+        let username_encrypted: String = row.get("username_encrypted");
+        let password_encrypted: String = row.get("password_encrypted");
+        let created_at: String = row.get("created_at");
+
+        let aad = |field| Self::history_aad(entry_id, &id, field);
+        let username = Self::decrypt_field(&username_encrypted, &aad("username"), encryption_key)?;
+        let password = Self::decrypt_field(&password_encrypted, &aad("password"), encryption_key)?;
+
+        Ok(EntryHistory {
+            username,
+            password,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .with_timezone(&Utc),
+            entry_id: entry_id.to_string(),
+            id,
+        })
+    }
+
+    /// Helper function to convert database row to PasswordEntry
+    fn row_to_password_entry(
+        &self,
+        row: sqlx::sqlite::SqliteRow,
+        encryption_key: &SecretKey,
+    ) -> Result<PasswordEntry, DatabaseError> {
+        let id: String = row.get("id");
+        let title_encrypted_str: String = row.get("title_encrypted");
+        let username_encrypted_str: String = row.get("username_encrypted");
+        let password_encrypted_str: String = row.get("password_encrypted");
+        let url_encrypted_str: Option<String> = row.get("url_encrypted");
+        let notes_encrypted_str: Option<String> = row.get("notes_encrypted");
+        let category_id: Option<String> = row.get("category_id");
+        let category_name_encrypted_str: Option<String> = row.get("category_name_encrypted");
+        let totp_encrypted_str: Option<String> = row.get("totp_encrypted");
+        let item_data_encrypted_str: Option<String> = row.get("item_data_encrypted");
+        let is_favorite: bool = row.get("is_favorite");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+        let deleted_at_str: Option<String> = row.get("deleted_at");
+
+        // Decrypt fields; a blob moved from another row or column fails here
+        let title = Self::decrypt_field(&title_encrypted_str, &Self::entry_aad(&id, "title"), encryption_key)?;
+        let username = Self::decrypt_field(&username_encrypted_str, &Self::entry_aad(&id, "username"), encryption_key)?;
+        let password = Self::decrypt_field(&password_encrypted_str, &Self::entry_aad(&id, "password"), encryption_key)?;
+        let url = url_encrypted_str
+            .map(|url_str| Self::decrypt_field(&url_str, &Self::entry_aad(&id, "url"), encryption_key))
+            .transpose()?;
+        let notes = notes_encrypted_str
+            .map(|notes_str| Self::decrypt_field(&notes_str, &Self::entry_aad(&id, "notes"), encryption_key))
+            .transpose()?;
+        let category = category_id
+            .as_ref()
+            .zip(category_name_encrypted_str)
+            .map(|(category_id, name_str)| {
+                Self::decrypt_field(&name_str, &Self::category_aad(category_id), encryption_key)
+            })
+            .transpose()?;
+        let totp = totp_encrypted_str
+            .map(|totp_str| Self::decrypt_field(&totp_str, &Self::entry_aad(&id, "totp"), encryption_key))
+            .transpose()?;
+        let item_data: Option<ItemData> = item_data_encrypted_str
+            .map(|data_str| {
+                let json = Zeroizing::new(Self::decrypt_field(&data_str, &Self::entry_aad(&id, "item_data"), encryption_key)?);
+                Ok::<_, DatabaseError>(serde_json::from_str(&json)?)
+            })
+            .transpose()?;
+
+        Ok(PasswordEntry {
+            id,
+            item_type: item_data.as_ref().map_or(ItemType::Login, ItemData::item_type),
+            item_data,
+            title,
+            username,
+            password,
+            url,
+            notes,
+            category_id,
+            category,
+            totp,
+            is_favorite,
+            custom_fields: Vec::new(),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at_str)
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .with_timezone(&Utc),
+            deleted_at: deleted_at_str
+                .map(|deleted_at| DateTime::parse_from_rfc3339(&deleted_at))
+                .transpose()
+                .map_err(|e| DatabaseError::DateParsingError(e.to_string()))?
+                .map(|deleted_at| deleted_at.with_timezone(&Utc)),
+        })
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::tempdir;
+    use tempfile::{tempdir, TempDir};
+
+    /// A new file with no master password yet; the directory must outlive
+    /// the database
+    async fn new_db() -> (TempDir, Database) {
+        let temp_dir = tempdir().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db").to_str().unwrap()).await.unwrap();
+        (temp_dir, db)
+    }
+
+    /// A new vault, unlocked with `test_master_password`
+    async fn unlocked_db() -> (TempDir, Database, SecretKey) {
+        let (temp_dir, db) = new_db().await;
+        db.set_master_password("test_master_password", &KdfParams::default(), false).await.unwrap();
+        let key = db.verify_master_password("test_master_password").await.unwrap();
+        (temp_dir, db, key)
+    }
+
+    /// A login with only the fields every entry has
+    fn login(title: &str, username: &str, password: &str) -> CreatePasswordEntry {
+        let mut entry = CreatePasswordEntry::default();
+        entry.title = title.to_string();
+        entry.username = username.to_string();
+        entry.password = password.to_string();
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_database_operations() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+        assert!(db.has_master_password().await.unwrap());
+
+        // Test password entry operations
+        let category = CreateCategory {
+            name: "Test".to_string(),
+            color: None,
+            icon: None,
+        };
+        let category_id = db.create_category(&category, &key).await.unwrap();
+
+        let mut entry = login("Test Entry", "testuser", "testpass");
+        entry.url = Some("https://example.com".to_string());
+        entry.notes = Some("Test notes".to_string());
+        entry.category_id = Some(category_id);
+        entry.totp = Some("otpauth://totp/Test?secret=GEZDGNBVGY3TQOJQ".to_string());
+
+        let entry_id = db.create_entry(entry, &key).await.unwrap();
+        
+        let retrieved_entry = db.get_entry_by_id(&entry_id, &key).await.unwrap();
+        assert_eq!(retrieved_entry.title, "Test Entry");
+        assert_eq!(retrieved_entry.username, "testuser");
+        assert_eq!(retrieved_entry.category.as_deref(), Some("Test"));
+        assert_eq!(retrieved_entry.totp.as_deref(), Some("otpauth://totp/Test?secret=GEZDGNBVGY3TQOJQ"));
+
+        let all_entries = db.get_all_entries(&key).await.unwrap();
+        assert_eq!(all_entries.len(), 1);
+
+        db.delete_entry(&entry_id).await.unwrap();
+        
+        let all_entries_after_delete = db.get_all_entries(&key).await.unwrap();
+        assert_eq!(all_entries_after_delete.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_change_master_password() {
+        let (_temp_dir, db, old_key) = unlocked_db().await;
+
+        let mut entry = login("Test Entry", "testuser", "testpass");
+        entry.url = Some("https://example.com".to_string());
+        let entry_id = db.create_entry(entry, &old_key).await.unwrap();
+
+        assert!(matches!(
+            db.change_master_password("wrong_password", "new_master_password").await,
+            Err(DatabaseError::InvalidMasterPassword)
+        ));
+
+        let new_key = db
+            .change_master_password("test_master_password", "new_master_password")
+            .await
+            .unwrap();
+        assert_eq!(old_key.as_bytes(), new_key.as_bytes());
+
+        assert!(matches!(
+            db.verify_master_password("test_master_password").await,
+            Err(DatabaseError::InvalidMasterPassword)
+        ));
+        assert_eq!(db.verify_master_password("new_master_password").await.unwrap().as_bytes(), new_key.as_bytes());
+
+        let retrieved_entry = db.get_entry_by_id(&entry_id, &new_key).await.unwrap();
+        assert_eq!(retrieved_entry.password, "testpass");
+        assert_eq!(retrieved_entry.url.as_deref(), Some("https://example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_vault_key_migration() {
+        let (_temp_dir, db) = new_db().await;
+
+        // Vaults created before the key hierarchy encrypt with the derived key
+        let salt = CryptoService::generate_salt();
+        let legacy_key =
+            CryptoService::derive_key_from_password("legacy_password", &salt, &KdfParams::default()).unwrap();
+        sqlx::query("INSERT INTO master_passwords (password_hash, salt, created_at) VALUES (?, ?, ?)")
+            .bind(CryptoService::hash_password("legacy_password", &KdfParams::default()).unwrap())
+            .bind(base64::engine::general_purpose::STANDARD.encode(salt))
+            .bind(Utc::now().to_rfc3339())
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let entry = login("Legacy Entry", "legacyuser", "legacypass");
+        let entry_id = db.create_entry(entry, &legacy_key).await.unwrap();
+
+        let key = db.verify_master_password("legacy_password").await.unwrap();
+        assert_eq!(key.as_bytes(), legacy_key.as_bytes());
+
+        let row = sqlx::query("SELECT wrapped_key FROM master_passwords")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        let wrapped_key: Option<String> = row.get("wrapped_key");
+        assert!(wrapped_key.is_some());
+
+        // The migrated vault unlocks to the same key on the next login
+        let key = db.verify_master_password("legacy_password").await.unwrap();
+        let retrieved_entry = db.get_entry_by_id(&entry_id, &key).await.unwrap();
+        assert_eq!(retrieved_entry.password, "legacypass");
+    }
+
+    /// Encrypt a value the way entries were stored before versioned envelopes
+    fn legacy_encrypt(data: &str, key: &SecretKey) -> String {
+        use aes_gcm::{aead::{Aead, AeadCore, KeyInit, OsRng}, Aes256Gcm};
+
+        let cipher = Aes256Gcm::new(key.as_bytes().into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, data.as_bytes()).unwrap();
+
+        serde_json::json!({
+            "ciphertext": base64::engine::general_purpose::STANDARD.encode(ciphertext),
+            "nonce": base64::engine::general_purpose::STANDARD.encode(nonce),
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_legacy_envelope_migration() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO password_entries
+            (id, title_encrypted, username_encrypted, password_encrypted, url_encrypted, notes_encrypted, created_at, updated_at)
+            VALUES ('legacy', ?, ?, ?, NULL, ?, ?, ?)
+            "#,
+        )
+        .bind(legacy_encrypt("Legacy Entry", &key))
+        .bind(legacy_encrypt("legacyuser", &key))
+        .bind(legacy_encrypt("legacypass", &key))
+        .bind(legacy_encrypt("legacy notes", &key))
+        .bind(&now)
+        .bind(&now)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        // Legacy envelopes are refused on normal reads until migrated at unlock
+        assert!(db.get_entry_by_id("legacy", &key).await.is_err());
+
+        let key = db.verify_master_password("test_master_password").await.unwrap();
+        let entry = db.get_entry_by_id("legacy", &key).await.unwrap();
+        assert_eq!(entry.title, "Legacy Entry");
+        assert_eq!(entry.username, "legacyuser");
+        assert_eq!(entry.password, "legacypass");
+        assert_eq!(entry.notes.as_deref(), Some("legacy notes"));
+
+        let row = sqlx::query("SELECT password_encrypted FROM password_entries WHERE id = 'legacy'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        let stored: EncryptedData = serde_json::from_str(row.get("password_encrypted")).unwrap();
+        assert!(!stored.is_legacy());
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_metadata_migration() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        // Lay out the original vault, where title and category were plaintext
+        let salt = CryptoService::generate_salt();
+        let legacy_key =
+            CryptoService::derive_key_from_password("legacy_password", &salt, &KdfParams::default()).unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", db_path.display())).await.unwrap();
+        for statement in [
+            "CREATE TABLE master_passwords (id INTEGER PRIMARY KEY AUTOINCREMENT, password_hash TEXT NOT NULL, salt TEXT NOT NULL, created_at TEXT NOT NULL)",
+            "CREATE TABLE password_entries (id TEXT PRIMARY KEY, title TEXT NOT NULL, username_encrypted TEXT NOT NULL, password_encrypted TEXT NOT NULL, url_encrypted TEXT, notes_encrypted TEXT, category TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        sqlx::query("INSERT INTO master_passwords (password_hash, salt, created_at) VALUES (?, ?, ?)")
+            .bind(CryptoService::hash_password("legacy_password", &KdfParams::default()).unwrap())
+            .bind(base64::engine::general_purpose::STANDARD.encode(salt))
+            .bind(&now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO password_entries
+            (id, title, username_encrypted, password_encrypted, category, created_at, updated_at)
+            VALUES ('old', 'My Bank', ?, ?, 'Finance', ?, ?)
+            "#,
+        )
+        .bind(legacy_encrypt("bankuser", &legacy_key))
+        .bind(legacy_encrypt("bankpass", &legacy_key))
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Enough rows that encrypting them moves data off the original pages
+        for i in 0..50 {
+            sqlx::query(
+                r#"
+                INSERT INTO password_entries
+                (id, title, username_encrypted, password_encrypted, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(format!("filler-{i}"))
+            .bind(format!("My Bank branch {i}"))
+            .bind(legacy_encrypt("user", &legacy_key))
+            .bind(legacy_encrypt("pass", &legacy_key))
+            .bind(&now)
+            .bind(&now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        pool.close().await;
+
+        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+        let key = db.verify_master_password("legacy_password").await.unwrap();
+        assert!(!db.column_exists("password_entries", "title").await.unwrap());
+        assert!(!db.column_exists("password_entries", "category").await.unwrap());
+
+        // No copy of the plaintext is left in the vault file or its log
+        for path in [db_path.clone(), temp_dir.path().join("test.db-wal")] {
+            let bytes = std::fs::read(&path).unwrap_or_default();
+            assert!(!bytes.windows(7).any(|window| window == b"My Bank"), "{}", path.display());
+        }
+
+        let entry = db.get_entry_by_id("old", &key).await.unwrap();
+        assert_eq!(entry.title, "My Bank");
+        assert_eq!(entry.category.as_deref(), Some("Finance"));
+        assert_eq!(entry.password, "bankpass");
+
+        // The free-text category became a category of its own
+        assert!(!db.column_exists("password_entries", "category_encrypted").await.unwrap());
+        let categories = db.get_categories(&key).await.unwrap();
+        assert_eq!(categories.len(), DEFAULT_CATEGORIES.len() + 1);
+        assert_eq!(entry.category_id.as_deref(), categories.iter().find(|c| c.name == "Finance").map(|c| c.id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_search_entries() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let finance = CreateCategory {
+            name: "Finance".to_string(),
+            color: None,
+            icon: None,
+        };
+        let finance = db.create_category(&finance, &key).await.unwrap();
+        let work = db.get_categories(&key).await.unwrap().into_iter().find(|c| c.name == "Work").unwrap().id;
+
+        for (title, category_id) in [("GitHub", Some(work)), ("Bank of Mars", Some(finance)), ("mail", None)] {
+            let mut entry = login(title, "user", "pass");
+            entry.category_id = category_id;
+            db.create_entry(entry, &key).await.unwrap();
+        }
+
+        let titles = |entries: Vec<PasswordEntry>| entries.iter().map(|e| e.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(db.search_entries("git", &key).await.unwrap()), ["GitHub"]);
+        assert_eq!(titles(db.search_entries("FINANCE", &key).await.unwrap()), ["Bank of Mars"]);
+        assert_eq!(titles(db.search_entries("", &key).await.unwrap()), ["Bank of Mars", "GitHub", "mail"]);
+
+        // Nothing readable is left in the table
+        let row = sqlx::query("SELECT title_encrypted FROM password_entries LIMIT 1")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        let stored: String = row.get("title_encrypted");
+        assert!(!stored.contains("GitHub") && !stored.contains("Bank") && !stored.contains("mail"));
+    }
 
     #[tokio::test]
-    async fn test_database_operations() {
-        let temp_dir = tempdir().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+    async fn test_favorite_entries() {
+        let (_temp_dir, db, key) = unlocked_db().await;
 
-        // Test master password
-        let master_password = "test_master_password";
-        db.set_master_password(master_password).await.unwrap();
-        
-        assert!(db.has_master_password().await.unwrap());
-        
-        let key = db.verify_master_password(master_password).await.unwrap();
+        let mut ids = Vec::new();
+        for (title, is_favorite) in [("Zebra", true), ("apple", true), ("Mail", false)] {
+            let mut entry = login(title, "user", "pass");
+            entry.is_favorite = is_favorite;
+            ids.push(db.create_entry(entry, &key).await.unwrap());
+        }
 
-        // Test password entry operations
-        let entry = CreatePasswordEntry {
-            title: "Test Entry".to_string(),
-            username: "testuser".to_string(),
-            password: "testpass".to_string(),
-            url: Some("https://example.com".to_string()),
-            notes: Some("Test notes".to_string()),
-            category: Some("Test".to_string()),
+        let titles = |entries: Vec<PasswordEntry>| entries.iter().map(|entry| entry.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(db.get_favorite_entries(&key).await.unwrap()), ["apple", "Zebra"]);
+
+        // Starring survives a reload and does not count as an edit
+        let before = db.get_entry_by_id(&ids[2], &key).await.unwrap().updated_at;
+        let mut star = UpdatePasswordEntry::default();
+        star.is_favorite = Some(true);
+        db.update_entry(&ids[2], star, &key).await.unwrap();
+        let starred = db.get_entry_by_id(&ids[2], &key).await.unwrap();
+        assert!(starred.is_favorite);
+        assert_eq!(starred.updated_at, before);
+
+        let mut unstar = UpdatePasswordEntry::default();
+        unstar.is_favorite = Some(false);
+        db.update_entry(&ids[0], unstar, &key).await.unwrap();
+        assert_eq!(titles(db.get_favorite_entries(&key).await.unwrap()), ["apple", "Mail"]);
+    }
+
+    #[tokio::test]
+    async fn test_entry_history() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let entry = login("Mail", "first_user", "first_pass");
+        let id = db.create_entry(entry, &key).await.unwrap();
+
+        let edit = |username: Option<&str>, password: Option<&str>, title: Option<&str>| {
+            let mut update = UpdatePasswordEntry::default();
+            update.title = title.map(str::to_string);
+            update.username = username.map(str::to_string);
+            update.password = password.map(str::to_string);
+            update
+        };
+
+        // Only a changed username or password is kept
+        db.update_entry(&id, edit(None, None, Some("Mail account")), &key).await.unwrap();
+        db.update_entry(&id, edit(Some("first_user"), Some("first_pass"), None), &key).await.unwrap();
+        assert!(db.get_entry_history(&id, &key).await.unwrap().is_empty());
+
+        db.update_entry(&id, edit(None, Some("second_pass"), None), &key).await.unwrap();
+        db.update_entry(&id, edit(Some("second_user"), Some("third_pass"), None), &key).await.unwrap();
+
+        let history = db.get_entry_history(&id, &key).await.unwrap();
+        let values = |history: &[EntryHistory]| {
+            history.iter().map(|item| (item.username.clone(), item.password.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values(&history),
+            [("first_user".to_string(), "second_pass".to_string()), ("first_user".to_string(), "first_pass".to_string())]
+        );
+
+        // Restoring is an edit of its own, so the values it replaces are kept
+        db.restore_entry_version(&id, &history[1].id, &key).await.unwrap();
+        let restored = db.get_entry_by_id(&id, &key).await.unwrap();
+        assert_eq!((restored.username.as_str(), restored.password.as_str()), ("first_user", "first_pass"));
+        let history = db.get_entry_history(&id, &key).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!((history[0].username.as_str(), history[0].password.as_str()), ("second_user", "third_pass"));
+
+        // A history value belongs to its entry
+        let other_id = db
+            .create_entry(login("Other", "other", "other"), &key)
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.restore_entry_version(&other_id, &history[0].id, &key).await,
+            Err(DatabaseError::EntryNotFound)
+        ));
+        sqlx::query("UPDATE entry_history SET entry_id = ? WHERE id = ?")
+            .bind(&other_id)
+            .bind(&history[0].id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db.get_entry_history(&other_id, &key).await.is_err());
+        sqlx::query("UPDATE entry_history SET entry_id = ? WHERE id = ?")
+            .bind(&id)
+            .bind(&history[0].id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(db.purge_entry_history(1).await.unwrap(), 2);
+        let history = db.get_entry_history(&id, &key).await.unwrap();
+        assert_eq!(values(&history), [("second_user".to_string(), "third_pass".to_string())]);
+
+        // History goes with its entry once it leaves the trash
+        db.delete_entry(&id).await.unwrap();
+        db.empty_trash().await.unwrap();
+        assert!(matches!(db.get_entry_history(&id, &key).await, Err(DatabaseError::EntryNotFound)));
+        let row = sqlx::query("SELECT COUNT(*) as count FROM entry_history").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(row.get::<i64, _>("count"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_custom_fields() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let field = |name: &str, value: &str, field_type| CustomField {
+            name: name.to_string(),
+            value: value.to_string(),
+            field_type,
+        };
+        let mut entry = login("Bank", "user", "pass");
+        entry.custom_fields = vec![
+            field("Account number", "12-3456-789", CustomFieldType::Text),
+            field("PIN", "4821", CustomFieldType::Hidden),
+            field("Paperless", "true", CustomFieldType::Boolean),
+            field("Card expires", "2027-03-01", CustomFieldType::Date),
+        ];
+        let id = db.create_entry(entry, &key).await.unwrap();
+
+        let summary = |entry: &PasswordEntry| {
+            entry
+                .custom_fields
+                .iter()
+                .map(|field| (field.name.clone(), field.value.clone(), field.field_type))
+                .collect::<Vec<_>>()
         };
+        let stored = db.get_entry_by_id(&id, &key).await.unwrap();
+        assert_eq!(summary(&stored)[0], ("Account number".to_string(), "12-3456-789".to_string(), CustomFieldType::Text));
+        assert_eq!(summary(&stored)[1], ("PIN".to_string(), "4821".to_string(), CustomFieldType::Hidden));
+        assert_eq!(summary(&stored), summary(&db.get_all_entries(&key).await.unwrap()[0]));
+
+        // Names and visible values match; hidden values do not
+        assert_eq!(db.search_entries("3456", &key).await.unwrap().len(), 1);
+        assert_eq!(db.search_entries("pin", &key).await.unwrap().len(), 1);
+        assert!(db.search_entries("4821", &key).await.unwrap().is_empty());
+
+        // An update replaces the list and its order, and counts as an edit
+        let mut update = UpdatePasswordEntry::default();
+        update.custom_fields = Some(vec![
+            field("Security answer", "Rex", CustomFieldType::Hidden),
+            field("Portal", "https://bank.example", CustomFieldType::Url),
+        ]);
+        db.update_entry(&id, update, &key).await.unwrap();
+        let updated = db.get_entry_by_id(&id, &key).await.unwrap();
+        assert_eq!(
+            summary(&updated),
+            [
+                ("Security answer".to_string(), "Rex".to_string(), CustomFieldType::Hidden),
+                ("Portal".to_string(), "https://bank.example".to_string(), CustomFieldType::Url),
+            ]
+        );
+        assert!(updated.updated_at > stored.updated_at);
+
+        // Each value is bound to its entry and field, and to the position
+        // and type stored beside it
+        for (tamper, restore) in [
+            (
+                "UPDATE entry_fields SET field_type = 'text' WHERE position = 0",
+                "UPDATE entry_fields SET field_type = 'hidden' WHERE position = 0",
+            ),
+            ("UPDATE entry_fields SET position = 1 - position", "UPDATE entry_fields SET position = 1 - position"),
+        ] {
+            sqlx::query(tamper).execute(&db.pool).await.unwrap();
+            assert!(db.get_entry_by_id(&id, &key).await.is_err(), "{tamper}");
+            sqlx::query(restore).execute(&db.pool).await.unwrap();
+        }
+        assert_eq!(summary(&db.get_entry_by_id(&id, &key).await.unwrap()), summary(&updated));
+        sqlx::query("UPDATE entry_fields SET value_encrypted = name_encrypted WHERE position = 0")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db.get_entry_by_id(&id, &key).await.is_err());
+
+        db.delete_entry(&id).await.unwrap();
+        db.empty_trash().await.unwrap();
+        let row = sqlx::query("SELECT COUNT(*) as count FROM entry_fields").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(row.get::<i64, _>("count"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_typed_items() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let card = ItemData::Card(crate::items::CardData {
+            cardholder_name: "Alex Doe".to_string(),
+            number: "4111 1111 1111 1111".to_string(),
+            expiry: "03/27".to_string(),
+            security_code: Some("123".to_string()),
+        });
+        let mut entry = login("Visa", "", "");
+        entry.item_data = Some(card);
+        let id = db.create_entry(entry, &key).await.unwrap();
+
+        let stored = db.get_entry_by_id(&id, &key).await.unwrap();
+        assert_eq!(stored.item_type, ItemType::Card);
+        let Some(ItemData::Card(card)) = &stored.item_data else { panic!("expected a card") };
+        assert_eq!(card.number, "4111 1111 1111 1111");
+        assert_eq!(card.security_code.as_deref(), Some("123"));
+
+        // Neither the number nor the type is readable in the file
+        let row = sqlx::query("SELECT item_data_encrypted FROM password_entries").fetch_one(&db.pool).await.unwrap();
+        let raw: String = row.get("item_data_encrypted");
+        assert!(!raw.contains("4111") && !raw.contains("card"));
+
+        // Blobs are bound to their entry like the other fields
+        let other = db.create_entry(login("Other", "", ""), &key).await.unwrap();
+        assert_eq!(db.get_entry_by_id(&other, &key).await.unwrap().item_type, ItemType::Login);
+        sqlx::query("UPDATE password_entries SET item_data_encrypted = ? WHERE id = ?")
+            .bind(&raw)
+            .bind(&other)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db.get_entry_by_id(&other, &key).await.is_err());
+        sqlx::query("UPDATE password_entries SET item_data_encrypted = NULL WHERE id = ?")
+            .bind(&other)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        // An update can turn an entry into another type of item
+        let mut update = UpdatePasswordEntry::default();
+        update.item_data = Some(Some(ItemData::SecureNote));
+        db.update_entry(&id, update, &key).await.unwrap();
+        assert_eq!(db.get_entry_by_id(&id, &key).await.unwrap().item_type, ItemType::SecureNote);
+
+        // Leaving item_data out keeps the type; null turns it back into a login
+        let update: UpdatePasswordEntry = serde_json::from_str(r#"{"title": "Visa card"}"#).unwrap();
+        assert!(update.item_data.is_none());
+        db.update_entry(&id, update, &key).await.unwrap();
+        assert_eq!(db.get_entry_by_id(&id, &key).await.unwrap().item_type, ItemType::SecureNote);
 
+        let update: UpdatePasswordEntry = serde_json::from_str(r#"{"item_data": null}"#).unwrap();
+        assert!(matches!(update.item_data, Some(None)));
+        db.update_entry(&id, update, &key).await.unwrap();
+        let stored = db.get_entry_by_id(&id, &key).await.unwrap();
+        assert_eq!(stored.item_type, ItemType::Login);
+        assert!(stored.item_data.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_attachments() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let entry = login("Server", "root", "pass");
         let entry_id = db.create_entry(entry, &key).await.unwrap();
-        
-        let retrieved_entry = db.get_entry_by_id(&entry_id, &key).await.unwrap();
-        assert_eq!(retrieved_entry.title, "Test Entry");
-        assert_eq!(retrieved_entry.username, "testuser");
+        let chunk_count = |id: String| {
+            let pool = db.pool.clone();
+            async move {
+                let row = sqlx::query("SELECT COUNT(*) as count FROM attachment_chunks WHERE attachment_id = ?")
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+                row.get::<i64, _>("count")
+            }
+        };
 
-        let all_entries = db.get_all_entries(&key).await.unwrap();
-        assert_eq!(all_entries.len(), 1);
+        // Several chunks, an exact multiple of the chunk size and an empty file
+        let contents: Vec<u8> = (0..ATTACHMENT_CHUNK_SIZE * 2 + 100).map(|i| (i % 251) as u8).collect();
+        let key_file = db.add_attachment(&entry_id, "id_ed25519", std::io::Cursor::new(contents.clone()), &key).await.unwrap();
+        assert_eq!(key_file.size, contents.len() as u64);
+        assert_eq!(chunk_count(key_file.id.clone()).await, 3);
+        let exact = db.add_attachment(&entry_id, "exact.bin", std::io::Cursor::new(contents[..ATTACHMENT_CHUNK_SIZE].to_vec()), &key).await.unwrap();
+        assert_eq!(chunk_count(exact.id.clone()).await, 2);
+        let empty = db.add_attachment(&entry_id, "empty.txt", std::io::empty(), &key).await.unwrap();
+
+        let listed = db.get_attachments(&entry_id, &key).await.unwrap();
+        assert_eq!(listed.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["id_ed25519", "exact.bin", "empty.txt"]);
+        assert_eq!(db.get_attachment(&exact.id, &key).await.unwrap().name, "exact.bin");
+        assert!(matches!(db.get_attachment("missing", &key).await, Err(DatabaseError::AttachmentNotFound)));
+
+        for (attachment, expected) in [(&key_file, &contents[..]), (&exact, &contents[..ATTACHMENT_CHUNK_SIZE]), (&empty, &[][..])] {
+            let mut exported = Vec::new();
+            let written = db.export_attachment(&attachment.id, &mut exported, &key).await.unwrap();
+            assert_eq!(written, expected.len() as u64);
+            assert_eq!(exported, expected);
+        }
+
+        // Chunks are stored as raw bytes, not base64 JSON
+        let row = sqlx::query("SELECT typeof(data_encrypted) AS kind, length(data_encrypted) AS len FROM attachment_chunks WHERE attachment_id = ? AND chunk_index = 0")
+            .bind(&key_file.id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("kind"), "blob");
+        // Version byte, nonce and GCM tag
+        assert_eq!(row.get::<i64, _>("len") as usize, ATTACHMENT_CHUNK_SIZE + 1 + 12 + 16);
 
+        // Names are encrypted like everything else
+        let row = sqlx::query("SELECT name_encrypted FROM attachments WHERE id = ?")
+            .bind(&key_file.id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert!(!row.get::<String, _>("name_encrypted").contains("id_ed25519"));
+
+        // Dropping the last chunk or replaying one in another position is caught
+        sqlx::query("DELETE FROM attachment_chunks WHERE attachment_id = ? AND chunk_index = 2")
+            .bind(&key_file.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let result = db.export_attachment(&key_file.id, &mut Vec::new(), &key).await;
+        assert!(matches!(result, Err(DatabaseError::CorruptAttachment)));
+        sqlx::query("UPDATE attachments SET chunk_count = 2 WHERE id = ?")
+            .bind(&key_file.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db.export_attachment(&key_file.id, &mut Vec::new(), &key).await.is_err());
+        sqlx::query(
+            "UPDATE attachment_chunks SET data_encrypted = \
+             (SELECT data_encrypted FROM attachment_chunks WHERE attachment_id = ?1 AND chunk_index = 0) \
+             WHERE attachment_id = ?1 AND chunk_index = 1",
+        )
+        .bind(&exact.id)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        assert!(db.export_attachment(&exact.id, &mut Vec::new(), &key).await.is_err());
+
+        // Files over the cap are refused and leave nothing behind
+        let too_large = std::io::repeat(7).take(MAX_ATTACHMENT_SIZE + 1);
+        let result = db.add_attachment(&entry_id, "huge.iso", too_large, &key).await;
+        assert!(matches!(result, Err(DatabaseError::AttachmentTooLarge { .. })));
+        assert_eq!(db.get_attachments(&entry_id, &key).await.unwrap().len(), 3);
+
+        db.delete_attachment(&empty.id).await.unwrap();
+        assert!(matches!(db.delete_attachment(&empty.id).await, Err(DatabaseError::AttachmentNotFound)));
+        assert_eq!(chunk_count(empty.id.clone()).await, 0);
+
+        // Attachments stay while the entry is in the trash and go when it is purged
         db.delete_entry(&entry_id).await.unwrap();
-        
-        let all_entries_after_delete = db.get_all_entries(&key).await.unwrap();
-        assert_eq!(all_entries_after_delete.len(), 0);
+        let result = db.add_attachment(&entry_id, "late.txt", &b"late"[..], &key).await;
+        assert!(matches!(result, Err(DatabaseError::EntryNotFound)));
+        assert_eq!(db.get_attachments(&entry_id, &key).await.unwrap().len(), 2);
+        db.empty_trash().await.unwrap();
+        for table in ["attachments", "attachment_chunks"] {
+            let row = sqlx::query(&format!("SELECT COUNT(*) as count FROM {}", table))
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+            assert_eq!(row.get::<i64, _>("count"), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trash() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let mut ids = Vec::new();
+        for title in ["Bank", "Mail", "Old forum"] {
+            let mut entry = login(title, "user", "pass");
+            entry.is_favorite = true;
+            ids.push(db.create_entry(entry, &key).await.unwrap());
+        }
+
+        db.delete_entry(&ids[1]).await.unwrap();
+        db.delete_entry(&ids[2]).await.unwrap();
+        assert!(matches!(db.delete_entry(&ids[1]).await, Err(DatabaseError::EntryNotFound)));
+
+        // Trashed entries are hidden everywhere but the trash
+        let titles = |entries: Vec<PasswordEntry>| entries.iter().map(|entry| entry.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(db.get_all_entries(&key).await.unwrap()), ["Bank"]);
+        assert_eq!(titles(db.get_favorite_entries(&key).await.unwrap()), ["Bank"]);
+        assert!(db.search_entries("mail", &key).await.unwrap().is_empty());
+        assert!(matches!(db.get_entry_by_id(&ids[1], &key).await, Err(DatabaseError::EntryNotFound)));
+        assert_eq!(titles(db.get_trashed_entries(&key).await.unwrap()), ["Old forum", "Mail"]);
+
+        db.restore_entry(&ids[1]).await.unwrap();
+        assert!(matches!(db.restore_entry(&ids[1]).await, Err(DatabaseError::EntryNotFound)));
+        let restored = db.get_entry_by_id(&ids[1], &key).await.unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert_eq!(titles(db.search_entries("mail", &key).await.unwrap()), ["Mail"]);
+
+        // Entries past the retention period are purged at unlock
+        assert_eq!(db.get_trash_settings().await.unwrap(), TrashSettings::default());
+        db.set_trash_settings(&TrashSettings { retention_days: 7 }).await.unwrap();
+        db.delete_entry(&ids[1]).await.unwrap();
+        sqlx::query("UPDATE password_entries SET deleted_at = ? WHERE id = ?")
+            .bind((Utc::now() - chrono::Duration::days(8)).to_rfc3339())
+            .bind(&ids[2])
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let key = db.verify_master_password("test_master_password").await.unwrap();
+        assert_eq!(titles(db.get_trashed_entries(&key).await.unwrap()), ["Mail"]);
+
+        assert_eq!(db.empty_trash().await.unwrap(), 1);
+        assert!(db.get_trashed_entries(&key).await.unwrap().is_empty());
+        assert_eq!(titles(db.get_all_entries(&key).await.unwrap()), ["Bank"]);
+    }
+
+    #[tokio::test]
+    async fn test_categories() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        // New vaults start with the defaults
+        let categories = db.get_categories(&key).await.unwrap();
+        assert_eq!(categories.len(), DEFAULT_CATEGORIES.len());
+        let work = categories.iter().find(|c| c.name == "Work").unwrap().id.clone();
+
+        let travel = CreateCategory {
+            name: "Travel".to_string(),
+            color: Some("#0ea5e9".to_string()),
+            icon: Some("✈️".to_string()),
+        };
+        let travel_id = db.create_category(&travel, &key).await.unwrap();
+        let duplicate = CreateCategory {
+            name: " travel ".to_string(),
+            color: None,
+            icon: None,
+        };
+        assert!(matches!(
+            db.create_category(&duplicate, &key).await,
+            Err(DatabaseError::DuplicateCategory(_))
+        ));
+
+        let mut entry = login("Airline", "user", "pass");
+        entry.category_id = Some(travel_id.clone());
+        let entry_id = db.create_entry(entry, &key).await.unwrap();
+
+        // A rename shows on the entry without rewriting it
+        let rename = UpdateCategory {
+            name: Some("Trips".to_string()),
+            color: None,
+            icon: None,
+        };
+        db.update_category(&travel_id, &rename, &key).await.unwrap();
+        assert_eq!(db.get_entry_by_id(&entry_id, &key).await.unwrap().category.as_deref(), Some("Trips"));
+        let clash = UpdateCategory {
+            name: Some("WORK".to_string()),
+            color: None,
+            icon: None,
+        };
+        assert!(matches!(
+            db.update_category(&travel_id, &clash, &key).await,
+            Err(DatabaseError::DuplicateCategory(_))
+        ));
+
+        // The reference is enforced by the database too
+        assert!(sqlx::query("DELETE FROM categories WHERE id = ?").bind(&travel_id).execute(&db.pool).await.is_err());
+
+        let to_self = CategoryDeletion::Reassign { category_id: travel_id.clone() };
+        assert!(matches!(db.delete_category(&travel_id, &to_self).await, Err(DatabaseError::CategoryNotFound)));
+
+        let to_work = CategoryDeletion::Reassign { category_id: work.clone() };
+        db.delete_category(&travel_id, &to_work).await.unwrap();
+        let moved = db.get_entry_by_id(&entry_id, &key).await.unwrap();
+        assert_eq!((moved.category_id.as_deref(), moved.category.as_deref()), (Some(work.as_str()), Some("Work")));
+
+        db.delete_category(&work, &CategoryDeletion::Clear).await.unwrap();
+        let cleared = db.get_entry_by_id(&entry_id, &key).await.unwrap();
+        assert!(cleared.category_id.is_none() && cleared.category.is_none());
+        assert!(matches!(db.delete_category(&work, &CategoryDeletion::Clear).await, Err(DatabaseError::CategoryNotFound)));
+
+        let mut orphan = login("Orphan", "user", "pass");
+        orphan.category_id = Some(work);
+        assert!(matches!(db.create_entry(orphan, &key).await, Err(DatabaseError::CategoryNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_swapped_ciphertexts_are_rejected() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let mut ids = Vec::new();
+        for name in ["first", "second"] {
+            let entry = login(name, &format!("{}user", name), &format!("{}pass", name));
+            ids.push(db.create_entry(entry, &key).await.unwrap());
+        }
+
+        // Copy the second entry's password blob into the first entry
+        sqlx::query(
+            "UPDATE password_entries SET password_encrypted = (SELECT password_encrypted FROM password_entries WHERE id = ?) WHERE id = ?"
+        )
+        .bind(&ids[1])
+        .bind(&ids[0])
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        assert!(db.get_entry_by_id(&ids[0], &key).await.is_err());
+
+        // Swap the username and password columns within the second entry
+        sqlx::query(
+            "UPDATE password_entries SET username_encrypted = password_encrypted, password_encrypted = username_encrypted WHERE id = ?"
+        )
+        .bind(&ids[1])
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        assert!(db.get_entry_by_id(&ids[1], &key).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_kdf_params() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+        assert_eq!(db.get_kdf_params().await.unwrap(), KdfParams::default());
+
+        let stronger = KdfParams { iterations: 3, ..KdfParams::default() };
+        assert!(db.upgrade_kdf_params("wrong_password", &stronger).await.is_err());
+        db.upgrade_kdf_params("test_master_password", &stronger).await.unwrap();
+
+        assert_eq!(db.get_kdf_params().await.unwrap(), stronger);
+        assert_eq!(db.verify_master_password("test_master_password").await.unwrap().as_bytes(), key.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_auto_lock_settings() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        assert_eq!(db.get_auto_lock_settings(&key).await.unwrap(), AutoLockSettings::default());
+
+        let settings = AutoLockSettings { enabled: false, idle_minutes: 5 };
+        db.set_auto_lock_settings(&settings, &key).await.unwrap();
+        assert_eq!(db.get_auto_lock_settings(&key).await.unwrap(), settings);
+
+        let settings = AutoLockSettings { enabled: true, idle_minutes: 30 };
+        db.set_auto_lock_settings(&settings, &key).await.unwrap();
+        assert_eq!(db.get_auto_lock_settings(&key).await.unwrap(), settings);
+
+        // Turning auto-lock off by editing the file is caught
+        db.set_setting("auto_lock", r#"{"enabled":false,"idle_minutes":30}"#).await.unwrap();
+        assert!(db.get_auto_lock_settings(&key).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_backoff() {
+        let (_temp_dir, db, _key) = unlocked_db().await;
+
+        for _ in 0..FREE_UNLOCK_ATTEMPTS {
+            assert!(matches!(
+                db.verify_master_password("wrong_password").await,
+                Err(DatabaseError::InvalidMasterPassword)
+            ));
+        }
+
+        // Even the right password is refused until the backoff has passed
+        match db.verify_master_password("test_master_password").await {
+            Err(DatabaseError::TooManyAttempts { retry_after_secs }) => {
+                assert!(retry_after_secs > 0 && retry_after_secs <= BASE_LOCKOUT_SECS);
+            }
+            other => panic!("expected lockout, got {:?}", other.map(|_| ())),
+        }
+
+        sqlx::query("UPDATE master_passwords SET locked_until = NULL")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.verify_master_password("test_master_password").await.unwrap();
+
+        let row = sqlx::query("SELECT failed_attempts, locked_until FROM master_passwords")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<i64, _>("failed_attempts"), 0);
+        assert!(row.get::<Option<String>, _>("locked_until").is_none());
+    }
+
+    #[test]
+    fn test_lockout_delay() {
+        assert_eq!(Database::lockout_delay(1), Duration::ZERO);
+        assert_eq!(Database::lockout_delay(FREE_UNLOCK_ATTEMPTS), Duration::from_secs(BASE_LOCKOUT_SECS));
+        assert_eq!(Database::lockout_delay(FREE_UNLOCK_ATTEMPTS + 2), Duration::from_secs(BASE_LOCKOUT_SECS * 4));
+        assert_eq!(Database::lockout_delay(1000), Duration::from_secs(MAX_LOCKOUT_SECS));
+    }
+
+    #[tokio::test]
+    async fn test_wipe_after_failures() {
+        let (temp_dir, db, key) = unlocked_db().await;
+
+        let entry = login("Bank", "user", "secret");
+        db.create_entry(entry, &key).await.unwrap();
+        db.set_auto_lock_settings(&AutoLockSettings { enabled: false, idle_minutes: 5 }, &key).await.unwrap();
+
+        db.set_lockout_settings(&LockoutSettings { wipe_after_failures: Some(5) }).await.unwrap();
+
+        let secrets = sqlx::query("SELECT wrapped_key, password_encrypted FROM master_passwords, password_entries")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        let secrets: Vec<String> = vec![secrets.get("wrapped_key"), secrets.get("password_encrypted")];
+
+        // Backups of this file go with it; other files' are left alone
+        std::fs::write(temp_dir.path().join("test.db.v3-20240101000000000.bak"), b"old").unwrap();
+        std::fs::write(temp_dir.path().join("other.db.v3-20240101000000000.bak"), b"old").unwrap();
+
+        for _ in 0..4 {
+            assert!(matches!(
+                db.verify_master_password("wrong_password").await,
+                Err(DatabaseError::InvalidMasterPassword)
+            ));
+            sqlx::query("UPDATE master_passwords SET locked_until = NULL")
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        assert!(matches!(
+            db.verify_master_password("wrong_password").await,
+            Err(DatabaseError::VaultWiped)
+        ));
+        assert!(!db.has_master_password().await.unwrap());
+        assert_eq!(backups(temp_dir.path()), vec![temp_dir.path().join("other.db.v3-20240101000000000.bak")]);
+
+        let row = sqlx::query("SELECT COUNT(*) as count FROM password_entries")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<i64, _>("count"), 0);
+
+        // Neither the file nor its log keeps a copy of what was deleted
+        for name in ["test.db", "test.db-wal"] {
+            let contents = std::fs::read(temp_dir.path().join(name)).unwrap_or_default();
+            for secret in &secrets {
+                assert!(
+                    !contents.windows(secret.len()).any(|window| window == secret.as_bytes()),
+                    "{name} still holds a deleted value"
+                );
+            }
+        }
+
+        // A new vault starts from the default auto-lock settings
+        db.set_master_password("test_master_password", &KdfParams::default(), false).await.unwrap();
+        let key = db.verify_master_password("test_master_password").await.unwrap();
+        assert_eq!(db.get_auto_lock_settings(&key).await.unwrap(), AutoLockSettings::default());
+    }
+
+    #[tokio::test]
+    async fn test_recovery_code() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let entry = login("Email", "user", "secret");
+        let entry_id = db.create_entry(entry, &key).await.unwrap();
+
+        assert!(!db.has_recovery_code().await.unwrap());
+        let code = db.create_recovery_code(&key).await.unwrap();
+        assert!(db.has_recovery_code().await.unwrap());
+
+        let wrong = CryptoService::generate_recovery_code();
+        assert!(matches!(
+            db.recover_with_code(&wrong, "new_password").await,
+            Err(DatabaseError::InvalidRecoveryCode)
+        ));
+
+        let recovered = db.recover_with_code(&code, "new_password").await.unwrap();
+        assert_eq!(recovered.as_bytes(), key.as_bytes());
+        assert!(db.verify_master_password("test_master_password").await.is_err());
+
+        let key = db.verify_master_password("new_password").await.unwrap();
+        assert_eq!(db.get_entry_by_id(&entry_id, &key).await.unwrap().password, "secret");
+
+        // A regenerated code replaces the old one
+        let new_code = db.create_recovery_code(&key).await.unwrap();
+        assert!(db.recover_with_code(&code, "other_password").await.is_err());
+
+        db.revoke_recovery_code().await.unwrap();
+        assert!(!db.has_recovery_code().await.unwrap());
+        assert!(matches!(
+            db.recover_with_code(&new_code, "other_password").await,
+            Err(DatabaseError::InvalidRecoveryCode)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_key_file_requirement() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let key_file = CryptoService::hash_key_file(&b"usb stick key file"[..]).unwrap();
+        let composite = CryptoService::composite_password("test_master_password", Some(&key_file));
+
+        assert!(!db.requires_key_file().await.unwrap());
+
+        let same_key = db.set_key_file_requirement("test_master_password", &composite, true).await.unwrap();
+        assert_eq!(same_key.as_bytes(), key.as_bytes());
+        assert!(db.requires_key_file().await.unwrap());
+
+        // The password alone no longer unlocks the vault
+        assert!(matches!(
+            db.verify_master_password("test_master_password").await,
+            Err(DatabaseError::InvalidMasterPassword)
+        ));
+        db.verify_master_password(&composite).await.unwrap();
+
+        db.set_key_file_requirement(&composite, "test_master_password", false).await.unwrap();
+        assert!(!db.requires_key_file().await.unwrap());
+        db.verify_master_password("test_master_password").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_password_policies() {
+        let (_temp_dir, db, key) = unlocked_db().await;
+
+        let pin = PasswordPolicy {
+            length: 6,
+            lowercase: false,
+            uppercase: false,
+            symbols: None,
+            min_lowercase: 0,
+            min_uppercase: 0,
+            min_symbols: 0,
+            ..PasswordPolicy::default()
+        };
+        let pin_id = db.create_password_policy("Bank PIN", &pin, &key).await.unwrap();
+        db.create_password_policy("alphanumeric", &PasswordPolicy { symbols: None, min_symbols: 0, ..PasswordPolicy::default() }, &key)
+            .await
+            .unwrap();
+
+        let policies = db.get_password_policies(&key).await.unwrap();
+        let names: Vec<&str> = policies.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["alphanumeric", "Bank PIN"]);
+        assert_eq!(policies[1].policy, pin);
+
+        // Names are not stored in the clear
+        let row = sqlx::query("SELECT name_encrypted FROM password_policies WHERE id = ?")
+            .bind(&pin_id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert!(!row.get::<String, _>("name_encrypted").contains("Bank PIN"));
+
+        db.delete_password_policy(&pin_id).await.unwrap();
+        assert_eq!(db.get_password_policies(&key).await.unwrap().len(), 1);
+        assert!(matches!(db.delete_password_policy(&pin_id).await, Err(DatabaseError::EntryNotFound)));
+    }
+
+    /// Tables as each schema version laid them out
+    fn historical_schema(version: i64) -> Vec<String> {
+        let mut master = vec!["id INTEGER PRIMARY KEY AUTOINCREMENT", "password_hash TEXT NOT NULL", "salt TEXT NOT NULL"];
+        if version >= 2 {
+            master.push("wrapped_key TEXT");
+        }
+        if version >= 3 {
+            master.extend(["kdf_algorithm TEXT", "kdf_memory_kib INTEGER", "kdf_iterations INTEGER", "kdf_parallelism INTEGER"]);
+        }
+        if version >= 6 {
+            master.extend(["failed_attempts INTEGER NOT NULL DEFAULT 0", "locked_until TEXT"]);
+        }
+        if version >= 7 {
+            master.push("recovery_wrapped_key TEXT");
+        }
+        if version >= 8 {
+            master.push("requires_key_file INTEGER NOT NULL DEFAULT 0");
+        }
+        master.push("created_at TEXT NOT NULL");
+
+        let mut entries = vec!["id TEXT PRIMARY KEY"];
+        entries.push(if version >= 4 { "title_encrypted TEXT NOT NULL" } else { "title TEXT NOT NULL" });
+        entries.extend(["username_encrypted TEXT NOT NULL", "password_encrypted TEXT NOT NULL", "url_encrypted TEXT", "notes_encrypted TEXT"]);
+        entries.push(match version {
+            ..=3 => "category TEXT",
+            4..=11 => "category_encrypted TEXT",
+            _ => "category_id TEXT REFERENCES categories(id)",
+        });
+        if version >= 10 {
+            entries.push("totp_encrypted TEXT");
+        }
+        if version >= 11 {
+            entries.push("is_favorite INTEGER NOT NULL DEFAULT 0");
+        }
+        if version >= 14 {
+            entries.push("deleted_at TEXT");
+        }
+        if version >= 16 {
+            entries.push("item_data_encrypted TEXT");
+        }
+        entries.extend(["created_at TEXT NOT NULL", "updated_at TEXT NOT NULL"]);
+
+        let mut tables = vec![format!("CREATE TABLE master_passwords ({})", master.join(", "))];
+        if version >= 12 {
+            tables.push("CREATE TABLE categories (id TEXT PRIMARY KEY, name_encrypted TEXT NOT NULL, color TEXT, icon TEXT, created_at TEXT NOT NULL)".to_string());
+        }
+        tables.push(format!("CREATE TABLE password_entries ({})", entries.join(", ")));
+        if version >= 5 {
+            tables.push("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)".to_string());
+        }
+        if version >= 13 {
+            tables.push("CREATE TABLE entry_history (id TEXT PRIMARY KEY, entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE, username_encrypted TEXT NOT NULL, password_encrypted TEXT NOT NULL, created_at TEXT NOT NULL)".to_string());
+        }
+        if version >= 15 {
+            tables.push("CREATE TABLE entry_fields (id TEXT PRIMARY KEY, entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE, position INTEGER NOT NULL, field_type TEXT NOT NULL, name_encrypted TEXT NOT NULL, value_encrypted TEXT NOT NULL)".to_string());
+        }
+        if version >= 17 {
+            tables.push("CREATE TABLE attachments (id TEXT PRIMARY KEY, entry_id TEXT NOT NULL REFERENCES password_entries(id) ON DELETE CASCADE, name_encrypted TEXT NOT NULL, size INTEGER NOT NULL, chunk_count INTEGER NOT NULL, created_at TEXT NOT NULL)".to_string());
+            tables.push("CREATE TABLE attachment_chunks (attachment_id TEXT NOT NULL REFERENCES attachments(id) ON DELETE CASCADE, chunk_index INTEGER NOT NULL, data_encrypted BLOB NOT NULL, PRIMARY KEY (attachment_id, chunk_index))".to_string());
+        }
+        if version >= 9 {
+            tables.push("CREATE TABLE password_policies (id TEXT PRIMARY KEY, name_encrypted TEXT NOT NULL, policy_encrypted TEXT NOT NULL, created_at TEXT NOT NULL)".to_string());
+        }
+        tables
+    }
+
+    /// Write a vault at a past schema version holding one entry, returning its key
+    ///
+    /// The master password row predates the key hierarchy, so the entry is
+    /// encrypted with the derived key at every version. Files from version 12
+    /// on record their version, so only the later migrations run on them;
+    /// older ones have none and go through every migration.
+    async fn write_fixture(db_path: &Path, version: i64) -> SecretKey {
+        let salt = CryptoService::generate_salt();
+        let key = CryptoService::derive_key_from_password("fixture_password", &salt, &KdfParams::default()).unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", db_path.display())).await.unwrap();
+        for statement in historical_schema(version) {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        if version >= 12 {
+            Database::set_schema_version(&mut pool.acquire().await.unwrap(), version).await.unwrap();
+        }
+        sqlx::query("INSERT INTO master_passwords (password_hash, salt, created_at) VALUES (?, ?, ?)")
+            .bind(CryptoService::hash_password("fixture_password", &KdfParams::default()).unwrap())
+            .bind(base64::engine::general_purpose::STANDARD.encode(salt))
+            .bind(&now)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (title_column, category_column, category) = match version {
+            ..=3 => ("title", "category", "Finance".to_string()),
+            4..=11 => ("title_encrypted", "category_encrypted", legacy_encrypt("Finance", &key)),
+            _ => {
+                sqlx::query("INSERT INTO categories (id, name_encrypted, created_at) VALUES ('finance', ?, ?)")
+                    .bind(Database::encrypt_field("Finance", &Database::category_aad("finance"), &key).unwrap())
+                    .bind(&now)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+                ("title_encrypted", "category_id", "finance".to_string())
+            }
+        };
+        let title = if version >= 4 { legacy_encrypt("My Bank", &key) } else { "My Bank".to_string() };
+        sqlx::query(&format!(
+            "INSERT INTO password_entries (id, {}, username_encrypted, password_encrypted, {}, created_at, updated_at) VALUES ('old', ?, ?, ?, ?, ?, ?)",
+            title_column, category_column
+        ))
+        .bind(title)
+        .bind(legacy_encrypt("bankuser", &key))
+        .bind(legacy_encrypt("bankpass", &key))
+        .bind(category)
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        key
+    }
+
+    /// Tables and their column names, in a stable order
+    async fn schema_layout(pool: &SqlitePool) -> Vec<(String, Vec<String>)> {
+        let tables: Vec<String> = sqlx::query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+
+        let mut layout = Vec::new();
+        for table in tables {
+            let mut columns: Vec<String> = sqlx::query("SELECT name FROM pragma_table_info(?)")
+                .bind(&table)
+                .fetch_all(pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get("name"))
+                .collect();
+            columns.sort();
+            layout.push((table, columns));
+        }
+        layout
+    }
+
+    fn backups(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "bak"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_schema_upgrades_from_every_version() {
+        let temp_dir = tempdir().unwrap();
+        let fresh = Database::new(temp_dir.path().join("fresh.db").to_str().unwrap()).await.unwrap();
+        assert_eq!(fresh.schema_version().await.unwrap(), SCHEMA_VERSION);
+        let expected = schema_layout(&fresh.pool).await;
+
+        for version in 1..=SCHEMA_VERSION {
+            let db_path = temp_dir.path().join(format!("v{}.db", version));
+            let fixture_key = write_fixture(&db_path, version).await;
+
+            let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+            assert_eq!(db.schema_version().await.unwrap(), SCHEMA_VERSION, "from version {version}");
+
+            // The backup is named after the version the file recorded
+            if version < SCHEMA_VERSION {
+                let recorded = if version >= 12 { version } else { 0 };
+                let prefix = format!("v{}.db.v{}-", version, recorded);
+                assert!(
+                    backups(temp_dir.path())
+                        .iter()
+                        .any(|path| path.file_name().unwrap().to_str().unwrap().starts_with(&prefix)),
+                    "from version {version}"
+                );
+            }
+
+            let key = db.verify_master_password("fixture_password").await.unwrap();
+            assert_eq!(key.as_bytes(), fixture_key.as_bytes(), "from version {version}");
+
+            let entry = db.get_entry_by_id("old", &key).await.unwrap();
+            assert_eq!(entry.title, "My Bank", "from version {version}");
+            assert_eq!(entry.username, "bankuser", "from version {version}");
+            assert_eq!(entry.password, "bankpass", "from version {version}");
+            assert_eq!(entry.category.as_deref(), Some("Finance"), "from version {version}");
+            assert!(!entry.is_favorite);
+
+            // Once unlocked, an upgraded file is laid out like a new one
+            assert_eq!(schema_layout(&db.pool).await, expected, "from version {version}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migration_backup() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        write_fixture(&db_path, 1).await;
+
+        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), SCHEMA_VERSION);
+
+        // The backup keeps the file as it was before migrating
+        let backup_paths = backups(temp_dir.path());
+        assert_eq!(backup_paths.len(), 1);
+        assert!(backup_paths[0].file_name().unwrap().to_str().unwrap().starts_with("test.db.v0-"));
+        let backup = Database {
+            pool: SqlitePool::connect(&format!("sqlite:{}", backup_paths[0].display())).await.unwrap(),
+            path: backup_paths[0].clone(),
+            unlock_guard: tokio::sync::Mutex::new(()),
+        };
+        assert_eq!(backup.schema_version().await.unwrap(), 0);
+        assert!(backup.column_exists("password_entries", "title").await.unwrap());
+        assert!(!backup.column_exists("master_passwords", "wrapped_key").await.unwrap());
+
+        // Files already at the current version are left alone
+        drop(db);
+        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+        assert_eq!(backups(temp_dir.path()).len(), 1);
+
+        // The first unlock finishes migrating, after which the backup goes
+        db.verify_master_password("fixture_password").await.unwrap();
+        assert!(backups(temp_dir.path()).is_empty());
+
+        // A new file has nothing to back up
+        let new_dir = tempdir().unwrap();
+        Database::new(new_dir.path().join("new.db").to_str().unwrap()).await.unwrap();
+        assert!(backups(new_dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_refused() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+        Database::set_schema_version(&mut db.pool.acquire().await.unwrap(), SCHEMA_VERSION + 1).await.unwrap();
+        db.pool.close().await;
+
+        assert!(matches!(
+            Database::new(db_path.to_str().unwrap()).await,
+            Err(DatabaseError::UnsupportedSchemaVersion(version)) if version == SCHEMA_VERSION + 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_keeps_last_version() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        // Entries that cannot be altered make migration 4 fail
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", db_path.display())).await.unwrap();
+        for statement in [
+            historical_schema(1)[0].clone(),
+            "CREATE VIEW password_entries AS SELECT 'old' AS id".to_string(),
+        ] {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+
+        assert!(Database::new(db_path.to_str().unwrap()).await.is_err());
+
+        let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.display())).await.unwrap();
+        let version: i64 = sqlx::query("PRAGMA user_version").fetch_one(&pool).await.unwrap().get(0);
+        assert_eq!(version, 3);
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(Database::has_column(&mut conn, "master_passwords", "kdf_iterations").await.unwrap());
+        assert!(!Database::has_column(&mut conn, "password_entries", "title_encrypted").await.unwrap());
+        assert_eq!(backups(temp_dir.path()).len(), 1);
     }
+
 }
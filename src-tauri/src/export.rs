@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::db::{CustomFieldType, PasswordEntry};
+use crate::items::{ItemData, ItemType};
+
+/// Identifies the file so an importer can tell it apart from other JSON
+const EXPORT_FORMAT: &str = "passvault-export";
+
+/// Bumped whenever a field is renamed or changes meaning
+const EXPORT_VERSION: u32 = 1;
+
+/// A decrypted copy of the vault, written as JSON
+///
+/// It borrows from the entries it was built from, so no secret is copied
+/// before it is serialized.
+#[derive(Debug, Serialize)]
+pub struct VaultExport<'a> {
+    pub format: &'static str,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub entries: Vec<ExportedEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedEntry<'a> {
+    pub item_type: ItemType,
+    pub title: &'a str,
+    pub username: &'a str,
+    pub password: &'a str,
+    pub url: Option<&'a str>,
+    pub notes: Option<&'a str>,
+    pub category: Option<&'a str>,
+    pub totp: Option<&'a str>,
+    pub is_favorite: bool,
+    pub item_data: Option<&'a ItemData>,
+    pub custom_fields: Vec<ExportedField<'a>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A custom field; `hidden` marks values the app masks, so an importer
+/// can mask them too
+#[derive(Debug, Serialize)]
+pub struct ExportedField<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    #[serde(rename = "type")]
+    pub field_type: CustomFieldType,
+    pub hidden: bool,
+}
+
+/// Lay out decrypted entries for export, in the order given
+pub fn build_export(entries: &[PasswordEntry], now: DateTime<Utc>) -> VaultExport<'_> {
+    VaultExport {
+        format: EXPORT_FORMAT,
+        version: EXPORT_VERSION,
+        exported_at: now,
+        entries: entries
+            .iter()
+            .map(|entry| ExportedEntry {
+                item_type: entry.item_type,
+                title: &entry.title,
+                username: &entry.username,
+                password: &entry.password,
+                url: entry.url.as_deref(),
+                notes: entry.notes.as_deref(),
+                category: entry.category.as_deref(),
+                totp: entry.totp.as_deref(),
+                is_favorite: entry.is_favorite,
+                item_data: entry.item_data.as_ref(),
+                custom_fields: entry
+                    .custom_fields
+                    .iter()
+                    .map(|field| ExportedField {
+                        name: &field.name,
+                        value: &field.value,
+                        field_type: field.field_type,
+                        hidden: field.field_type == CustomFieldType::Hidden,
+                    })
+                    .collect(),
+                created_at: entry.created_at,
+                updated_at: entry.updated_at,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::CustomField;
+
+    #[test]
+    fn test_export_marks_hidden_fields() {
+        let now = Utc::now();
+        let entry = PasswordEntry {
+            id: "bank".to_string(),
+            title: "Bank".to_string(),
+            username: "user@example.com".to_string(),
+            password: "Xk9#mQ2$vL7!pR4&".to_string(),
+            url: Some("https://bank.example".to_string()),
+            notes: None,
+            category_id: Some("finance".to_string()),
+            category: Some("Finance".to_string()),
+            totp: None,
+            is_favorite: true,
+            custom_fields: vec![
+                CustomField {
+                    name: "Account number".to_string(),
+                    value: "12-3456-789".to_string(),
+                    field_type: CustomFieldType::Text,
+                },
+                CustomField {
+                    name: "PIN".to_string(),
+                    value: "4821".to_string(),
+                    field_type: CustomFieldType::Hidden,
+                },
+            ],
+            item_type: ItemType::Login,
+            item_data: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+
+        let export = serde_json::to_value(build_export(std::slice::from_ref(&entry), now)).unwrap();
+        assert_eq!(export["format"], "passvault-export");
+        assert_eq!(export["version"], 1);
+
+        let exported = &export["entries"][0];
+        assert_eq!(exported["title"], "Bank");
+        assert_eq!(exported["password"], "Xk9#mQ2$vL7!pR4&");
+        assert_eq!(exported["category"], "Finance");
+        assert_eq!(exported["item_type"], "login");
+        // Internal ids mean nothing outside this vault
+        assert!(exported.get("id").is_none());
+        assert!(exported.get("category_id").is_none());
+
+        let fields = exported["custom_fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0]["name"], "Account number");
+        assert_eq!(fields[0]["type"], "text");
+        assert_eq!(fields[0]["hidden"], false);
+        assert_eq!(fields[1]["value"], "4821");
+        assert_eq!(fields[1]["type"], "hidden");
+        assert_eq!(fields[1]["hidden"], true);
+    }
+}
@@ -0,0 +1,469 @@
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+pub const DEFAULT_SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+
+/// Characters easily confused with one another when read or typed
+const AMBIGUOUS: &str = "0Oo1lI|";
+
+/// Longest password a policy may ask for
+pub const MAX_PASSWORD_LENGTH: usize = 1024;
+
+/// EFF's large wordlist, one `<dice roll>\t<word>` entry per line
+const EFF_LARGE_WORDLIST: &str = include_str!("../wordlists/eff_large_wordlist.txt");
+
+/// Most words a passphrase may ask for
+pub const MAX_PASSPHRASE_WORDS: usize = 32;
+
+static WORDLIST: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PolicyError {
+    #[error("Password length must be between 1 and {MAX_PASSWORD_LENGTH}")]
+    InvalidLength,
+    #[error("At least one character class must be enabled")]
+    NoCharacterClasses,
+    #[error("The {0} character set is empty")]
+    EmptyCharacterClass(&'static str),
+    #[error("Minimum character counts add up to more than the password length")]
+    MinimumsExceedLength,
+    #[error("Not enough distinct characters to avoid repeats")]
+    NotEnoughUniqueCharacters,
+    #[error("Not enough distinct {0} characters to meet its minimum without repeats")]
+    NotEnoughUniqueInClass(&'static str),
+    #[error("Word count must be between 1 and {MAX_PASSPHRASE_WORDS}")]
+    InvalidWordCount,
+}
+
+/// Rules a generated password must satisfy
+///
+/// A `min_*` count only applies when its class is enabled. `symbols` is the
+/// symbol set to draw from; `None` disables symbols.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: Option<String>,
+    pub min_lowercase: usize,
+    pub min_uppercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    /// Leave out characters such as 0/O and l/1
+    pub exclude_ambiguous: bool,
+    /// Use every character at most once
+    pub no_repeats: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: Some(DEFAULT_SYMBOLS.to_string()),
+            min_lowercase: 1,
+            min_uppercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            exclude_ambiguous: false,
+            no_repeats: false,
+        }
+    }
+}
+
+/// One enabled character class and how many of its characters are required
+struct CharClass {
+    name: &'static str,
+    chars: Vec<char>,
+    min: usize,
+}
+
+impl PasswordPolicy {
+    /// Character classes the policy enables, with ambiguous characters and
+    /// duplicates already removed
+    fn classes(&self) -> Result<Vec<CharClass>, PolicyError> {
+        let symbols = self.symbols.as_deref();
+        let candidates = [
+            ("lowercase", self.lowercase.then_some(LOWERCASE), self.min_lowercase),
+            ("uppercase", self.uppercase.then_some(UPPERCASE), self.min_uppercase),
+            ("digit", self.digits.then_some(DIGITS), self.min_digits),
+            ("symbol", symbols, self.min_symbols),
+        ];
+
+        let mut seen = Vec::new();
+        let mut classes = Vec::new();
+        for (name, chars, min) in candidates {
+            let Some(chars) = chars else { continue };
+
+            let chars: Vec<char> = chars
+                .chars()
+                .filter(|c| c.is_ascii_graphic())
+                .filter(|c| name != "symbol" || !c.is_ascii_alphanumeric())
+                .filter(|c| !(self.exclude_ambiguous && AMBIGUOUS.contains(*c)))
+                .filter(|c| {
+                    let new = !seen.contains(c);
+                    seen.push(*c);
+                    new
+                })
+                .collect();
+
+            if chars.is_empty() {
+                return Err(PolicyError::EmptyCharacterClass(name));
+            }
+            classes.push(CharClass { name, chars, min });
+        }
+
+        Ok(classes)
+    }
+
+    /// Check that a password meeting every constraint can exist
+    fn validate(&self, classes: &[CharClass]) -> Result<(), PolicyError> {
+        if self.length == 0 || self.length > MAX_PASSWORD_LENGTH {
+            return Err(PolicyError::InvalidLength);
+        }
+        if classes.is_empty() {
+            return Err(PolicyError::NoCharacterClasses);
+        }
+        if classes.iter().map(|class| class.min).sum::<usize>() > self.length {
+            return Err(PolicyError::MinimumsExceedLength);
+        }
+        if self.no_repeats {
+            if let Some(class) = classes.iter().find(|class| class.chars.len() < class.min) {
+                return Err(PolicyError::NotEnoughUniqueInClass(class.name));
+            }
+            let available: usize = classes.iter().map(|class| class.chars.len()).sum();
+            if available < self.length {
+                return Err(PolicyError::NotEnoughUniqueCharacters);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shape of a generated passphrase
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PassphraseOptions {
+    pub word_count: usize,
+    pub separator: String,
+    /// Upper-case the first letter of every word
+    pub capitalize: bool,
+    /// Append one random digit to the end
+    pub include_digit: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            word_count: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            include_digit: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Passphrase {
+    pub passphrase: String,
+    /// Entropy of the random choices, assuming the wordlist is known
+    pub entropy_bits: f64,
+}
+
+/// Words of the embedded wordlist, parsed on first use
+pub(crate) fn wordlist() -> &'static [&'static str] {
+    WORDLIST.get_or_init(|| parse_wordlist(EFF_LARGE_WORDLIST))
+}
+
+/// Take the last column of every non-empty line, so both the dice-numbered
+/// EFF format and a plain one-word-per-line list are accepted
+fn parse_wordlist(text: &str) -> Vec<&str> {
+    text.lines().filter_map(|line| line.split_whitespace().last()).collect()
+}
+
+pub struct PasswordGenerator;
+
+impl PasswordGenerator {
+    /// Generate a password that satisfies every rule of `policy`
+    ///
+    /// Each class first contributes its minimum, the rest is drawn from all
+    /// enabled classes together, and the result is shuffled so the required
+    /// characters do not sit at fixed positions.
+    pub fn generate(policy: &PasswordPolicy) -> Result<String, PolicyError> {
+        let mut classes = policy.classes()?;
+        policy.validate(&classes)?;
+
+        let mut password = Vec::with_capacity(policy.length);
+        for class in classes.iter_mut() {
+            for _ in 0..class.min {
+                password.push(Self::pick(&mut class.chars, policy.no_repeats));
+            }
+        }
+
+        let mut pool: Vec<char> = classes.into_iter().flat_map(|class| class.chars).collect();
+        while password.len() < policy.length {
+            password.push(Self::pick(&mut pool, policy.no_repeats));
+        }
+
+        Self::shuffle(&mut password);
+        Ok(password.into_iter().collect())
+    }
+
+    /// Generate a passphrase from the embedded EFF large wordlist
+    pub fn generate_passphrase(options: &PassphraseOptions) -> Result<Passphrase, PolicyError> {
+        Self::passphrase_from(wordlist(), options)
+    }
+
+    fn passphrase_from(words: &[&str], options: &PassphraseOptions) -> Result<Passphrase, PolicyError> {
+        if options.word_count == 0 || options.word_count > MAX_PASSPHRASE_WORDS {
+            return Err(PolicyError::InvalidWordCount);
+        }
+        if words.is_empty() {
+            return Err(PolicyError::EmptyCharacterClass("word"));
+        }
+
+        let chosen: Vec<String> = (0..options.word_count)
+            .map(|_| {
+                let word = words[Self::random_index(words.len())];
+                if options.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect();
+
+        let mut passphrase = chosen.join(&options.separator);
+        let mut entropy_bits = options.word_count as f64 * (words.len() as f64).log2();
+        if options.include_digit {
+            let digit = DIGITS.as_bytes()[Self::random_index(DIGITS.len())];
+            passphrase.push(digit as char);
+            entropy_bits += (DIGITS.len() as f64).log2();
+        }
+
+        Ok(Passphrase { passphrase, entropy_bits })
+    }
+
+    /// Pick a random character, taking it out of the pool if it may not repeat
+    fn pick(chars: &mut Vec<char>, remove: bool) -> char {
+        let idx = Self::random_index(chars.len());
+        if remove {
+            chars.swap_remove(idx)
+        } else {
+            chars[idx]
+        }
+    }
+
+    /// Fisher-Yates shuffle
+    fn shuffle(chars: &mut [char]) {
+        for i in (1..chars.len()).rev() {
+            chars.swap(i, Self::random_index(i + 1));
+        }
+    }
+
+    /// Uniformly random index below `len`
+    fn random_index(len: usize) -> usize {
+        OsRng.gen_range(0..len)
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(password: &str, set: &str) -> usize {
+        password.chars().filter(|c| set.contains(*c)).count()
+    }
+
+    #[test]
+    fn test_minimum_counts() {
+        let policy = PasswordPolicy {
+            length: 12,
+            min_lowercase: 2,
+            min_uppercase: 3,
+            min_digits: 4,
+            min_symbols: 2,
+            symbols: Some("#$%".to_string()),
+            ..PasswordPolicy::default()
+        };
+
+        for _ in 0..200 {
+            let password = PasswordGenerator::generate(&policy).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(count(&password, LOWERCASE) >= 2);
+            assert!(count(&password, UPPERCASE) >= 3);
+            assert!(count(&password, DIGITS) >= 4);
+            assert!(count(&password, "#$%") >= 2);
+            assert!(password.chars().all(|c| c.is_ascii_alphanumeric() || "#$%".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_exclude_ambiguous_and_no_repeats() {
+        let policy = PasswordPolicy {
+            length: 40,
+            exclude_ambiguous: true,
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+
+        for _ in 0..200 {
+            let password = PasswordGenerator::generate(&policy).unwrap();
+            assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
+
+            let mut chars: Vec<char> = password.chars().collect();
+            chars.sort_unstable();
+            chars.dedup();
+            assert_eq!(chars.len(), 40);
+        }
+    }
+
+    #[test]
+    fn test_disabled_classes() {
+        let policy = PasswordPolicy {
+            uppercase: false,
+            symbols: None,
+            ..PasswordPolicy::default()
+        };
+
+        let password = PasswordGenerator::generate(&policy).unwrap();
+        assert!(password.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn test_impossible_policies() {
+        let too_short = PasswordPolicy { length: 3, ..PasswordPolicy::default() };
+        assert_eq!(PasswordGenerator::generate(&too_short), Err(PolicyError::MinimumsExceedLength));
+
+        let nothing = PasswordPolicy {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: None,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(PasswordGenerator::generate(&nothing), Err(PolicyError::NoCharacterClasses));
+
+        let digits_only = PasswordPolicy {
+            length: 11,
+            lowercase: false,
+            uppercase: false,
+            symbols: None,
+            min_digits: 0,
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(PasswordGenerator::generate(&digits_only), Err(PolicyError::NotEnoughUniqueCharacters));
+
+        let many_digits = PasswordPolicy { min_digits: 11, no_repeats: true, ..PasswordPolicy::default() };
+        assert_eq!(PasswordGenerator::generate(&many_digits), Err(PolicyError::NotEnoughUniqueInClass("digit")));
+
+        let letters_as_symbols = PasswordPolicy { symbols: Some("abc".to_string()), ..PasswordPolicy::default() };
+        assert_eq!(
+            PasswordGenerator::generate(&letters_as_symbols),
+            Err(PolicyError::EmptyCharacterClass("symbol"))
+        );
+
+        let empty = PasswordPolicy { length: 0, ..PasswordPolicy::default() };
+        assert_eq!(PasswordGenerator::generate(&empty), Err(PolicyError::InvalidLength));
+    }
+
+    /// Chi-square statistic of `counts` against a flat distribution
+    fn chi_square(counts: &[u32]) -> f64 {
+        let expected = counts.iter().sum::<u32>() as f64 / counts.len() as f64;
+        counts.iter().map(|&observed| (observed as f64 - expected).powi(2) / expected).sum()
+    }
+
+    #[test]
+    fn test_uniform_selection() {
+        // 2^32 % 88 != 0, so a modulo would be biased for this length
+        let mut indexes = [0u32; 88];
+        for _ in 0..88_000 {
+            indexes[PasswordGenerator::random_index(88)] += 1;
+        }
+        // 87 degrees of freedom; 150 is far beyond the 99.99th percentile
+        assert!(chi_square(&indexes) < 150.0);
+
+        // Every symbol of a symbols-only policy should turn up equally often
+        let policy = PasswordPolicy {
+            length: 100,
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            min_symbols: 0,
+            ..PasswordPolicy::default()
+        };
+        let mut symbols = [0u32; DEFAULT_SYMBOLS.len()];
+        for _ in 0..520 {
+            for c in PasswordGenerator::generate(&policy).unwrap().chars() {
+                symbols[DEFAULT_SYMBOLS.find(c).unwrap()] += 1;
+            }
+        }
+        // 25 degrees of freedom; 70 is far beyond the 99.99th percentile
+        assert!(chi_square(&symbols) < 70.0);
+    }
+
+    #[test]
+    fn test_embedded_wordlist() {
+        let words = wordlist();
+        assert_eq!(words.len(), 7776);
+
+        let mut unique = words.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 7776);
+
+        // Every line is numbered with the five dice rolls that select it
+        let rolls: Vec<&str> = EFF_LARGE_WORDLIST.lines().filter_map(|line| line.split_whitespace().next()).collect();
+        assert_eq!(rolls.first(), Some(&"11111"));
+        assert_eq!(rolls.last(), Some(&"66666"));
+    }
+
+    #[test]
+    fn test_passphrase_options() {
+        let words = parse_wordlist("11\tapple\n12\tbanana\n13\tcherry\n14\tdate\n");
+        assert_eq!(words, ["apple", "banana", "cherry", "date"]);
+
+        let options = PassphraseOptions {
+            word_count: 5,
+            separator: " ".to_string(),
+            capitalize: true,
+            include_digit: true,
+        };
+        let result = PasswordGenerator::passphrase_from(&words, &options).unwrap();
+        let (phrase, digit) = result.passphrase.split_at(result.passphrase.len() - 1);
+        assert!(digit.chars().all(|c| c.is_ascii_digit()));
+
+        let chosen: Vec<&str> = phrase.split(' ').collect();
+        assert_eq!(chosen.len(), 5);
+        for word in chosen {
+            assert!(["Apple", "Banana", "Cherry", "Date"].contains(&word));
+        }
+        // 5 words from 4 give 10 bits, plus log2(10) for the digit
+        assert!((result.entropy_bits - (10.0 + 10f64.log2())).abs() < 1e-9);
+
+        let plain = PasswordGenerator::passphrase_from(&words, &PassphraseOptions::default()).unwrap();
+        assert_eq!(plain.passphrase.split('-').count(), 6);
+        assert!(plain.passphrase.chars().all(|c| c.is_ascii_lowercase() || c == '-'));
+        assert_eq!(plain.entropy_bits, 12.0);
+
+        let none = PassphraseOptions { word_count: 0, ..PassphraseOptions::default() };
+        assert_eq!(PasswordGenerator::passphrase_from(&words, &none).unwrap_err(), PolicyError::InvalidWordCount);
+    }
+}
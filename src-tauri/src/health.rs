@@ -0,0 +1,215 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::db::{HealthSettings, PasswordEntry};
+use crate::items::ItemType;
+use crate::strength;
+
+/// How much each kind of problem takes off an entry's share of the score
+const REUSED_PENALTY: f64 = 0.4;
+const WEAK_PENALTY: f64 = 0.4;
+const OLD_PENALTY: f64 = 0.15;
+const MISSING_URL_PENALTY: f64 = 0.05;
+
+/// Entries that share one password; the password itself is never reported
+#[derive(Debug, Clone, Serialize)]
+pub struct ReusedPassword {
+    pub entry_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeakPassword {
+    pub entry_id: String,
+    pub score: u8,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OldPassword {
+    pub entry_id: String,
+    pub updated_at: DateTime<Utc>,
+    pub age_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// 0 to 100; 100 when no entry has a problem
+    pub score: u8,
+    pub total_entries: usize,
+    pub reused: Vec<ReusedPassword>,
+    pub weak: Vec<WeakPassword>,
+    pub old: Vec<OldPassword>,
+    pub missing_url: Vec<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Check decrypted entries for reused, weak and old passwords and missing URLs
+///
+/// Every entry starts with an equal share of the score and loses part of
+/// it for each kind of problem it has. Items without a password, such as
+/// cards and notes, are left out.
+pub fn build_report(entries: &[PasswordEntry], settings: &HealthSettings, now: DateTime<Utc>) -> HealthReport {
+    let entries: Vec<&PasswordEntry> = entries.iter().filter(|entry| entry.item_type.has_password()).collect();
+
+    let mut by_password: HashMap<&str, Vec<&str>> = HashMap::new();
+    for entry in entries.iter().filter(|entry| !entry.password.is_empty()) {
+        by_password.entry(&entry.password).or_default().push(&entry.id);
+    }
+    let mut reused: Vec<ReusedPassword> = by_password
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .map(|ids| ReusedPassword {
+            entry_ids: ids.into_iter().map(str::to_string).collect(),
+        })
+        .collect();
+    // Largest groups first, then in entry order so reports are stable
+    reused.sort_by(|a, b| b.entry_ids.len().cmp(&a.entry_ids.len()).then_with(|| a.entry_ids.cmp(&b.entry_ids)));
+
+    let mut weak = Vec::new();
+    let mut old = Vec::new();
+    let mut missing_url = Vec::new();
+    let mut penalties: HashMap<&str, f64> = HashMap::new();
+
+    for group in &reused {
+        for id in &group.entry_ids {
+            *penalties.entry(id).or_default() += REUSED_PENALTY;
+        }
+    }
+
+    let max_age = Duration::days(settings.max_password_age_days as i64);
+    for entry in &entries {
+        let inputs = [entry.title.clone(), entry.username.clone(), entry.url.clone().unwrap_or_default()];
+        let estimate = strength::estimate_strength(&entry.password, &inputs);
+        if estimate.score <= strength::WEAK_SCORE {
+            weak.push(WeakPassword {
+                entry_id: entry.id.clone(),
+                score: estimate.score,
+                warning: estimate.warning,
+            });
+            *penalties.entry(&entry.id).or_default() += WEAK_PENALTY;
+        }
+
+        let age = now - entry.updated_at;
+        if age > max_age {
+            old.push(OldPassword {
+                entry_id: entry.id.clone(),
+                updated_at: entry.updated_at,
+                age_days: age.num_days(),
+            });
+            *penalties.entry(&entry.id).or_default() += OLD_PENALTY;
+        }
+
+        if entry.item_type == ItemType::Login && entry.url.as_deref().is_none_or(|url| url.trim().is_empty()) {
+            missing_url.push(entry.id.clone());
+            *penalties.entry(&entry.id).or_default() += MISSING_URL_PENALTY;
+        }
+    }
+
+    let score = if entries.is_empty() {
+        100
+    } else {
+        let healthy: f64 = entries
+            .iter()
+            .map(|entry| 1.0 - penalties.get(entry.id.as_str()).copied().unwrap_or(0.0).min(1.0))
+            .sum();
+        (healthy / entries.len() as f64 * 100.0).round() as u8
+    };
+
+    HealthReport {
+        score,
+        total_entries: entries.len(),
+        reused,
+        weak,
+        old,
+        missing_url,
+        generated_at: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, password: &str, url: Option<&str>, age_days: i64, now: DateTime<Utc>) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: format!("Site {id}"),
+            username: "user@example.com".to_string(),
+            password: password.to_string(),
+            url: url.map(str::to_string),
+            notes: None,
+            category_id: None,
+            category: None,
+            totp: None,
+            is_favorite: false,
+            custom_fields: Vec::new(),
+            item_type: ItemType::Login,
+            item_data: None,
+            created_at: now - Duration::days(age_days),
+            updated_at: now - Duration::days(age_days),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_health_report() {
+        let now = Utc::now();
+        let strong = "Xk9#mQ2$vL7!pR4&";
+        let entries = vec![
+            entry("a", strong, Some("https://a.example"), 10, now),
+            entry("b", strong, Some("https://b.example"), 10, now),
+            entry("c", "password", Some("https://c.example"), 10, now),
+            entry("d", "Tz8&wN3!hY6@kD1#", None, 400, now),
+            entry("e", "Jq5%rB9^mF2*cX7$", Some("https://e.example"), 10, now),
+        ];
+
+        let report = build_report(&entries, &HealthSettings::default(), now);
+        assert_eq!(report.total_entries, 5);
+
+        assert_eq!(report.reused.len(), 1);
+        assert_eq!(report.reused[0].entry_ids, ["a", "b"]);
+        let serialized = serde_json::to_string(&report).unwrap();
+        assert!(!serialized.contains(strong));
+
+        assert_eq!(report.weak.len(), 1);
+        assert_eq!(report.weak[0].entry_id, "c");
+        assert_eq!(report.weak[0].score, 0);
+
+        assert_eq!(report.old.len(), 1);
+        assert_eq!(report.old[0].entry_id, "d");
+        assert_eq!(report.old[0].age_days, 400);
+        assert_eq!(report.missing_url, ["d"]);
+
+        // a and b lose 0.4, c 0.4, d 0.2, e nothing
+        assert_eq!(report.score, 72);
+
+        // A longer allowed age clears the old finding
+        let lenient = HealthSettings { max_password_age_days: 500 };
+        assert!(build_report(&entries, &lenient, now).old.is_empty());
+    }
+
+    #[test]
+    fn test_items_without_passwords_are_skipped() {
+        let now = Utc::now();
+        let mut card = entry("card", "", None, 400, now);
+        card.item_type = ItemType::Card;
+        let mut wifi = entry("wifi", "password", None, 10, now);
+        wifi.item_type = ItemType::Wifi;
+
+        let report = build_report(&[card, wifi], &HealthSettings::default(), now);
+        assert_eq!(report.total_entries, 1);
+        assert_eq!(report.weak.len(), 1);
+        assert_eq!(report.weak[0].entry_id, "wifi");
+        assert!(report.old.is_empty());
+        // Networks have no website to be missing
+        assert!(report.missing_url.is_empty());
+    }
+
+    #[test]
+    fn test_empty_vault_is_healthy() {
+        let report = build_report(&[], &HealthSettings::default(), Utc::now());
+        assert_eq!(report.score, 100);
+        assert!(report.reused.is_empty() && report.weak.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[derive(Error, Debug)]
+pub enum ItemError {
+    #[error("{0} is required")]
+    MissingField(&'static str),
+    #[error("Card number is not valid")]
+    InvalidCardNumber,
+    #[error("Card expiry must be a month and year, MM/YY or MM/YYYY")]
+    InvalidExpiry,
+    #[error("Security code must be 3 or 4 digits")]
+    InvalidSecurityCode,
+    #[error("{0} must be a date in YYYY-MM-DD form")]
+    InvalidDate(&'static str),
+    #[error("Email address is not valid")]
+    InvalidEmail,
+    #[error("Network name must be 1 to 32 bytes")]
+    InvalidSsid,
+}
+
+/// What kind of item an entry holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    #[default]
+    Login,
+    Card,
+    SecureNote,
+    Identity,
+    Wifi,
+    License,
+}
+
+impl ItemType {
+    /// Whether the entry's password field holds a secret worth auditing
+    pub fn has_password(self) -> bool {
+        matches!(self, ItemType::Login | ItemType::Wifi)
+    }
+}
+
+/// Fields specific to an item type, stored encrypted alongside the entry
+///
+/// Logins have none beyond the entry's own fields, and a secure note keeps
+/// its text in the entry's notes. Wi-Fi keeps its passphrase in the entry's
+/// password so the health report and breach check cover it.
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemData {
+    Card(CardData),
+    SecureNote,
+    Identity(IdentityData),
+    Wifi(WifiData),
+    License(LicenseData),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
+pub struct CardData {
+    pub cardholder_name: String,
+    pub number: String,
+    /// MM/YY or MM/YYYY
+    pub expiry: String,
+    #[serde(default)]
+    pub security_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
+pub struct IdentityData {
+    pub full_name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    /// YYYY-MM-DD
+    #[serde(default)]
+    pub birth_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
+pub struct WifiData {
+    pub ssid: String,
+    #[zeroize(skip)]
+    pub security: WifiSecurity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiSecurity {
+    Open,
+    Wep,
+    WpaPersonal,
+    Wpa3Personal,
+    Enterprise,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
+pub struct LicenseData {
+    pub license_key: String,
+    #[serde(default)]
+    pub licensed_to: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// YYYY-MM-DD
+    #[serde(default)]
+    pub expires: Option<String>,
+}
+
+impl ItemData {
+    pub fn item_type(&self) -> ItemType {
+        match self {
+            ItemData::Card(_) => ItemType::Card,
+            ItemData::SecureNote => ItemType::SecureNote,
+            ItemData::Identity(_) => ItemType::Identity,
+            ItemData::Wifi(_) => ItemType::Wifi,
+            ItemData::License(_) => ItemType::License,
+        }
+    }
+
+    /// Check the fields against their type's schema
+    pub fn validate(&self) -> Result<(), ItemError> {
+        match self {
+            ItemData::Card(card) => {
+                if card.cardholder_name.trim().is_empty() {
+                    return Err(ItemError::MissingField("Cardholder name"));
+                }
+                if !is_valid_card_number(&card.number) {
+                    return Err(ItemError::InvalidCardNumber);
+                }
+                parse_expiry(&card.expiry).ok_or(ItemError::InvalidExpiry)?;
+                if let Some(code) = non_empty(&card.security_code) {
+                    if !(3..=4).contains(&code.len()) || !code.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(ItemError::InvalidSecurityCode);
+                    }
+                }
+            }
+            ItemData::SecureNote => {}
+            ItemData::Identity(identity) => {
+                if identity.full_name.trim().is_empty() {
+                    return Err(ItemError::MissingField("Full name"));
+                }
+                if let Some(email) = non_empty(&identity.email) {
+                    let valid = email
+                        .split_once('@')
+                        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !email.contains(' '));
+                    if !valid {
+                        return Err(ItemError::InvalidEmail);
+                    }
+                }
+                validate_date(&identity.birth_date, "Birth date")?;
+            }
+            ItemData::Wifi(wifi) => {
+                if wifi.ssid.is_empty() || wifi.ssid.len() > 32 {
+                    return Err(ItemError::InvalidSsid);
+                }
+            }
+            ItemData::License(license) => {
+                if license.license_key.trim().is_empty() {
+                    return Err(ItemError::MissingField("License key"));
+                }
+                validate_date(&license.expires, "Expiry date")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 12 to 19 digits, optionally grouped with spaces or dashes, passing the
+/// Luhn check
+pub fn is_valid_card_number(number: &str) -> bool {
+    let digits: Vec<u32> = number
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_digit(10))
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+    if !(12..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    // Double every second digit from the right, subtracting 9 when that
+    // gives two digits
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Month and four-digit year of an `MM/YY` or `MM/YYYY` expiry
+pub fn parse_expiry(expiry: &str) -> Option<(u32, i32)> {
+    let (month, year) = expiry.trim().split_once('/')?;
+    let month: u32 = month.trim().parse().ok().filter(|month| (1..=12).contains(month))?;
+    let year = year.trim();
+    let year: i32 = match year.len() {
+        2 => 2000 + year.parse::<i32>().ok()?,
+        4 => year.parse().ok()?,
+        _ => return None,
+    };
+    Some((month, year))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|value| !value.is_empty())
+}
+
+fn validate_date(value: &Option<String>, field: &'static str) -> Result<(), ItemError> {
+    match non_empty(value) {
+        Some(date) if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() => Err(ItemError::InvalidDate(field)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(number: &str, expiry: &str, security_code: Option<&str>) -> ItemData {
+        ItemData::Card(CardData {
+            cardholder_name: "Alex Doe".to_string(),
+            number: number.to_string(),
+            expiry: expiry.to_string(),
+            security_code: security_code.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_card_numbers() {
+        for number in ["4111111111111111", "4242 4242 4242 4242", "3782-822463-10005", "6011111111111117"] {
+            assert!(is_valid_card_number(number), "{number}");
+        }
+        for number in ["4111111111111112", "4111 1111 1111", "4111x11111111111", "", "0000 0000 0000 0000 0000"] {
+            assert!(!is_valid_card_number(number), "{number}");
+        }
+    }
+
+    #[test]
+    fn test_card_expiry() {
+        assert_eq!(parse_expiry("03/27"), Some((3, 2027)));
+        assert_eq!(parse_expiry(" 12 / 2031 "), Some((12, 2031)));
+        assert_eq!(parse_expiry("13/27"), None);
+        assert_eq!(parse_expiry("00/27"), None);
+        assert_eq!(parse_expiry("0327"), None);
+        assert_eq!(parse_expiry("03/207"), None);
+
+        assert!(card("4111111111111111", "03/27", Some("123")).validate().is_ok());
+        assert!(matches!(card("4111111111111112", "03/27", None).validate(), Err(ItemError::InvalidCardNumber)));
+        assert!(matches!(card("4111111111111111", "3/2027/1", None).validate(), Err(ItemError::InvalidExpiry)));
+        assert!(matches!(
+            card("4111111111111111", "03/27", Some("12a")).validate(),
+            Err(ItemError::InvalidSecurityCode)
+        ));
+    }
+
+    #[test]
+    fn test_item_schemas() {
+        let identity = |email: Option<&str>, birth_date: Option<&str>| {
+            ItemData::Identity(IdentityData {
+                full_name: "Alex Doe".to_string(),
+                email: email.map(str::to_string),
+                phone: None,
+                address: None,
+                birth_date: birth_date.map(str::to_string),
+            })
+        };
+        assert!(identity(Some("alex@example.com"), Some("1990-04-01")).validate().is_ok());
+        assert!(identity(Some(""), None).validate().is_ok());
+        assert!(matches!(identity(Some("alex.example.com"), None).validate(), Err(ItemError::InvalidEmail)));
+        assert!(matches!(identity(None, Some("01/04/1990")).validate(), Err(ItemError::InvalidDate(_))));
+
+        let wifi = |ssid: &str| ItemData::Wifi(WifiData { ssid: ssid.to_string(), security: WifiSecurity::WpaPersonal });
+        assert!(wifi("Home").validate().is_ok());
+        assert!(matches!(wifi("").validate(), Err(ItemError::InvalidSsid)));
+        assert!(matches!(wifi(&"x".repeat(33)).validate(), Err(ItemError::InvalidSsid)));
+
+        let license = ItemData::License(LicenseData {
+            license_key: " ".to_string(),
+            licensed_to: None,
+            version: None,
+            expires: None,
+        });
+        assert!(matches!(license.validate(), Err(ItemError::MissingField(_))));
+        assert!(ItemData::SecureNote.validate().is_ok());
+    }
+
+    #[test]
+    fn test_item_data_is_tagged_by_type() {
+        let json = serde_json::to_value(card("4111111111111111", "03/27", None)).unwrap();
+        assert_eq!(json["type"], "card");
+        assert_eq!(json["number"], "4111111111111111");
+
+        let note: ItemData = serde_json::from_str(r#"{"type":"secure_note"}"#).unwrap();
+        assert_eq!(note.item_type(), ItemType::SecureNote);
+        let wifi: ItemData = serde_json::from_str(r#"{"type":"wifi","ssid":"Home","security":"wpa3_personal"}"#).unwrap();
+        assert_eq!(wifi.item_type(), ItemType::Wifi);
+        assert!(serde_json::from_str::<ItemData>(r#"{"type":"login"}"#).is_err());
+    }
+}
@@ -1,10 +1,17 @@
+mod autolock;
+mod breach;
 mod commands;
 mod crypto;
 mod db;
+mod export;
+mod generator;
+mod health;
+mod items;
+mod strength;
+mod totp;
 
 use commands::AppState;
 use db::Database;
-use std::sync::Mutex;
 use tauri::Manager;
 use dirs::data_local_dir;
 
@@ -47,26 +54,70 @@ pub fn run() {
             .expect("Failed to initialize database");
 
             // Create and register app state
-            let app_state = AppState {
-                db: database,
-                encryption_key: Mutex::new(None),
-            };
+            let app_state = AppState::new(database);
 
             app.manage(app_state);
 
+            // Lock the vault from the backend once it has been idle too long
+            autolock::spawn(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::setup_master_password,
             commands::verify_master_password,
             commands::has_master_password,
+            commands::change_master_password,
+            commands::recover_with_key,
+            commands::has_recovery_key,
+            commands::regenerate_recovery_key,
+            commands::revoke_recovery_key,
+            commands::requires_key_file,
+            commands::has_plaintext_metadata,
+            commands::add_key_file,
+            commands::remove_key_file,
+            commands::get_kdf_params,
+            commands::upgrade_kdf_params,
+            commands::get_auto_lock_settings,
+            commands::set_auto_lock_settings,
+            commands::get_lockout_settings,
+            commands::set_lockout_settings,
+            commands::get_health_settings,
+            commands::set_health_settings,
+            commands::get_trash_settings,
+            commands::set_trash_settings,
+            commands::vault_health_report,
+            commands::check_breached_passwords,
             commands::create_password_entry,
             commands::get_all_entries,
+            commands::get_favorite_entries,
             commands::get_entry_by_id,
             commands::update_password_entry,
+            commands::get_totp_code,
+            commands::get_entry_history,
+            commands::restore_entry_version,
+            commands::purge_entry_history,
             commands::delete_password_entry,
+            commands::get_trashed_entries,
+            commands::restore_entry,
+            commands::empty_trash,
+            commands::add_attachment,
+            commands::get_attachments,
+            commands::export_attachment,
+            commands::export_vault,
+            commands::delete_attachment,
             commands::search_entries,
             commands::generate_password,
+            commands::generate_password_with_policy,
+            commands::generate_passphrase,
+            commands::estimate_strength,
+            commands::save_password_policy,
+            commands::get_password_policies,
+            commands::delete_password_policy,
+            commands::get_categories,
+            commands::create_category,
+            commands::update_category,
+            commands::delete_category,
             commands::logout
         ])
         .run(tauri::generate_context!())
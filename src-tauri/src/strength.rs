@@ -0,0 +1,786 @@
+use chrono::Datelike;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::generator;
+
+/// Very common passwords, most common first
+const COMMON_PASSWORDS: &str = include_str!("../wordlists/common_passwords.txt");
+
+/// Only this many leading characters are analysed
+const MAX_ANALYZED_LENGTH: usize = 100;
+
+/// Shortest token looked up in a dictionary
+const MIN_WORD_LENGTH: usize = 3;
+
+/// Longest token looked up in a dictionary
+const MAX_WORD_LENGTH: usize = 32;
+
+/// Guesses per character not covered by any pattern
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// Guesses per second of an offline attack on the Argon2 password hash
+const GUESSES_PER_SECOND: f64 = 1e4;
+
+/// Scores at or below this count as weak, wherever a password is judged
+pub const WEAK_SCORE: u8 = 2;
+
+/// log10 of the guesses a password must exceed for scores 1 to 4
+const SCORE_THRESHOLDS: [f64; 4] = [3.0, 6.0, 8.0, 10.0];
+
+/// Dates and years closer than this to the current year count as this far
+const MIN_YEAR_SPACE: i32 = 20;
+
+/// Keys of a US QWERTY keyboard, unshifted and shifted, one row at a time.
+/// Every row sits half a key to the right of the one above.
+const QWERTY: [(&str, &str); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// Keys a keyboard walk can start from and the average number of neighbours
+const KEYBOARD_STARTING_POSITIONS: f64 = 47.0;
+const KEYBOARD_AVERAGE_DEGREE: f64 = 4.6;
+
+/// Characters commonly substituted for letters, with the letters they stand for
+const L33T_TABLE: &[(char, &str)] = &[
+    ('4', "a"),
+    ('@', "a"),
+    ('8', "b"),
+    ('(', "c"),
+    ('3', "e"),
+    ('6', "g"),
+    ('9', "g"),
+    ('1', "il"),
+    ('!', "i"),
+    ('|', "il"),
+    ('0', "o"),
+    ('$', "s"),
+    ('5', "s"),
+    ('7', "t"),
+    ('+', "t"),
+    ('2', "z"),
+];
+
+const DATE_SEPARATORS: &str = "/\\-._ ";
+
+static COMMON_DICTIONARY: OnceLock<HashMap<String, usize>> = OnceLock::new();
+static WORD_DICTIONARY: OnceLock<HashMap<String, usize>> = OnceLock::new();
+
+/// How hard a password is to guess
+#[derive(Debug, Clone, Serialize)]
+pub struct StrengthEstimate {
+    /// 0 (too guessable) to 4 (very unguessable)
+    pub score: u8,
+    pub guesses_log10: f64,
+    /// Time to guess the password offline against the master password hash
+    pub crack_time_seconds: f64,
+    pub crack_time_display: String,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dictionary {
+    CommonPasswords,
+    Words,
+    UserInputs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    Dictionary {
+        dictionary: Dictionary,
+        capitalized: bool,
+        l33t: bool,
+        reversed: bool,
+    },
+    Spatial {
+        turns: usize,
+    },
+    Sequence,
+    Repeat,
+    Date,
+}
+
+/// A pattern found at `start..end` of the password
+#[derive(Debug, Clone)]
+struct Match {
+    start: usize,
+    end: usize,
+    pattern: Pattern,
+    guesses: f64,
+}
+
+impl Match {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// Estimate how many guesses an attacker who knows common patterns needs
+///
+/// The password is split into the sequence of patterns (common passwords,
+/// dictionary words, keyboard walks, sequences, repeats and dates) that
+/// needs the fewest guesses; anything no pattern covers is brute forced.
+/// `user_inputs` are words such as names or site details that an attacker
+/// targeting this password would try first.
+pub fn estimate_strength(password: &str, user_inputs: &[String]) -> StrengthEstimate {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYZED_LENGTH).collect();
+    let estimator = Estimator {
+        user_inputs: user_input_dictionary(user_inputs),
+        reference_year: chrono::Utc::now().year(),
+    };
+
+    let (guesses_log10, matches) = estimator.most_guessable(&chars);
+    let score = SCORE_THRESHOLDS.iter().filter(|&&threshold| guesses_log10 > threshold).count() as u8;
+    let crack_time_seconds = 10f64.powf(guesses_log10) / GUESSES_PER_SECOND;
+    let (warning, suggestions) = feedback(score, &matches, chars.len());
+
+    StrengthEstimate {
+        score,
+        guesses_log10,
+        crack_time_seconds,
+        crack_time_display: display_time(crack_time_seconds),
+        warning,
+        suggestions,
+    }
+}
+
+struct Estimator {
+    user_inputs: HashMap<String, usize>,
+    reference_year: i32,
+}
+
+impl Estimator {
+    /// log10 of the fewest guesses for `chars`, and the matches that give it
+    fn most_guessable(&self, chars: &[char]) -> (f64, Vec<Match>) {
+        let n = chars.len();
+        let matches = self.matches(chars);
+
+        // best[k] covers the first k characters; back[k] is the match ending
+        // there, or None when character k - 1 is brute forced
+        let mut best = vec![0.0; n + 1];
+        let mut back: Vec<Option<usize>> = vec![None; n + 1];
+        for k in 1..=n {
+            best[k] = best[k - 1] + BRUTEFORCE_CARDINALITY.log10();
+            for (idx, m) in matches.iter().enumerate().filter(|(_, m)| m.end == k) {
+                let min_guesses = if m.len() == 1 { 10.0 } else { 50.0 };
+                let candidate = best[m.start] + m.guesses.max(min_guesses).log10();
+                if candidate < best[k] {
+                    best[k] = candidate;
+                    back[k] = Some(idx);
+                }
+            }
+        }
+
+        let mut sequence = Vec::new();
+        let mut k = n;
+        while k > 0 {
+            match back[k] {
+                Some(idx) => {
+                    sequence.push(matches[idx].clone());
+                    k = matches[idx].start;
+                }
+                None => k -= 1,
+            }
+        }
+        sequence.reverse();
+
+        (best[n], sequence)
+    }
+
+    fn matches(&self, chars: &[char]) -> Vec<Match> {
+        let mut matches = self.dictionary_matches(chars);
+        matches.extend(spatial_matches(chars));
+        matches.extend(sequence_matches(chars));
+        matches.extend(self.repeat_matches(chars));
+        matches.extend(self.date_matches(chars));
+        matches
+    }
+
+    fn dictionary_matches(&self, chars: &[char]) -> Vec<Match> {
+        let dictionaries = [
+            (Dictionary::CommonPasswords, common_dictionary()),
+            (Dictionary::Words, word_dictionary()),
+            (Dictionary::UserInputs, &self.user_inputs),
+        ];
+
+        let mut matches = Vec::new();
+        for start in 0..chars.len() {
+            let longest = (start + MAX_WORD_LENGTH).min(chars.len());
+            for end in (start + MIN_WORD_LENGTH)..=longest {
+                let token = &chars[start..end];
+                let lower: String = token.iter().map(|c| c.to_ascii_lowercase()).collect();
+                let reversed: String = lower.chars().rev().collect();
+                let unleeted = unleet(token);
+
+                let mut candidates = vec![(lower, false, false), (reversed, false, true)];
+                candidates.extend(unleeted.into_iter().map(|word| (word, true, false)));
+
+                for (word, l33t, reversed) in candidates {
+                    for (dictionary, words) in &dictionaries {
+                        let Some(&rank) = words.get(&word) else { continue };
+
+                        let mut guesses = rank as f64 * uppercase_variations(token);
+                        if l33t {
+                            guesses *= l33t_variations(token, &word);
+                        }
+                        if reversed {
+                            guesses *= 2.0;
+                        }
+                        matches.push(Match {
+                            start,
+                            end,
+                            pattern: Pattern::Dictionary {
+                                dictionary: *dictionary,
+                                capitalized: token.iter().any(|c| c.is_uppercase()),
+                                l33t,
+                                reversed,
+                            },
+                            guesses,
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// Blocks repeated back to back, such as "aaa" or "abcabc"
+    fn repeat_matches(&self, chars: &[char]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for start in 0..chars.len() {
+            for block_len in 1..=(chars.len() - start) / 2 {
+                let block = &chars[start..start + block_len];
+                // Only the leftmost copy of a repetition starts a match
+                if start >= block_len && &chars[start - block_len..start] == block {
+                    continue;
+                }
+
+                let mut count = 1;
+                while chars[start + count * block_len..].starts_with(block) {
+                    count += 1;
+                }
+                if count < 2 {
+                    continue;
+                }
+
+                let (block_log10, _) = self.most_guessable(block);
+                matches.push(Match {
+                    start,
+                    end: start + count * block_len,
+                    pattern: Pattern::Repeat,
+                    guesses: 10f64.powf(block_log10) * count as f64,
+                });
+                // Longer blocks starting here only cover less of the run
+                break;
+            }
+        }
+        matches
+    }
+
+    /// Dates such as 1987, 13/04/1987, 1987-04-13 or 130487
+    fn date_matches(&self, chars: &[char]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for start in 0..chars.len() {
+            for end in (start + 4)..=(start + 10).min(chars.len()) {
+                let token: String = chars[start..end].iter().collect();
+                let Some((year, separated)) = parse_date(&token) else { continue };
+
+                let year_space = (year - self.reference_year).abs().max(MIN_YEAR_SPACE) as f64;
+                let guesses = match (token.len(), separated) {
+                    (4, false) if token.parse::<i32>() == Ok(year) => year_space,
+                    (_, false) => 365.0 * year_space,
+                    (_, true) => 365.0 * year_space * 4.0,
+                };
+                matches.push(Match {
+                    start,
+                    end,
+                    pattern: Pattern::Date,
+                    guesses,
+                });
+            }
+        }
+        matches
+    }
+}
+
+fn common_dictionary() -> &'static HashMap<String, usize> {
+    COMMON_DICTIONARY.get_or_init(|| {
+        COMMON_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(idx, word)| (word.to_lowercase(), idx + 1))
+            .collect()
+    })
+}
+
+/// Words of the passphrase wordlist, all equally likely
+fn word_dictionary() -> &'static HashMap<String, usize> {
+    WORD_DICTIONARY.get_or_init(|| {
+        let words = generator::wordlist();
+        words.iter().map(|word| (word.to_lowercase(), words.len())).collect()
+    })
+}
+
+/// Rank user inputs by position, splitting values such as email addresses
+/// into their parts as well
+fn user_input_dictionary(inputs: &[String]) -> HashMap<String, usize> {
+    let mut dictionary = HashMap::new();
+    let words = inputs.iter().flat_map(|input| {
+        let input = input.trim().to_lowercase();
+        let parts: Vec<String> = input
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| part.chars().count() >= MIN_WORD_LENGTH)
+            .map(str::to_string)
+            .collect();
+        std::iter::once(input).chain(parts)
+    });
+    for word in words.filter(|word| !word.is_empty()) {
+        let rank = dictionary.len() + 1;
+        dictionary.entry(word).or_insert(rank);
+    }
+    dictionary
+}
+
+/// Readings of `token` with l33t characters replaced by letters. Ambiguous
+/// characters such as '1' are read as each of their letters in turn.
+fn unleet(token: &[char]) -> Vec<String> {
+    if !token.iter().any(|c| l33t_letters(*c).is_some()) {
+        return Vec::new();
+    }
+
+    let mut readings = Vec::new();
+    for variant in 0..2 {
+        let reading: String = token
+            .iter()
+            .map(|&c| match l33t_letters(c) {
+                Some(letters) => letters.chars().nth(variant).unwrap_or_else(|| letters.chars().next().unwrap()),
+                None => c.to_ascii_lowercase(),
+            })
+            .collect();
+        if !readings.contains(&reading) {
+            readings.push(reading);
+        }
+    }
+    readings
+}
+
+fn l33t_letters(c: char) -> Option<&'static str> {
+    L33T_TABLE.iter().find(|(sub, _)| *sub == c).map(|(_, letters)| *letters)
+}
+
+fn n_choose_k(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Ways to place `changed` marked characters among `changed + unchanged`,
+/// counting at least one of each to avoid a free pass for all-changed tokens
+fn variations(changed: usize, unchanged: usize) -> f64 {
+    if changed == 0 || unchanged == 0 {
+        return if changed == 0 { 1.0 } else { 2.0 };
+    }
+    (1..=changed.min(unchanged)).map(|i| n_choose_k(changed + unchanged, i)).sum()
+}
+
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+
+    // Capitalising the first or last letter is the first thing attackers try
+    let first_or_last = token.first().is_some_and(|c| c.is_uppercase())
+        || token.last().is_some_and(|c| c.is_uppercase());
+    if upper == 1 && first_or_last {
+        return 2.0;
+    }
+    variations(upper, lower)
+}
+
+fn l33t_variations(token: &[char], word: &str) -> f64 {
+    let mut per_letter: HashMap<char, (usize, usize)> = HashMap::new();
+    for (&c, letter) in token.iter().zip(word.chars()) {
+        let entry = per_letter.entry(letter).or_default();
+        if c.to_ascii_lowercase() == letter {
+            entry.1 += 1;
+        } else {
+            entry.0 += 1;
+        }
+    }
+    per_letter
+        .values()
+        .filter(|(substituted, _)| *substituted > 0)
+        .map(|&(substituted, unsubstituted)| variations(substituted, unsubstituted))
+        .product()
+}
+
+/// Row, column and shift state of a key on the QWERTY layout
+fn key_position(c: char) -> Option<(i32, i32, bool)> {
+    QWERTY.iter().enumerate().find_map(|(row, (plain, shifted))| {
+        plain
+            .chars()
+            .position(|k| k == c)
+            .map(|col| (row as i32, col as i32, false))
+            .or_else(|| shifted.chars().position(|k| k == c).map(|col| (row as i32, col as i32, true)))
+    })
+}
+
+/// Direction from key `a` to key `b`, if they are neighbours
+fn key_direction(a: char, b: char) -> Option<usize> {
+    const NEIGHBOURS: [(i32, i32); 6] = [(0, -1), (0, 1), (-1, 0), (-1, 1), (1, -1), (1, 0)];
+
+    let (row_a, col_a, _) = key_position(a)?;
+    let (row_b, col_b, _) = key_position(b)?;
+    NEIGHBOURS.iter().position(|&offset| offset == (row_b - row_a, col_b - col_a))
+}
+
+/// Runs of neighbouring keys such as "qwerty" or "zxcvfr"
+fn spatial_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut last_direction = None;
+        while end < chars.len() {
+            let Some(direction) = key_direction(chars[end - 1], chars[end]) else { break };
+            if last_direction != Some(direction) {
+                turns += 1;
+                last_direction = Some(direction);
+            }
+            end += 1;
+        }
+
+        let len = end - start;
+        if len >= 3 {
+            let shifted = chars[start..end]
+                .iter()
+                .filter(|&&c| key_position(c).is_some_and(|(_, _, shifted)| shifted))
+                .count();
+
+            let mut guesses = 0.0;
+            for i in 2..=len {
+                for j in 1..=turns.min(i - 1) {
+                    guesses += n_choose_k(i - 1, j - 1)
+                        * KEYBOARD_STARTING_POSITIONS
+                        * KEYBOARD_AVERAGE_DEGREE.powi(j as i32);
+                }
+            }
+            guesses *= variations(shifted, len - shifted);
+
+            matches.push(Match {
+                start,
+                end,
+                pattern: Pattern::Spatial { turns },
+                guesses,
+            });
+        }
+        start = end;
+    }
+    matches
+}
+
+/// Runs with a constant small step, such as "abc", "9753" or "ZYX"
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    fn class(c: char) -> Option<u8> {
+        match c {
+            'a'..='z' => Some(0),
+            'A'..='Z' => Some(1),
+            '0'..='9' => Some(2),
+            _ => None,
+        }
+    }
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let delta = chars[start + 1] as i32 - chars[start] as i32;
+        let mut end = start + 1;
+        while end < chars.len()
+            && chars[end] as i32 - chars[end - 1] as i32 == delta
+            && class(chars[end]).is_some()
+            && class(chars[end]) == class(chars[start])
+        {
+            end += 1;
+        }
+
+        let len = end - start;
+        if len >= 3 && (1..=5).contains(&delta.abs()) {
+            let first = chars[start];
+            let base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let direction = if delta < 0 { 2.0 } else { 1.0 };
+            matches.push(Match {
+                start,
+                end,
+                pattern: Pattern::Sequence,
+                guesses: base * len as f64 * direction,
+            });
+        }
+
+        // Sequences may share their boundary character, as in "abcba"
+        start = if len > 1 { end - 1 } else { end };
+    }
+    matches
+}
+
+/// Year of a date written as digits, optionally split by one repeated
+/// separator, and whether it was separated
+fn parse_date(token: &str) -> Option<(i32, bool)> {
+    if !token.chars().all(|c| c.is_ascii_digit() || DATE_SEPARATORS.contains(c)) {
+        return None;
+    }
+
+    if token.chars().all(|c| c.is_ascii_digit()) {
+        if token.len() == 4 {
+            let year: i32 = token.parse().ok()?;
+            if (1900..=2050).contains(&year) {
+                return Some((year, false));
+            }
+        }
+        // Try every way of cutting the digits into three parts
+        for first in 1..=4.min(token.len()) {
+            for second in 1..=2.min(token.len() - first) {
+                let rest = token.len() - first - second;
+                if rest == 0 || rest > 4 {
+                    continue;
+                }
+                let parts = [&token[..first], &token[first..first + second], &token[first + second..]];
+                if let Some(year) = date_year(parts) {
+                    return Some((year, false));
+                }
+            }
+        }
+        return None;
+    }
+
+    let separator = token.chars().find(|c| DATE_SEPARATORS.contains(*c))?;
+    let parts: Vec<&str> = token.split(separator).collect();
+    match parts[..] {
+        [a, b, c] if [a, b, c].iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())) => {
+            date_year([a, b, c]).map(|year| (year, true))
+        }
+        _ => None,
+    }
+}
+
+/// Year of a day, month and year in any common order
+fn date_year(parts: [&str; 3]) -> Option<i32> {
+    let [a, b, c] = parts;
+    // (year, month, day) in day-month-year, month-day-year and year-month-day order
+    let orders = [(c, b, a), (c, a, b), (a, b, c)];
+    orders.into_iter().find_map(|(year, month, day)| {
+        if month.len() > 2 || day.len() > 2 {
+            return None;
+        }
+        let month: u32 = month.parse().ok()?;
+        let day: u32 = day.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let value: i32 = year.parse().ok()?;
+        match year.len() {
+            2 if value > 50 => Some(1900 + value),
+            2 => Some(2000 + value),
+            4 if (1000..=2050).contains(&value) => Some(value),
+            _ => None,
+        }
+    })
+}
+
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (amount, unit) = if seconds < 1.0 {
+        return "less than a second".to_string();
+    } else if seconds < MINUTE {
+        (seconds, "second")
+    } else if seconds < HOUR {
+        (seconds / MINUTE, "minute")
+    } else if seconds < DAY {
+        (seconds / HOUR, "hour")
+    } else if seconds < MONTH {
+        (seconds / DAY, "day")
+    } else if seconds < YEAR {
+        (seconds / MONTH, "month")
+    } else if seconds < CENTURY {
+        (seconds / YEAR, "year")
+    } else {
+        return "centuries".to_string();
+    };
+
+    let amount = amount.round() as u64;
+    if amount == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{amount} {unit}s")
+    }
+}
+
+/// A warning about the most prominent pattern and suggestions to improve
+fn feedback(score: u8, matches: &[Match], length: usize) -> (Option<String>, Vec<String>) {
+    if length == 0 {
+        return (
+            None,
+            vec![
+                "Use a few words, avoid common phrases".to_string(),
+                "No need for symbols, digits, or uppercase letters".to_string(),
+            ],
+        );
+    }
+    if score > 2 {
+        return (None, Vec::new());
+    }
+
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better.".to_string()];
+    let Some(longest) = matches.iter().max_by_key(|m| m.len()) else {
+        suggestions.push("Use a longer password".to_string());
+        return (None, suggestions);
+    };
+    let only_match = matches.len() == 1 && longest.len() == length;
+
+    let warning = match &longest.pattern {
+        Pattern::Dictionary {
+            dictionary,
+            capitalized,
+            l33t,
+            reversed,
+        } => {
+            if *capitalized {
+                suggestions.push("Capitalization doesn't help very much".to_string());
+            }
+            if *reversed {
+                suggestions.push("Reversed words aren't much harder to guess".to_string());
+            }
+            if *l33t {
+                suggestions.push("Predictable substitutions like '@' instead of 'a' don't help very much".to_string());
+            }
+            match dictionary {
+                Dictionary::CommonPasswords if only_match && !l33t && !reversed => {
+                    Some("This is a very common password")
+                }
+                Dictionary::CommonPasswords => Some("This is similar to a commonly used password"),
+                Dictionary::Words if only_match => Some("A word by itself is easy to guess"),
+                Dictionary::Words => None,
+                Dictionary::UserInputs => Some("Names and details tied to this account are easy to guess"),
+            }
+        }
+        Pattern::Spatial { turns } => {
+            suggestions.push("Use a longer keyboard pattern with more turns".to_string());
+            if *turns == 1 {
+                Some("Straight rows of keys are easy to guess")
+            } else {
+                Some("Short keyboard patterns are easy to guess")
+            }
+        }
+        Pattern::Sequence => {
+            suggestions.push("Avoid sequences".to_string());
+            Some("Sequences like abc or 6543 are easy to guess")
+        }
+        Pattern::Repeat => {
+            suggestions.push("Avoid repeated words and characters".to_string());
+            Some("Repeats like \"aaa\" or \"abcabc\" are easy to guess")
+        }
+        Pattern::Date => {
+            suggestions.push("Avoid dates and years that are associated with you".to_string());
+            Some("Dates are often easy to guess")
+        }
+    };
+
+    (warning.map(str::to_string), suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(password: &str) -> Vec<Pattern> {
+        let estimator = Estimator {
+            user_inputs: HashMap::new(),
+            reference_year: 2024,
+        };
+        let chars: Vec<char> = password.chars().collect();
+        estimator.most_guessable(&chars).1.into_iter().map(|m| m.pattern).collect()
+    }
+
+    #[test]
+    fn test_common_and_weak_passwords() {
+        for password in ["password", "123456", "qwerty", "P@ssw0rd", "drowssap"] {
+            let estimate = estimate_strength(password, &[]);
+            assert_eq!(estimate.score, 0, "{password} should be too guessable");
+            assert!(estimate.warning.is_some());
+            assert!(!estimate.suggestions.is_empty());
+        }
+
+        let estimate = estimate_strength("password", &[]);
+        assert_eq!(estimate.warning.as_deref(), Some("This is a very common password"));
+        assert_eq!(estimate.crack_time_display, "less than a second");
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(matches!(patterns("sdfghjk")[..], [Pattern::Spatial { turns: 1 }]));
+        assert!(matches!(patterns("qazxdr")[..], [Pattern::Spatial { .. }]));
+        assert_eq!(patterns("abcdefg"), [Pattern::Sequence]);
+        assert_eq!(patterns("97531"), [Pattern::Sequence]);
+        assert_eq!(patterns("%%%%%%"), [Pattern::Repeat]);
+        assert_eq!(patterns("13/04/1987"), [Pattern::Date]);
+        assert_eq!(patterns("19870413"), [Pattern::Date]);
+        assert!(matches!(
+            patterns("M0nk3y")[..],
+            [Pattern::Dictionary { dictionary: Dictionary::CommonPasswords, l33t: true, capitalized: true, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_user_inputs() {
+        let inputs = vec!["alice.smith@example.com".to_string()];
+        let estimate = estimate_strength("smith", &inputs);
+        assert_eq!(estimate.score, 0);
+        assert_eq!(
+            estimate.warning.as_deref(),
+            Some("Names and details tied to this account are easy to guess")
+        );
+        assert!(estimate_strength("smith", &[]).guesses_log10 > estimate.guesses_log10);
+    }
+
+    #[test]
+    fn test_strong_passwords() {
+        for password in ["Xk9#mQ2$vL7!pR4&", "correct-HORSE-battery-staple-93"] {
+            let estimate = estimate_strength(password, &[]);
+            assert_eq!(estimate.score, 4, "{password} should be strong");
+            assert!(estimate.warning.is_none());
+            assert!(estimate.suggestions.is_empty());
+            assert_eq!(estimate.crack_time_display, "centuries");
+        }
+
+        // Longer random strings never get weaker
+        let short = estimate_strength("Xk9#mQ", &[]);
+        let long = estimate_strength("Xk9#mQ2$vL", &[]);
+        assert!(long.guesses_log10 > short.guesses_log10);
+    }
+
+    #[test]
+    fn test_empty_password() {
+        let estimate = estimate_strength("", &[]);
+        assert_eq!(estimate.score, 0);
+        assert_eq!(estimate.guesses_log10, 0.0);
+        assert_eq!(estimate.suggestions.len(), 2);
+    }
+}
@@ -2,6 +2,7 @@ import { createEffect, Show } from "solid-js";
 import { authState, initializeAuth, isLoading } from "./store/auth";
 import Setup from "./components/Auth/Setup";
 import LoginComponent from "./components/Auth/LoginComponent";
+import RecoveryCodeDialog from "./components/Auth/RecoveryCodeDialog";
 import Dashboard from "./views/Dashboard";
 import ErrorBoundary from "./components/StateFeedback/ErrorBoundary";
 import { ToastContainer } from "./components/StateFeedback/Toast";
@@ -39,6 +40,9 @@ function App() {
           </Show>
         </Show>
 
+        {/* Newly issued recovery code, shown once */}
+        <RecoveryCodeDialog />
+
         {/* Global Toast Container */}
         <ToastContainer />
       </main>
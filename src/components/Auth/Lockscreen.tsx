@@ -1,5 +1,5 @@
-import { createSignal, createEffect } from "solid-js";
-import { login, isLoading, error, clearError } from "../../store/auth";
+import { createSignal, Show } from "solid-js";
+import { login, authState, isLoading, error, clearError, retryAfter } from "../../store/auth";
 
 interface LockscreenProps {
   onUnlock?: () => void;
@@ -8,32 +8,10 @@ interface LockscreenProps {
 
 const Lockscreen = (props: LockscreenProps) => {
   const [password, setPassword] = createSignal("");
-  const [attempts, setAttempts] = createSignal(0);
-  const [isBlocked, setIsBlocked] = createSignal(false);
-  const [blockTimeRemaining, setBlockTimeRemaining] = createSignal(0);
+  const [keyFilePath, setKeyFilePath] = createSignal("");
 
-  const MAX_ATTEMPTS = 5;
-  const BLOCK_DURATION = 300; // 5 minutes in seconds
-
-  // Handle failed attempts and blocking
-  createEffect(() => {
-    if (attempts() >= MAX_ATTEMPTS && !isBlocked()) {
-      setIsBlocked(true);
-      setBlockTimeRemaining(BLOCK_DURATION);
-      
-      const interval = setInterval(() => {
-        setBlockTimeRemaining(prev => {
-          if (prev <= 1) {
-            clearInterval(interval);
-            setIsBlocked(false);
-            setAttempts(0);
-            return 0;
-          }
-          return prev - 1;
-        });
-      }, 1000);
-    }
-  });
+  // Failed attempts are counted and throttled by the backend
+  const isBlocked = () => retryAfter() > 0;
 
   const handleSubmit = async (e: Event) => {
     e.preventDefault();
@@ -44,14 +22,10 @@ const Lockscreen = (props: LockscreenProps) => {
     const pwd = password();
     if (!pwd) return;
 
-    const success = await login(pwd);
+    const success = await login(pwd, authState().requiresKeyFile ? keyFilePath() : undefined);
+    setPassword("");
     if (success) {
-      setAttempts(0);
-      setPassword("");
       props.onUnlock?.();
-    } else {
-      setAttempts(prev => prev + 1);
-      setPassword("");
     }
   };
 
@@ -87,15 +61,22 @@ const Lockscreen = (props: LockscreenProps) => {
               />
             </div>
 
-            {isBlocked() && (
-              <div class="block-message">
-                <p>Too many failed attempts. Try again in {formatTime(blockTimeRemaining())}</p>
+            <Show when={authState().requiresKeyFile}>
+              <div class="form-group">
+                <input
+                  type="text"
+                  value={keyFilePath()}
+                  onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                  placeholder="Key file path"
+                  disabled={isBlocked() || isLoading()}
+                  class="lockscreen-input"
+                />
               </div>
-            )}
+            </Show>
 
-            {attempts() > 0 && attempts() < MAX_ATTEMPTS && !isBlocked() && (
-              <div class="attempts-warning">
-                <p>Invalid password. {MAX_ATTEMPTS - attempts()} attempts remaining.</p>
+            {isBlocked() && (
+              <div class="block-message">
+                <p>Too many failed attempts. Try again in {formatTime(retryAfter())}</p>
               </div>
             )}
 
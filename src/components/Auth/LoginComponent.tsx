@@ -1,8 +1,25 @@
-import { createSignal } from "solid-js";
-import { login, isLoading, error, clearError } from "../../store/auth";
+import { createSignal, onMount, Show } from "solid-js";
+import { PasswordVaultAPI } from "../../services/api";
+import { login, recoverWithKey, authState, isLoading, error, clearError, retryAfter } from "../../store/auth";
 
 const LoginComponent = () => {
   const [password, setPassword] = createSignal("");
+  const [keyFilePath, setKeyFilePath] = createSignal("");
+  const [isRecovering, setIsRecovering] = createSignal(false);
+  const [recoveryCode, setRecoveryCode] = createSignal("");
+  const [newPassword, setNewPassword] = createSignal("");
+  const [confirmNewPassword, setConfirmNewPassword] = createSignal("");
+  const [validationError, setValidationError] = createSignal("");
+  const [isRecoveringVault, setIsRecoveringVault] = createSignal(false);
+  const [hasPlaintextMetadata, setHasPlaintextMetadata] = createSignal(false);
+
+  onMount(async () => {
+    try {
+      setHasPlaintextMetadata(await PasswordVaultAPI.hasPlaintextMetadata());
+    } catch (err) {
+      console.error("Failed to check for plaintext metadata:", err);
+    }
+  });
 
   const handleSubmit = async (e: Event) => {
     e.preventDefault();
@@ -11,46 +28,169 @@ const LoginComponent = () => {
     const pwd = password();
     if (!pwd) return;
 
-    const success = await login(pwd);
+    const success = await login(pwd, authState().requiresKeyFile ? keyFilePath() : undefined);
     if (success) {
       // Login successful, the auth state will handle navigation
       console.log("Login successful");
     }
   };
 
+  const handleRecover = async (e: Event) => {
+    e.preventDefault();
+    clearError();
+    setValidationError("");
+
+    if (newPassword().length < 8) {
+      setValidationError("Password must be at least 8 characters long");
+      return;
+    }
+
+    if (newPassword() !== confirmNewPassword()) {
+      setValidationError("Passwords do not match");
+      return;
+    }
+
+    setIsRecoveringVault(true);
+    await recoverWithKey(recoveryCode(), newPassword());
+    setIsRecoveringVault(false);
+  };
+
+  const toggleRecovery = () => {
+    clearError();
+    setValidationError("");
+    setIsRecovering(!isRecovering());
+  };
+
   return (
     <section class="login-container">
-      <div class="login-form">
-        <h1>Unlock Your Vault</h1>
-        <p>Enter your master password to access your passwords.</p>
+      <Show when={isRecovering()}>
+        <div class="login-form">
+          <h1>Recover Your Vault</h1>
+          <p>Enter your recovery code and choose a new master password.</p>
+
+          <form onSubmit={handleRecover}>
+            <div class="form-group">
+              <label for="recovery-code">Recovery Code</label>
+              <input
+                id="recovery-code"
+                type="text"
+                value={recoveryCode()}
+                onInput={(e) => setRecoveryCode(e.currentTarget.value)}
+                placeholder="XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX"
+                autocomplete="off"
+                required
+                autofocus
+              />
+            </div>
+
+            <div class="form-group">
+              <label for="new-master-password">New Master Password</label>
+              <input
+                id="new-master-password"
+                type="password"
+                value={newPassword()}
+                onInput={(e) => setNewPassword(e.currentTarget.value)}
+                placeholder="Enter new master password"
+                required
+                minLength={8}
+              />
+            </div>
+
+            <div class="form-group">
+              <label for="confirm-new-master-password">Confirm New Password</label>
+              <input
+                id="confirm-new-master-password"
+                type="password"
+                value={confirmNewPassword()}
+                onInput={(e) => setConfirmNewPassword(e.currentTarget.value)}
+                placeholder="Confirm new master password"
+                required
+                minLength={8}
+              />
+            </div>
+
+            {(validationError() || error()) && (
+              <div class="error-message">{validationError() || error()}</div>
+            )}
+
+            <button
+              type="submit"
+              disabled={isRecoveringVault() || !recoveryCode() || !newPassword()}
+              class="login-button"
+            >
+              {isRecoveringVault() ? "Recovering..." : "Recover Vault"}
+            </button>
+
+            <button type="button" class="link-button" onClick={toggleRecovery}>
+              Back to unlock
+            </button>
+          </form>
+        </div>
+      </Show>
+
+      <Show when={!isRecovering()}>
+        <div class="login-form">
+          <h1>Unlock Your Vault</h1>
+          <p>Enter your master password to access your passwords.</p>
+
+          <Show when={hasPlaintextMetadata()}>
+            <div class="notice-message">
+              This vault was created by an older version that stored entry
+              titles and categories unencrypted. They are encrypted and
+              scrubbed from the vault file when you unlock it.
+            </div>
+          </Show>
         
-        <form onSubmit={handleSubmit}>
-          <div class="form-group">
-            <label for="master-password">Master Password</label>
-            <input
-              id="master-password"
-              type="password"
-              value={password()}
-              onInput={(e) => setPassword(e.currentTarget.value)}
-              placeholder="Enter your master password"
-              required
-              autofocus
-            />
-          </div>
-
-          {error() && (
-            <div class="error-message">{error()}</div>
-          )}
-
-          <button 
-            type="submit" 
-            disabled={isLoading() || !password()}
-            class="login-button"
-          >
-            {isLoading() ? "Unlocking..." : "Unlock Vault"}
-          </button>
-        </form>
-      </div>
+          <form onSubmit={handleSubmit}>
+            <div class="form-group">
+              <label for="master-password">Master Password</label>
+              <input
+                id="master-password"
+                type="password"
+                value={password()}
+                onInput={(e) => setPassword(e.currentTarget.value)}
+                placeholder="Enter your master password"
+                required
+                autofocus
+              />
+            </div>
+
+            <Show when={authState().requiresKeyFile}>
+              <div class="form-group">
+                <label for="key-file-path">Key File</label>
+                <input
+                  id="key-file-path"
+                  type="text"
+                  value={keyFilePath()}
+                  onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                  placeholder="Path to your key file"
+                  required
+                />
+              </div>
+            </Show>
+
+            {retryAfter() > 0 ? (
+              <div class="error-message">
+                Too many failed attempts. Try again in {retryAfter()} seconds.
+              </div>
+            ) : error() && (
+              <div class="error-message">{error()}</div>
+            )}
+
+            <button 
+              type="submit" 
+              disabled={isLoading() || !password() || retryAfter() > 0}
+              class="login-button"
+            >
+              {isLoading() ? "Unlocking..." : "Unlock Vault"}
+            </button>
+
+            <button type="button" class="link-button" onClick={toggleRecovery}>
+              Forgot your master password?
+            </button>
+          </form>
+        </div>
+      </Show>
     </section>
   );
 };
@@ -0,0 +1,64 @@
+import { createSignal, Show } from "solid-js";
+import { recoveryCode, dismissRecoveryCode } from "../../store/auth";
+import { showToast } from "../StateFeedback/Toast";
+
+// Shows a freshly issued recovery code once; it cannot be displayed again
+const RecoveryCodeDialog = () => {
+  const [confirmed, setConfirmed] = createSignal(false);
+
+  const handleCopy = async () => {
+    try {
+      await navigator.clipboard.writeText(recoveryCode() ?? "");
+      showToast("Recovery code copied to clipboard", "success");
+    } catch (err) {
+      console.error("Failed to copy to clipboard:", err);
+      showToast("Failed to copy recovery code", "error");
+    }
+  };
+
+  const handleDone = () => {
+    setConfirmed(false);
+    dismissRecoveryCode();
+  };
+
+  return (
+    <Show when={recoveryCode()}>
+      <div class="modal-overlay">
+        <div class="modal-content">
+          <div class="modal-header">
+            <h3>Your Recovery Code</h3>
+          </div>
+
+          <div class="modal-body">
+            <p>
+              This code can unlock your vault if you forget your master password.
+              Write it down and keep it somewhere safe. It will not be shown again.
+            </p>
+
+            <pre class="recovery-code">{recoveryCode()}</pre>
+
+            <label class="setting-label">
+              <input
+                type="checkbox"
+                checked={confirmed()}
+                onChange={(e) => setConfirmed(e.currentTarget.checked)}
+              />
+              <span>I have saved my recovery code</span>
+            </label>
+          </div>
+
+          <div class="modal-actions">
+            <button class="secondary-button" onClick={handleCopy}>
+              Copy
+            </button>
+            <button class="primary-button" onClick={handleDone} disabled={!confirmed()}>
+              Done
+            </button>
+          </div>
+        </div>
+      </div>
+    </Show>
+  );
+};
+
+export default RecoveryCodeDialog;
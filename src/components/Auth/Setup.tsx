@@ -1,10 +1,13 @@
 import { createSignal } from "solid-js";
 import { setupMasterPassword, isLoading, error, clearError } from "../../store/auth";
+import StrengthMeter from "../ui/StrengthMeter";
 
 const Setup = () => {
   const [password, setPassword] = createSignal("");
   const [confirmPassword, setConfirmPassword] = createSignal("");
   const [validationError, setValidationError] = createSignal("");
+  const [createRecoveryCode, setCreateRecoveryCode] = createSignal(true);
+  const [keyFilePath, setKeyFilePath] = createSignal("");
 
   const handleSubmit = async (e: Event) => {
     e.preventDefault();
@@ -25,7 +28,7 @@ const Setup = () => {
       return;
     }
 
-    const success = await setupMasterPassword(pwd);
+    const success = await setupMasterPassword(pwd, createRecoveryCode(), keyFilePath() || undefined);
     if (success) {
       // Setup successful, the auth state will handle navigation
       console.log("Master password setup successful");
@@ -50,6 +53,7 @@ const Setup = () => {
               required
               minLength={8}
             />
+            <StrengthMeter password={password()} />
           </div>
 
           <div class="form-group">
@@ -65,6 +69,28 @@ const Setup = () => {
             />
           </div>
 
+          <div class="form-group">
+            <label for="key-file-path">Key File (optional)</label>
+            <input
+              id="key-file-path"
+              type="text"
+              value={keyFilePath()}
+              onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+              placeholder="Path to a file required alongside the password"
+            />
+          </div>
+
+          <div class="form-group checkbox-group">
+            <label>
+              <input
+                type="checkbox"
+                checked={createRecoveryCode()}
+                onChange={(e) => setCreateRecoveryCode(e.currentTarget.checked)}
+              />
+              <span>Create a recovery code in case I forget this password</span>
+            </label>
+          </div>
+
           {validationError() && (
             <div class="error-message">{validationError()}</div>
           )}
@@ -4,6 +4,7 @@ import {
   navigateToAll, 
   navigateToFavorites, 
   navigateToCategory, 
+  navigateToTrash,
   navigateToSettings 
 } from "../../store/router";
 import { categories } from "../../store/categories";
@@ -18,7 +19,7 @@ const Sidebar = (props: SidebarProps) => {
 
   const getEntryCount = () => entries().length;
   const getFavoriteCount = () => getFavoriteEntries().length;
-  const getCategoryCount = (categoryName: string) => getEntriesByCategory(categoryName).length;
+  const getCategoryCount = (categoryId: string) => getEntriesByCategory(categoryId).length;
 
   return (
     <div class="sidebar-container">
@@ -88,8 +89,8 @@ const Sidebar = (props: SidebarProps) => {
                         {category.icon}
                       </span>
                       <span>{category.name}</span>
-                      <Show when={getCategoryCount(category.name) > 0}>
-                        <span class="item-count">{getCategoryCount(category.name)}</span>
+                      <Show when={getCategoryCount(category.id) > 0}>
+                        <span class="item-count">{getCategoryCount(category.id)}</span>
                       </Show>
                     </button>
                   </li>
@@ -97,7 +98,7 @@ const Sidebar = (props: SidebarProps) => {
               </For>
               
               {/* Show uncategorized if there are any */}
-              <Show when={entries().some(e => !e.category)}>
+              <Show when={entries().some(e => !e.category_id)}>
                 <li class="sidebar-item subcategory">
                   <button 
                     onClick={() => navigateToCategory('uncategorized')} 
@@ -106,7 +107,7 @@ const Sidebar = (props: SidebarProps) => {
                     <span class="category-icon">📁</span>
                     <span>Uncategorized</span>
                     <span class="item-count">
-                      {entries().filter(e => !e.category).length}
+                      {entries().filter(e => !e.category_id).length}
                     </span>
                   </button>
                 </li>
@@ -117,6 +118,14 @@ const Sidebar = (props: SidebarProps) => {
 
         {/* Settings */}
         <ul class="sidebar-items sidebar-bottom">
+          <li class={`sidebar-item ${currentView() === 'trash' ? 'active' : ''}`}>
+            <button onClick={navigateToTrash} class="sidebar-button">
+              <svg class="sidebar-icon" width="20" height="20" viewBox="0 0 24 24" fill="currentColor">
+                <path d="M19,4H15.5L14.5,3H9.5L8.5,4H5V6H19M6,19A2,2 0 0,0 8,21H16A2,2 0 0,0 18,19V7H6V19Z" />
+              </svg>
+              <span>Trash</span>
+            </button>
+          </li>
           <li class={`sidebar-item ${currentView() === 'settings' ? 'active' : ''}`}>
             <button onClick={navigateToSettings} class="sidebar-button">
               <svg class="sidebar-icon" width="20" height="20" viewBox="0 0 24 24" fill="currentColor">
@@ -0,0 +1,197 @@
+import { createSignal, onMount, For, Show } from "solid-js";
+import { policies, loadPolicies, savePolicy, deletePolicy, generateWithPolicy, defaultPolicy, error } from "../../store/generator";
+import { showToast } from "../StateFeedback/Toast";
+import type { PasswordPolicy } from "../../types";
+
+// Settings tab for building, previewing and saving generator policies
+const GeneratorSettings = () => {
+  const [name, setName] = createSignal("");
+  const [policy, setPolicy] = createSignal<PasswordPolicy>({ ...defaultPolicy });
+  const [preview, setPreview] = createSignal("");
+
+  onMount(loadPolicies);
+
+  const updatePolicy = <K extends keyof PasswordPolicy>(key: K, value: PasswordPolicy[K]) => {
+    setPolicy(prev => ({ ...prev, [key]: value }));
+  };
+
+  const handlePreview = async () => {
+    const password = await generateWithPolicy(policy());
+    setPreview(password);
+    if (!password) {
+      showToast(error() ?? "Policy cannot produce a password", "error");
+    }
+  };
+
+  const handleSave = async () => {
+    if (!name().trim()) {
+      showToast("Policy name is required", "error");
+      return;
+    }
+
+    if (await savePolicy(name().trim(), policy())) {
+      showToast("Policy saved", "success");
+      setName("");
+    } else {
+      showToast(error() ?? "Failed to save policy", "error");
+    }
+  };
+
+  const handleDelete = async (id: string) => {
+    if (!(await deletePolicy(id))) {
+      showToast(error() ?? "Failed to delete policy", "error");
+    }
+  };
+
+  const classRow = (
+    label: string,
+    enabled: () => boolean,
+    setEnabled: (value: boolean) => void,
+    min: () => number,
+    setMin: (value: number) => void,
+  ) => (
+    <div class="setting-item">
+      <label class="setting-label">
+        <input
+          type="checkbox"
+          checked={enabled()}
+          onChange={(e) => setEnabled(e.currentTarget.checked)}
+        />
+        <span>{label}</span>
+      </label>
+      <Show when={enabled()}>
+        <label class="setting-description">
+          At least{" "}
+          <input
+            type="number"
+            min={0}
+            value={min()}
+            onInput={(e) => setMin(parseInt(e.currentTarget.value) || 0)}
+            class="setting-number"
+          />
+        </label>
+      </Show>
+    </div>
+  );
+
+  return (
+    <div class="settings-section">
+      <h3>Password Generator</h3>
+
+      <div class="setting-item">
+        <label class="setting-label">Length</label>
+        <input
+          type="number"
+          min={1}
+          max={1024}
+          value={policy().length}
+          onInput={(e) => updatePolicy("length", parseInt(e.currentTarget.value) || 0)}
+          class="setting-number"
+        />
+      </div>
+
+      {classRow("Lowercase letters", () => policy().lowercase, (v) => updatePolicy("lowercase", v),
+        () => policy().min_lowercase, (v) => updatePolicy("min_lowercase", v))}
+      {classRow("Uppercase letters", () => policy().uppercase, (v) => updatePolicy("uppercase", v),
+        () => policy().min_uppercase, (v) => updatePolicy("min_uppercase", v))}
+      {classRow("Digits", () => policy().digits, (v) => updatePolicy("digits", v),
+        () => policy().min_digits, (v) => updatePolicy("min_digits", v))}
+      {classRow("Symbols", () => policy().symbols !== null,
+        (v) => updatePolicy("symbols", v ? defaultPolicy.symbols : null),
+        () => policy().min_symbols, (v) => updatePolicy("min_symbols", v))}
+
+      <Show when={policy().symbols !== null}>
+        <div class="setting-item">
+          <label class="setting-label">Symbol Set</label>
+          <input
+            type="text"
+            value={policy().symbols ?? ""}
+            onInput={(e) => updatePolicy("symbols", e.currentTarget.value)}
+            class="setting-input"
+          />
+          <p class="setting-description">Only these symbols are used</p>
+        </div>
+      </Show>
+
+      <div class="setting-item">
+        <label class="setting-label">
+          <input
+            type="checkbox"
+            checked={policy().exclude_ambiguous}
+            onChange={(e) => updatePolicy("exclude_ambiguous", e.currentTarget.checked)}
+          />
+          <span>Exclude Ambiguous Characters</span>
+        </label>
+        <p class="setting-description">Leave out characters such as 0, O, l and 1</p>
+      </div>
+
+      <div class="setting-item">
+        <label class="setting-label">
+          <input
+            type="checkbox"
+            checked={policy().no_repeats}
+            onChange={(e) => updatePolicy("no_repeats", e.currentTarget.checked)}
+          />
+          <span>No Repeated Characters</span>
+        </label>
+        <p class="setting-description">Use every character at most once</p>
+      </div>
+
+      <div class="setting-item">
+        <div class="setting-actions">
+          <button class="secondary-button" onClick={handlePreview}>
+            Preview
+          </button>
+          <input
+            type="text"
+            value={name()}
+            onInput={(e) => setName(e.currentTarget.value)}
+            placeholder="Policy name"
+            class="setting-input"
+          />
+          <button class="primary-button" onClick={handleSave} disabled={!name().trim()}>
+            Save Policy
+          </button>
+        </div>
+        <Show when={preview()}>
+          <pre class="recovery-code">{preview()}</pre>
+        </Show>
+      </div>
+
+      <h3>Saved Policies</h3>
+      <div class="categories-list">
+        <For each={policies()} fallback={<p class="setting-description">No saved policies</p>}>
+          {(saved) => (
+            <div class="category-item">
+              <div class="category-info">
+                <span class="category-name">{saved.name}</span>
+              </div>
+              <div class="category-actions">
+                <button
+                  class="icon-button"
+                  onClick={() => setPolicy({ ...saved.policy })}
+                  title="Load into editor"
+                >
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
+                    <path d="M20.71,7.04C21.1,6.65 21.1,6 20.71,5.63L18.37,3.29C18,2.9 17.35,2.9 16.96,3.29L15.12,5.12L18.87,8.87M3,17.25V21H6.75L17.81,9.93L14.06,6.18L3,17.25Z" />
+                  </svg>
+                </button>
+                <button
+                  class="icon-button danger"
+                  onClick={() => handleDelete(saved.id)}
+                  title="Delete policy"
+                >
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
+                    <path d="M19,4H15.5L14.5,3H9.5L8.5,4H5V6H19M6,19A2,2 0 0,0 8,21H16A2,2 0 0,0 18,19V7H6V19Z" />
+                  </svg>
+                </button>
+              </div>
+            </div>
+          )}
+        </For>
+      </div>
+    </div>
+  );
+};
+
+export default GeneratorSettings;
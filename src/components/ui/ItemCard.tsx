@@ -1,7 +1,7 @@
 import { Show } from "solid-js";
 import type { PasswordEntry } from "../../types";
 import { selectEntry, selectedEntry, toggleFavorite } from "../../store/passwords";
-import { getCategoryByName } from "../../store/categories";
+import { getCategoryById } from "../../store/categories";
 
 interface ItemCardProps {
   entry: PasswordEntry;
@@ -60,8 +60,8 @@ const ItemCard = (props: ItemCardProps) => {
   };
 
   const getCategoryInfo = () => {
-    if (!entry.category) return null;
-    return getCategoryByName(entry.category);
+    if (!entry.category_id) return null;
+    return getCategoryById(entry.category_id);
   };
 
   const categoryInfo = () => getCategoryInfo();
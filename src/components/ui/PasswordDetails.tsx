@@ -1,6 +1,26 @@
-import { createSignal, Show } from "solid-js";
-import { deleteEntry, selectedEntry, selectEntry } from "../../store/passwords";
-import type { PasswordEntry } from "../../types";
+import { createSignal, createEffect, onCleanup, For, Show } from "solid-js";
+import { deleteEntry, restoreEntryVersion, selectedEntry, selectEntry } from "../../store/passwords";
+import { PasswordVaultAPI } from "../../services/api";
+import type { Attachment, CustomField, EntryHistory, ItemData, PasswordEntry, TotpCode } from "../../types";
+
+// Labels of each item type's fields, in display order
+const ITEM_FIELD_LABELS: Record<string, [string, string][]> = {
+  card: [["cardholder_name", "Cardholder Name"], ["number", "Card Number"], ["expiry", "Expiry"], ["security_code", "Security Code"]],
+  identity: [["full_name", "Full Name"], ["email", "Email"], ["phone", "Phone"], ["address", "Address"], ["birth_date", "Birth Date"]],
+  wifi: [["ssid", "Network Name"], ["security", "Security"]],
+  license: [["license_key", "License Key"], ["licensed_to", "Licensed To"], ["version", "Version"], ["expires", "Expires"]],
+};
+
+// Masked until revealed
+const SECRET_ITEM_FIELDS = ["number", "security_code", "license_key"];
+
+const WIFI_SECURITY_LABELS: Record<string, string> = {
+  open: "None",
+  wep: "WEP",
+  wpa_personal: "WPA/WPA2 Personal",
+  wpa3_personal: "WPA3 Personal",
+  enterprise: "Enterprise",
+};
 
 interface PasswordDetailsProps {
   onEdit: (entry: PasswordEntry) => void;
@@ -10,8 +30,173 @@ const PasswordDetails = (props: PasswordDetailsProps) => {
   const [showPassword, setShowPassword] = createSignal(false);
   const [showDeleteConfirm, setShowDeleteConfirm] = createSignal(false);
 
+  const [totp, setTotp] = createSignal<TotpCode | null>(null);
+  const [totpError, setTotpError] = createSignal("");
+
+  const [history, setHistory] = createSignal<EntryHistory[] | null>(null);
+  const [historyError, setHistoryError] = createSignal("");
+  const [revealedHistoryId, setRevealedHistoryId] = createSignal<string | null>(null);
+  // Hidden custom fields are masked until revealed, by position in the list
+  const [revealedFields, setRevealedFields] = createSignal<number[]>([]);
+  const [revealedItemFields, setRevealedItemFields] = createSignal<string[]>([]);
+
+  const [attachments, setAttachments] = createSignal<Attachment[]>([]);
+  const [attachmentPath, setAttachmentPath] = createSignal("");
+  const [attachmentError, setAttachmentError] = createSignal("");
+  const [isAttaching, setIsAttaching] = createSignal(false);
+  // Export asks for a destination path, delete for confirmation, one attachment at a time
+  const [exportingId, setExportingId] = createSignal<string | null>(null);
+  const [exportPath, setExportPath] = createSignal("");
+  const [deletingAttachmentId, setDeletingAttachmentId] = createSignal<string | null>(null);
+
   const entry = selectedEntry;
 
+  // Codes are fetched on request and counted down locally; polling the
+  // backend would count as activity and keep auto-lock from firing
+  let totpTimer: ReturnType<typeof setInterval> | undefined;
+  const hideTotp = () => {
+    clearInterval(totpTimer);
+    setTotp(null);
+  };
+
+  const showTotp = async (id: string) => {
+    hideTotp();
+    setTotpError("");
+    try {
+      setTotp(await PasswordVaultAPI.getTotpCode(id));
+      totpTimer = setInterval(() => {
+        const current = totp();
+        if (!current || current.seconds_remaining <= 1) {
+          hideTotp();
+        } else {
+          setTotp({ ...current, seconds_remaining: current.seconds_remaining - 1 });
+        }
+      }, 1000);
+    } catch (err: any) {
+      setTotpError(err?.message ?? "Failed to generate code");
+    }
+  };
+
+  const showHistory = async (id: string) => {
+    setHistoryError("");
+    try {
+      setHistory(await PasswordVaultAPI.getEntryHistory(id));
+    } catch (err: any) {
+      setHistoryError(err?.message ?? "Failed to load history");
+    }
+  };
+
+  const loadAttachments = async (entryId: string) => {
+    try {
+      setAttachments(await PasswordVaultAPI.getAttachments(entryId));
+    } catch (err: any) {
+      setAttachmentError(err?.message ?? "Failed to load attachments");
+    }
+  };
+
+  const handleAddAttachment = async (entryId: string) => {
+    const path = attachmentPath().trim();
+    if (!path) return;
+    setAttachmentError("");
+    setIsAttaching(true);
+    try {
+      const attachment = await PasswordVaultAPI.addAttachment(entryId, path);
+      setAttachments(prev => [...prev, attachment]);
+      setAttachmentPath("");
+    } catch (err: any) {
+      setAttachmentError(err?.message ?? "Failed to attach file");
+    } finally {
+      setIsAttaching(false);
+    }
+  };
+
+  const handleExportAttachment = async (id: string) => {
+    const path = exportPath().trim();
+    if (!path) return;
+    setAttachmentError("");
+    try {
+      await PasswordVaultAPI.exportAttachment(id, path);
+      setExportingId(null);
+      setExportPath("");
+    } catch (err: any) {
+      setAttachmentError(err?.message ?? "Failed to export attachment");
+    }
+  };
+
+  const handleDeleteAttachment = async (id: string) => {
+    setAttachmentError("");
+    try {
+      await PasswordVaultAPI.deleteAttachment(id);
+      setAttachments(prev => prev.filter(attachment => attachment.id !== id));
+    } catch (err: any) {
+      setAttachmentError(err?.message ?? "Failed to delete attachment");
+    } finally {
+      setDeletingAttachmentId(null);
+    }
+  };
+
+  const formatSize = (bytes: number) => {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  };
+
+  const handleRestore = async (id: string, historyId: string) => {
+    if (await restoreEntryVersion(id, historyId)) {
+      await showHistory(id);
+    }
+  };
+
+  createEffect(() => {
+    const current = entry();
+    setAttachments([]);
+    setAttachmentPath("");
+    setAttachmentError("");
+    setExportingId(null);
+    setDeletingAttachmentId(null);
+    if (current) loadAttachments(current.id);
+    hideTotp();
+    setTotpError("");
+    setHistory(null);
+    setHistoryError("");
+    setRevealedHistoryId(null);
+    setRevealedFields([]);
+    setRevealedItemFields([]);
+  });
+
+  // Set fields of the entry's item data as key, label and value
+  const itemFields = (data: ItemData) => {
+    const values = data as unknown as Record<string, string | undefined>;
+    return (ITEM_FIELD_LABELS[data.type] ?? [])
+      .filter(([key]) => values[key])
+      .map(([key, label]) => ({ key, label, value: values[key]! }));
+  };
+
+  const formatItemValue = (key: string, value: string) => {
+    if (key === "security") return WIFI_SECURITY_LABELS[value] ?? value;
+    if ((key === "birth_date" || key === "expires") && value) return new Date(`${value}T00:00:00`).toLocaleDateString();
+    return value;
+  };
+
+  // Cards show their last four digits while masked
+  const maskItemValue = (key: string, value: string) =>
+    key === "number" ? `•••• ${value.replace(/[\s-]/g, "").slice(-4)}` : "••••••••";
+
+  const toggleRevealedItemField = (key: string) => {
+    setRevealedItemFields(prev => prev.includes(key) ? prev.filter(k => k !== key) : [...prev, key]);
+  };
+
+  const toggleRevealedField = (index: number) => {
+    setRevealedFields(prev => prev.includes(index) ? prev.filter(i => i !== index) : [...prev, index]);
+  };
+
+  const formatCustomValue = (field: CustomField) => {
+    if (field.field_type === "boolean") return field.value === "true" ? "Yes" : "No";
+    if (field.field_type === "date" && field.value) return new Date(`${field.value}T00:00:00`).toLocaleDateString();
+    return field.value || "Not set";
+  };
+  onCleanup(hideTotp);
+
   const copyToClipboard = async (text: string, type: string) => {
     try {
       await navigator.clipboard.writeText(text);
@@ -71,41 +256,129 @@ const PasswordDetails = (props: PasswordDetailsProps) => {
           </div>
 
           <div class="details-content">
-            <div class="detail-field">
-              <label>Username/Email</label>
-              <div class="field-value">
-                <span>{currentEntry().username || "Not set"}</span>
-                {currentEntry().username && (
+            <Show when={currentEntry().item_data}>
+              {(data) => (
+                <For each={itemFields(data())}>
+                  {(field) => (
+                    <div class="detail-field">
+                      <label>{field.label}</label>
+                      <div class="field-value">
+                        <Show when={SECRET_ITEM_FIELDS.includes(field.key)} fallback={
+                          <span>{formatItemValue(field.key, field.value)}</span>
+                        }>
+                          <span class="password-field">
+                            {revealedItemFields().includes(field.key) ? field.value : maskItemValue(field.key, field.value)}
+                          </span>
+                          <button class="toggle-button" onClick={() => toggleRevealedItemField(field.key)}>
+                            {revealedItemFields().includes(field.key) ? "Hide" : "Show"}
+                          </button>
+                        </Show>
+                        <button class="copy-button" onClick={() => copyToClipboard(field.value, field.label)}>
+                          Copy
+                        </button>
+                      </div>
+                    </div>
+                  )}
+                </For>
+              )}
+            </Show>
+
+            <Show when={currentEntry().item_type === "login"}>
+              <div class="detail-field">
+                <label>Username/Email</label>
+                <div class="field-value">
+                  <span>{currentEntry().username || "Not set"}</span>
+                  {currentEntry().username && (
+                    <button 
+                      class="copy-button"
+                      onClick={() => copyToClipboard(currentEntry().username, "Username")}
+                    >
+                      Copy
+                    </button>
+                  )}
+                </div>
+              </div>
+            </Show>
+
+            <Show when={currentEntry().item_type === "login" || (currentEntry().item_type === "wifi" && currentEntry().password)}>
+              <div class="detail-field">
+                <label>{currentEntry().item_type === "wifi" ? "Network Password" : "Password"}</label>
+                <div class="field-value">
+                  <span class="password-field">
+                    {showPassword() ? currentEntry().password : "••••••••"}
+                  </span>
+                  <button 
+                    class="toggle-button"
+                    onClick={() => setShowPassword(!showPassword())}
+                  >
+                    {showPassword() ? "Hide" : "Show"}
+                  </button>
                   <button 
                     class="copy-button"
-                    onClick={() => copyToClipboard(currentEntry().username, "Username")}
+                    onClick={() => copyToClipboard(currentEntry().password, "Password")}
                   >
                     Copy
                   </button>
-                )}
+                </div>
               </div>
-            </div>
+            </Show>
 
-            <div class="detail-field">
-              <label>Password</label>
-              <div class="field-value">
-                <span class="password-field">
-                  {showPassword() ? currentEntry().password : "••••••••"}
-                </span>
-                <button 
-                  class="toggle-button"
-                  onClick={() => setShowPassword(!showPassword())}
-                >
-                  {showPassword() ? "Hide" : "Show"}
-                </button>
-                <button 
-                  class="copy-button"
-                  onClick={() => copyToClipboard(currentEntry().password, "Password")}
-                >
-                  Copy
-                </button>
+            <Show when={currentEntry().totp}>
+              <div class="detail-field">
+                <label>Two-Factor Code</label>
+                <div class="field-value">
+                  <Show
+                    when={totp()}
+                    fallback={
+                      <button class="toggle-button" onClick={() => showTotp(currentEntry().id)}>
+                        Show code
+                      </button>
+                    }
+                  >
+                    {(code) => (
+                      <>
+                        <span class="password-field totp-code">{code().code}</span>
+                        <span class="totp-remaining">{code().seconds_remaining}s</span>
+                        <button
+                          class="copy-button"
+                          onClick={() => copyToClipboard(code().code, "Code")}
+                        >
+                          Copy
+                        </button>
+                      </>
+                    )}
+                  </Show>
+                </div>
+                <Show when={totpError()}>
+                  <div class="error-message">{totpError()}</div>
+                </Show>
               </div>
-            </div>
+            </Show>
+
+            <For each={currentEntry().custom_fields}>
+              {(field, index) => (
+                <div class="detail-field">
+                  <label>{field.name}</label>
+                  <div class="field-value">
+                    <Show when={field.field_type === "hidden"} fallback={
+                      <span>{formatCustomValue(field)}</span>
+                    }>
+                      <span class="password-field">
+                        {revealedFields().includes(index()) ? field.value : "••••••••"}
+                      </span>
+                      <button class="toggle-button" onClick={() => toggleRevealedField(index())}>
+                        {revealedFields().includes(index()) ? "Hide" : "Show"}
+                      </button>
+                    </Show>
+                    <Show when={field.field_type !== "boolean" && field.value}>
+                      <button class="copy-button" onClick={() => copyToClipboard(field.value, field.name)}>
+                        Copy
+                      </button>
+                    </Show>
+                  </div>
+                </div>
+              )}
+            </For>
 
             {currentEntry().url && (
               <div class="detail-field">
@@ -146,6 +419,124 @@ const PasswordDetails = (props: PasswordDetailsProps) => {
               </div>
             )}
 
+            <div class="detail-field">
+              <label>Attachments</label>
+              <Show when={attachments().length > 0}>
+                <ul class="attachment-list">
+                  <For each={attachments()}>
+                    {(attachment) => (
+                      <li class="attachment-item">
+                        <span class="attachment-name">{attachment.name}</span>
+                        <span class="attachment-size">{formatSize(attachment.size)}</span>
+                        <Show
+                          when={exportingId() === attachment.id}
+                          fallback={
+                            <button class="copy-button" onClick={() => { setExportingId(attachment.id); setExportPath(""); }}>
+                              Export
+                            </button>
+                          }
+                        >
+                          <input
+                            type="text"
+                            value={exportPath()}
+                            onInput={(e) => setExportPath(e.currentTarget.value)}
+                            placeholder={`Save to, e.g. /home/me/${attachment.name}`}
+                          />
+                          <button class="copy-button" onClick={() => handleExportAttachment(attachment.id)}>
+                            Save
+                          </button>
+                          <button class="toggle-button" onClick={() => setExportingId(null)}>
+                            Cancel
+                          </button>
+                        </Show>
+                        <Show
+                          when={deletingAttachmentId() === attachment.id}
+                          fallback={
+                            <button class="delete-button" onClick={() => setDeletingAttachmentId(attachment.id)}>
+                              Delete
+                            </button>
+                          }
+                        >
+                          <span>Delete permanently?</span>
+                          <button class="delete-confirm-button" onClick={() => handleDeleteAttachment(attachment.id)}>
+                            Delete
+                          </button>
+                          <button class="toggle-button" onClick={() => setDeletingAttachmentId(null)}>
+                            Cancel
+                          </button>
+                        </Show>
+                      </li>
+                    )}
+                  </For>
+                </ul>
+              </Show>
+              <div class="field-value">
+                <input
+                  type="text"
+                  value={attachmentPath()}
+                  onInput={(e) => setAttachmentPath(e.currentTarget.value)}
+                  placeholder="Path to a file, up to 25 MB"
+                />
+                <button
+                  class="edit-button"
+                  onClick={() => handleAddAttachment(currentEntry().id)}
+                  disabled={isAttaching() || !attachmentPath().trim()}
+                >
+                  {isAttaching() ? "Attaching..." : "Attach"}
+                </button>
+              </div>
+              <Show when={attachmentError()}>
+                <div class="error-message">{attachmentError()}</div>
+              </Show>
+            </div>
+
+            <div class="detail-field">
+              <label>Password History</label>
+              <Show
+                when={history()}
+                fallback={
+                  <div class="field-value">
+                    <button class="toggle-button" onClick={() => showHistory(currentEntry().id)}>
+                      Show history
+                    </button>
+                  </div>
+                }
+              >
+                {(items) => (
+                  <Show when={items().length > 0} fallback={<p class="history-empty">No previous passwords</p>}>
+                    <ul class="history-list">
+                      <For each={items()}>
+                        {(item) => (
+                          <li class="history-item">
+                            <span class="history-date">Replaced {formatDate(item.created_at)}</span>
+                            <span>{item.username || "No username"}</span>
+                            <span class="password-field">
+                              {revealedHistoryId() === item.id ? item.password : "••••••••"}
+                            </span>
+                            <button
+                              class="toggle-button"
+                              onClick={() => setRevealedHistoryId(revealedHistoryId() === item.id ? null : item.id)}
+                            >
+                              {revealedHistoryId() === item.id ? "Hide" : "Show"}
+                            </button>
+                            <button class="copy-button" onClick={() => copyToClipboard(item.password, "Password")}>
+                              Copy
+                            </button>
+                            <button class="edit-button" onClick={() => handleRestore(currentEntry().id, item.id)}>
+                              Restore
+                            </button>
+                          </li>
+                        )}
+                      </For>
+                    </ul>
+                  </Show>
+                )}
+              </Show>
+              <Show when={historyError()}>
+                <div class="error-message">{historyError()}</div>
+              </Show>
+            </div>
+
             <div class="detail-field">
               <label>Created</label>
               <div class="field-value">
@@ -166,7 +557,7 @@ const PasswordDetails = (props: PasswordDetailsProps) => {
             <div class="modal-overlay">
               <div class="modal">
                 <h3>Delete Entry</h3>
-                <p>Are you sure you want to delete "{currentEntry().title}"? This action cannot be undone.</p>
+                <p>Move "{currentEntry().title}" to the trash? You can restore it, with its attachments, until the trash is emptied.</p>
                 <div class="modal-actions">
                   <button 
                     class="cancel-button"
@@ -1,13 +1,99 @@
-import { createSignal, createEffect, For } from "solid-js";
-import { createEntry, updateEntry, generatePassword, isLoading, error, clearError } from "../../store/passwords";
+import { createSignal, createEffect, onMount, For, Show } from "solid-js";
+import { createEntry, updateEntry, isLoading, error, clearError } from "../../store/passwords";
+import {
+  policies,
+  loadPolicies,
+  generateWithPolicy,
+  generatePassphrase,
+  defaultPolicy,
+  error as generatorError,
+} from "../../store/generator";
+import StrengthMeter, { WEAK_SCORE } from "./StrengthMeter";
+import { showToast } from "../StateFeedback/Toast";
 import { categories } from "../../store/categories";
-import type { PasswordEntry, CreatePasswordEntry, UpdatePasswordEntry } from "../../types";
+import type {
+  PasswordEntry,
+  CreatePasswordEntry,
+  UpdatePasswordEntry,
+  SavedEntry,
+  StrengthEstimate,
+  CustomField,
+  CustomFieldType,
+  ItemData,
+  ItemType,
+} from "../../types";
+
+const CUSTOM_FIELD_TYPES: { value: CustomFieldType; label: string }[] = [
+  { value: "text", label: "Text" },
+  { value: "hidden", label: "Hidden" },
+  { value: "boolean", label: "Yes/No" },
+  { value: "url", label: "URL" },
+  { value: "date", label: "Date" },
+];
+
+const ITEM_TYPES: { value: ItemType; label: string }[] = [
+  { value: "login", label: "Login" },
+  { value: "card", label: "Card" },
+  { value: "secure_note", label: "Secure Note" },
+  { value: "identity", label: "Identity" },
+  { value: "wifi", label: "Wi-Fi" },
+  { value: "license", label: "Software License" },
+];
+
+interface ItemField {
+  key: string;
+  label: string;
+  type?: string;
+  placeholder?: string;
+  required?: boolean;
+  options?: { value: string; label: string }[];
+}
+
+// Fields of each item type beyond the entry's own; logins and notes have none
+const ITEM_FIELDS: Partial<Record<ItemType, ItemField[]>> = {
+  card: [
+    { key: "cardholder_name", label: "Cardholder Name", required: true },
+    { key: "number", label: "Card Number", placeholder: "1234 5678 9012 3456", required: true },
+    { key: "expiry", label: "Expiry", placeholder: "MM/YY", required: true },
+    { key: "security_code", label: "Security Code", type: "password", placeholder: "CVC" },
+  ],
+  identity: [
+    { key: "full_name", label: "Full Name", required: true },
+    { key: "email", label: "Email", type: "email" },
+    { key: "phone", label: "Phone", type: "tel" },
+    { key: "address", label: "Address" },
+    { key: "birth_date", label: "Birth Date", type: "date" },
+  ],
+  wifi: [
+    { key: "ssid", label: "Network Name", required: true },
+    {
+      key: "security",
+      label: "Security",
+      options: [
+        { value: "wpa_personal", label: "WPA/WPA2 Personal" },
+        { value: "wpa3_personal", label: "WPA3 Personal" },
+        { value: "enterprise", label: "Enterprise" },
+        { value: "wep", label: "WEP" },
+        { value: "open", label: "None" },
+      ],
+    },
+  ],
+  license: [
+    { key: "license_key", label: "License Key", required: true },
+    { key: "licensed_to", label: "Licensed To" },
+    { key: "version", label: "Version" },
+    { key: "expires", label: "Expires", type: "date" },
+  ],
+};
+
+// Only these keep a secret in the entry's password
+const hasPassword = (type: ItemType) => type === "login" || type === "wifi";
 
 interface PasswordFormProps {
   entry?: PasswordEntry;
   onClose: () => void;
   onSave?: () => void;
-  defaultCategory?: string;
+  defaultCategory?: string; // category id
   defaultFavorite?: boolean;
 }
 
@@ -18,12 +104,22 @@ const PasswordForm = (props: PasswordFormProps) => {
     password: "",
     url: "",
     notes: "",
-    category: props.defaultCategory || "",
+    category_id: props.defaultCategory || "",
+    totp: "",
     is_favorite: props.defaultFavorite || false,
   });
 
+  const [customFields, setCustomFields] = createSignal<CustomField[]>([]);
+  const [itemType, setItemType] = createSignal<ItemType>("login");
+  const [itemFields, setItemFields] = createSignal<Record<string, string>>({});
   const [showPassword, setShowPassword] = createSignal(false);
   const [validationError, setValidationError] = createSignal("");
+  const [policyId, setPolicyId] = createSignal("");
+  const [strength, setStrength] = createSignal<StrengthEstimate | null>(null);
+  // A weak password is saved only after the user submits a second time
+  const [weakConfirmed, setWeakConfirmed] = createSignal(false);
+
+  onMount(loadPolicies);
 
   // Initialize form with existing entry data if editing
   createEffect(() => {
@@ -34,20 +130,91 @@ const PasswordForm = (props: PasswordFormProps) => {
         password: props.entry.password,
         url: props.entry.url || "",
         notes: props.entry.notes || "",
-        category: props.entry.category || "",
+        category_id: props.entry.category_id || "",
+        totp: props.entry.totp || "",
         is_favorite: props.entry.is_favorite || false,
       });
+      setCustomFields(props.entry.custom_fields.map(field => ({ ...field })));
+      setItemType(props.entry.item_type);
+      const { type: _, ...fields } = (props.entry.item_data ?? {}) as Record<string, string>;
+      setItemFields(fields);
     }
   });
 
+  const updateItemField = (key: string, value: string) => {
+    setItemFields(prev => ({ ...prev, [key]: value }));
+  };
+
+  const changeItemType = (type: ItemType) => {
+    setItemType(type);
+    // Selects start on their first option rather than empty
+    const defaults: Record<string, string> = {};
+    for (const field of ITEM_FIELDS[type] ?? []) {
+      if (field.options) defaults[field.key] = field.options[0].value;
+    }
+    setItemFields(defaults);
+  };
+
+  // The typed payload sent to the backend; blank optional fields are left out
+  const buildItemData = (): ItemData | undefined => {
+    const type = itemType();
+    if (type === "login") return undefined;
+    const data: Record<string, string> = { type };
+    for (const field of ITEM_FIELDS[type] ?? []) {
+      const value = (itemFields()[field.key] ?? "").trim();
+      if (value || field.required) data[field.key] = value;
+    }
+    return data as unknown as ItemData;
+  };
+
   const updateField = (field: string, value: string | boolean) => {
     setFormData(prev => ({ ...prev, [field]: value }));
   };
 
+  const updateCustomField = (index: number, changes: Partial<CustomField>) => {
+    setCustomFields(prev => prev.map((field, i) => {
+      if (i !== index) return field;
+      const updated = { ...field, ...changes };
+      // A boolean needs a value the backend accepts
+      if (changes.field_type === "boolean" && updated.value !== "true") updated.value = "false";
+      return updated;
+    }));
+  };
+
+  const addCustomField = () => {
+    setCustomFields(prev => [...prev, { name: "", value: "", field_type: "text" }]);
+  };
+
+  const removeCustomField = (index: number) => {
+    setCustomFields(prev => prev.filter((_, i) => i !== index));
+  };
+
+  const moveCustomField = (index: number, offset: number) => {
+    setCustomFields(prev => {
+      const target = index + offset;
+      if (target < 0 || target >= prev.length) return prev;
+      const fields = [...prev];
+      [fields[index], fields[target]] = [fields[target], fields[index]];
+      return fields;
+    });
+  };
+
   const handleGeneratePassword = async () => {
-    const newPassword = await generatePassword(16, true);
+    const saved = policies().find(p => p.id === policyId());
+    const newPassword = await generateWithPolicy(saved?.policy ?? defaultPolicy);
     if (newPassword) {
       updateField("password", newPassword);
+    } else {
+      setValidationError(generatorError() ?? "Failed to generate password");
+    }
+  };
+
+  const handleGeneratePassphrase = async () => {
+    const result = await generatePassphrase();
+    if (result) {
+      updateField("password", result.passphrase);
+    } else {
+      setValidationError(generatorError() ?? "Failed to generate passphrase");
     }
   };
 
@@ -64,12 +231,32 @@ const PasswordForm = (props: PasswordFormProps) => {
       return;
     }
 
-    if (!data.password.trim()) {
+    if (itemType() === "login" && !data.password.trim()) {
       setValidationError("Password is required");
       return;
     }
 
-    let success = false;
+    const missing = (ITEM_FIELDS[itemType()] ?? []).find(
+      field => field.required && !(itemFields()[field.key] ?? "").trim()
+    );
+    if (missing) {
+      setValidationError(`${missing.label} is required`);
+      return;
+    }
+
+    if (customFields().some(field => !field.name.trim())) {
+      setValidationError("Every custom field needs a name");
+      return;
+    }
+
+    const estimate = strength();
+    if (hasPassword(itemType()) && data.password && estimate && estimate.score <= WEAK_SCORE && !weakConfirmed()) {
+      setWeakConfirmed(true);
+      setValidationError("This password is easy to guess. Save again to keep it anyway.");
+      return;
+    }
+
+    let saved: SavedEntry | null;
 
     if (props.entry) {
       // Update existing entry
@@ -79,10 +266,18 @@ const PasswordForm = (props: PasswordFormProps) => {
       if (data.password !== props.entry.password) updateData.password = data.password;
       if (data.url !== (props.entry.url || "")) updateData.url = data.url || undefined;
       if (data.notes !== (props.entry.notes || "")) updateData.notes = data.notes || undefined;
-      if (data.category !== (props.entry.category || "")) updateData.category = data.category || undefined;
+      if (data.category_id !== (props.entry.category_id || "")) updateData.category_id = data.category_id;
+      if (data.totp !== (props.entry.totp || "")) updateData.totp = data.totp;
       if (data.is_favorite !== (props.entry.is_favorite || false)) updateData.is_favorite = data.is_favorite;
+      if (JSON.stringify(customFields()) !== JSON.stringify(props.entry.custom_fields)) {
+        updateData.custom_fields = customFields();
+      }
+      const itemData = buildItemData() ?? null;
+      if (JSON.stringify(itemData) !== JSON.stringify(props.entry.item_data ?? null)) {
+        updateData.item_data = itemData;
+      }
 
-      success = await updateEntry(props.entry.id, updateData);
+      saved = await updateEntry(props.entry.id, updateData);
     } else {
       // Create new entry
       const createData: CreatePasswordEntry = {
@@ -91,14 +286,21 @@ const PasswordForm = (props: PasswordFormProps) => {
         password: data.password,
         url: data.url || undefined,
         notes: data.notes || undefined,
-        category: data.category || undefined,
+        category_id: data.category_id || undefined,
+        totp: data.totp || undefined,
         is_favorite: data.is_favorite,
+        custom_fields: customFields(),
+        item_data: buildItemData(),
       };
 
-      success = await createEntry(createData);
+      saved = await createEntry(createData);
     }
 
-    if (success) {
+    if (saved) {
+      // The meter's estimate may not have arrived before saving
+      if (saved.warning === "weak_password" && !weakConfirmed()) {
+        showToast("Saved, but this password is easy to guess", "warning");
+      }
       props.onSave?.();
       props.onClose();
     }
@@ -119,6 +321,20 @@ const PasswordForm = (props: PasswordFormProps) => {
         </div>
 
         <form onSubmit={handleSubmit}>
+          <div class="form-group">
+            <label for="item-type">Type</label>
+            <select
+              id="item-type"
+              value={itemType()}
+              onChange={(e) => changeItemType(e.currentTarget.value as ItemType)}
+              disabled={isEditing()}
+            >
+              <For each={ITEM_TYPES}>
+                {(type) => <option value={type.value}>{type.label}</option>}
+              </For>
+            </select>
+          </div>
+
           <div class="form-row">
             <div class="form-group">
               <label for="title">Title *</label>
@@ -149,86 +365,229 @@ const PasswordForm = (props: PasswordFormProps) => {
             </div>
           </div>
 
-          <div class="form-group">
-            <label for="username">Username/Email</label>
-            <input
-              id="username"
-              type="text"
-              value={formData().username}
-              onInput={(e) => updateField("username", e.currentTarget.value)}
-              placeholder="Enter username or email"
-            />
-          </div>
+          <For each={ITEM_FIELDS[itemType()] ?? []}>
+            {(field) => (
+              <div class="form-group">
+                <label for={`item-${field.key}`}>{field.label}{field.required ? " *" : ""}</label>
+                <Show
+                  when={field.options}
+                  fallback={
+                    <input
+                      id={`item-${field.key}`}
+                      type={field.type ?? "text"}
+                      value={itemFields()[field.key] ?? ""}
+                      onInput={(e) => updateItemField(field.key, e.currentTarget.value)}
+                      placeholder={field.placeholder}
+                      autocomplete="off"
+                    />
+                  }
+                >
+                  {(options) => (
+                    <select
+                      id={`item-${field.key}`}
+                      value={itemFields()[field.key] ?? ""}
+                      onChange={(e) => updateItemField(field.key, e.currentTarget.value)}
+                    >
+                      <For each={options()}>
+                        {(option) => <option value={option.value}>{option.label}</option>}
+                      </For>
+                    </select>
+                  )}
+                </Show>
+              </div>
+            )}
+          </For>
 
-          <div class="form-group">
-            <label for="password">Password *</label>
-            <div class="password-input-group">
+          <Show when={itemType() === "login"}>
+            <div class="form-group">
+              <label for="username">Username/Email</label>
               <input
-                id="password"
-                type={showPassword() ? "text" : "password"}
-                value={formData().password}
-                onInput={(e) => updateField("password", e.currentTarget.value)}
-                placeholder="Enter password"
-                required
+                id="username"
+                type="text"
+                value={formData().username}
+                onInput={(e) => updateField("username", e.currentTarget.value)}
+                placeholder="Enter username or email"
               />
-              <button
-                type="button"
-                class="input-button toggle-password"
-                onClick={() => setShowPassword(!showPassword())}
-                title={showPassword() ? "Hide password" : "Show password"}
-              >
-                <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
-                  {showPassword() ? (
-                    <path d="M11.83,9L15,12.16C15,12.11 15,12.05 15,12A3,3 0 0,0 12,9C11.94,9 11.89,9 11.83,9M7.53,9.8L9.08,11.35C9.03,11.56 9,11.77 9,12A3,3 0 0,0 12,15C12.22,15 12.44,14.97 12.65,14.92L14.2,16.47C13.53,16.8 12.79,17 12,17A5,5 0 0,1 7,12C7,11.21 7.2,10.47 7.53,9.8M2,4.27L4.28,6.55L4.73,7C3.08,8.3 1.78,10 1,12C2.73,16.39 7,19.5 12,19.5C13.55,19.5 15.03,19.2 16.38,18.66L16.81,19.09L19.73,22L21,20.73L3.27,3M12,7A5,5 0 0,1 17,12C17,12.64 16.87,13.26 16.64,13.82L19.57,16.75C21.07,15.5 22.27,13.86 23,12C21.27,7.61 17,4.5 12,4.5C10.6,4.5 9.26,4.75 8,5.2L10.17,7.35C10.76,7.13 11.37,7 12,7Z" />
-                  ) : (
-                    <path d="M12,9A3,3 0 0,0 9,12A3,3 0 0,0 12,15A3,3 0 0,0 15,12A3,3 0 0,0 12,9M12,17A5,5 0 0,1 7,12A5,5 0 0,1 12,7A5,5 0 0,1 17,12A5,5 0 0,1 12,17M12,4.5C7,4.5 2.73,7.61 1,12C2.73,16.39 7,19.5 12,19.5C17,19.5 21.27,16.39 23,12C21.27,7.61 17,4.5 12,4.5Z" />
-                  )}
-                </svg>
-              </button>
-              <button
-                type="button"
-                class="input-button generate-password"
-                onClick={handleGeneratePassword}
-                title="Generate secure password"
-              >
-                <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
-                  <path d="M12,6V9L16,5L12,1V4A8,8 0 0,0 4,12C4,13.57 4.46,15.03 5.24,16.26L6.7,14.8C6.25,13.97 6,13 6,12A6,6 0 0,1 12,6M18.76,7.74L17.3,9.2C17.74,10.04 18,11 18,12A6,6 0 0,1 12,18V15L8,19L12,23V20A8,8 0 0,0 20,12C20,10.43 19.54,8.97 18.76,7.74Z" />
-                </svg>
-              </button>
             </div>
-          </div>
+          </Show>
 
-          <div class="form-row">
+          <Show when={hasPassword(itemType())}>
             <div class="form-group">
-              <label for="url">Website URL</label>
-              <input
-                id="url"
-                type="url"
-                value={formData().url}
-                onInput={(e) => updateField("url", e.currentTarget.value)}
-                placeholder="https://example.com"
+              <label for="password">{itemType() === "wifi" ? "Network Password" : "Password *"}</label>
+              <div class="password-input-group">
+                <input
+                  id="password"
+                  type={showPassword() ? "text" : "password"}
+                  value={formData().password}
+                  onInput={(e) => {
+                    updateField("password", e.currentTarget.value);
+                    setWeakConfirmed(false);
+                  }}
+                  placeholder="Enter password"
+                  required={itemType() === "login"}
+                />
+                <button
+                  type="button"
+                  class="input-button toggle-password"
+                  onClick={() => setShowPassword(!showPassword())}
+                  title={showPassword() ? "Hide password" : "Show password"}
+                >
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
+                    {showPassword() ? (
+                      <path d="M11.83,9L15,12.16C15,12.11 15,12.05 15,12A3,3 0 0,0 12,9C11.94,9 11.89,9 11.83,9M7.53,9.8L9.08,11.35C9.03,11.56 9,11.77 9,12A3,3 0 0,0 12,15C12.22,15 12.44,14.97 12.65,14.92L14.2,16.47C13.53,16.8 12.79,17 12,17A5,5 0 0,1 7,12C7,11.21 7.2,10.47 7.53,9.8M2,4.27L4.28,6.55L4.73,7C3.08,8.3 1.78,10 1,12C2.73,16.39 7,19.5 12,19.5C13.55,19.5 15.03,19.2 16.38,18.66L16.81,19.09L19.73,22L21,20.73L3.27,3M12,7A5,5 0 0,1 17,12C17,12.64 16.87,13.26 16.64,13.82L19.57,16.75C21.07,15.5 22.27,13.86 23,12C21.27,7.61 17,4.5 12,4.5C10.6,4.5 9.26,4.75 8,5.2L10.17,7.35C10.76,7.13 11.37,7 12,7Z" />
+                    ) : (
+                      <path d="M12,9A3,3 0 0,0 9,12A3,3 0 0,0 12,15A3,3 0 0,0 15,12A3,3 0 0,0 12,9M12,17A5,5 0 0,1 7,12A5,5 0 0,1 12,7A5,5 0 0,1 17,12A5,5 0 0,1 12,17M12,4.5C7,4.5 2.73,7.61 1,12C2.73,16.39 7,19.5 12,19.5C17,19.5 21.27,16.39 23,12C21.27,7.61 17,4.5 12,4.5Z" />
+                    )}
+                  </svg>
+                </button>
+                <button
+                  type="button"
+                  class="input-button generate-password"
+                  onClick={handleGeneratePassword}
+                  title="Generate secure password"
+                >
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
+                    <path d="M12,6V9L16,5L12,1V4A8,8 0 0,0 4,12C4,13.57 4.46,15.03 5.24,16.26L6.7,14.8C6.25,13.97 6,13 6,12A6,6 0 0,1 12,6M18.76,7.74L17.3,9.2C17.74,10.04 18,11 18,12A6,6 0 0,1 12,18V15L8,19L12,23V20A8,8 0 0,0 20,12C20,10.43 19.54,8.97 18.76,7.74Z" />
+                  </svg>
+                </button>
+                <button
+                  type="button"
+                  class="input-button generate-password"
+                  onClick={handleGeneratePassphrase}
+                  title="Generate passphrase"
+                >
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="currentColor">
+                    <path d="M3,5H21V7H3V5M3,11H21V13H3V11M3,17H15V19H3V17Z" />
+                  </svg>
+                </button>
+              </div>
+              <Show when={policies().length > 0}>
+                <select
+                  class="generator-policy"
+                  value={policyId()}
+                  onChange={(e) => setPolicyId(e.currentTarget.value)}
+                  title="Policy used by the generate button"
+                >
+                  <option value="">Default policy</option>
+                  <For each={policies()}>
+                    {(saved) => <option value={saved.id}>{saved.name}</option>}
+                  </For>
+                </select>
+              </Show>
+              <StrengthMeter
+                password={formData().password}
+                userInputs={[formData().title, formData().username, formData().url]}
+                onEstimate={setStrength}
               />
             </div>
+          </Show>
+
+          <div class="form-row">
+            <Show when={itemType() === "login"}>
+              <div class="form-group">
+                <label for="url">Website URL</label>
+                <input
+                  id="url"
+                  type="url"
+                  value={formData().url}
+                  onInput={(e) => updateField("url", e.currentTarget.value)}
+                  placeholder="https://example.com"
+                />
+              </div>
+            </Show>
 
             <div class="form-group">
               <label for="category">Category</label>
               <select
                 id="category"
-                value={formData().category}
-                onChange={(e) => updateField("category", e.currentTarget.value)}
+                value={formData().category_id}
+                onChange={(e) => updateField("category_id", e.currentTarget.value)}
               >
                 <option value="">Select category</option>
                 <For each={categories()}>
                   {(category) => (
-                    <option value={category.name}>{category.icon} {category.name}</option>
+                    <option value={category.id}>{category.icon} {category.name}</option>
                   )}
                 </For>
               </select>
             </div>
           </div>
 
+          <Show when={itemType() === "login"}>
+            <div class="form-group">
+              <label for="totp">Two-Factor Secret</label>
+              <input
+                id="totp"
+                type="password"
+                value={formData().totp}
+                onInput={(e) => updateField("totp", e.currentTarget.value)}
+                placeholder="otpauth:// URI or base32 secret (optional)"
+                autocomplete="off"
+              />
+            </div>
+          </Show>
+
+          <div class="form-group">
+            <label>Custom Fields</label>
+            <For each={customFields()}>
+              {(field, index) => (
+                <div class="custom-field-row">
+                  <input
+                    type="text"
+                    value={field.name}
+                    onInput={(e) => updateCustomField(index(), { name: e.currentTarget.value })}
+                    placeholder="Name, e.g. PIN"
+                  />
+                  <select
+                    value={field.field_type}
+                    onChange={(e) => updateCustomField(index(), { field_type: e.currentTarget.value as CustomFieldType })}
+                  >
+                    <For each={CUSTOM_FIELD_TYPES}>
+                      {(type) => <option value={type.value}>{type.label}</option>}
+                    </For>
+                  </select>
+                  <Show
+                    when={field.field_type === "boolean"}
+                    fallback={
+                      <input
+                        type={
+                          field.field_type === "hidden" ? "password"
+                          : field.field_type === "date" ? "date"
+                          : field.field_type === "url" ? "url"
+                          : "text"
+                        }
+                        value={field.value}
+                        onInput={(e) => updateCustomField(index(), { value: e.currentTarget.value })}
+                        placeholder="Value"
+                        autocomplete="off"
+                      />
+                    }
+                  >
+                    <input
+                      type="checkbox"
+                      checked={field.value === "true"}
+                      onChange={(e) => updateCustomField(index(), { value: e.currentTarget.checked ? "true" : "false" })}
+                    />
+                  </Show>
+                  <button type="button" class="input-button" onClick={() => moveCustomField(index(), -1)} title="Move up">
+                    ↑
+                  </button>
+                  <button type="button" class="input-button" onClick={() => moveCustomField(index(), 1)} title="Move down">
+                    ↓
+                  </button>
+                  <button type="button" class="input-button" onClick={() => removeCustomField(index())} title="Remove field">
+                    ×
+                  </button>
+                </div>
+              )}
+            </For>
+            <button type="button" class="secondary-button" onClick={addCustomField}>
+              Add Field
+            </button>
+          </div>
+
           <div class="form-group">
-            <label for="notes">Notes</label>
+            <label for="notes">{itemType() === "secure_note" ? "Note" : "Notes"}</label>
             <textarea
               id="notes"
               value={formData().notes}
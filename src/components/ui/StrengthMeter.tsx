@@ -0,0 +1,71 @@
+import { createEffect, createSignal, onCleanup, For, Show } from "solid-js";
+import { PasswordVaultAPI } from "../../services/api";
+import type { StrengthEstimate } from "../../types";
+
+const LABELS = ["Very weak", "Weak", "Fair", "Strong", "Very strong"];
+
+// Scores at or below this count as weak; the same threshold as the backend's
+export const WEAK_SCORE = 2;
+
+// Wait for typing to pause before asking the backend
+const ESTIMATE_DELAY_MS = 250;
+
+interface StrengthMeterProps {
+  password: string;
+  userInputs?: string[];
+  onEstimate?: (estimate: StrengthEstimate | null) => void;
+}
+
+// Strength bar with crack time and feedback for the password being typed
+const StrengthMeter = (props: StrengthMeterProps) => {
+  const [estimate, setEstimate] = createSignal<StrengthEstimate | null>(null);
+
+  createEffect(() => {
+    const password = props.password;
+    const userInputs = (props.userInputs ?? []).filter(Boolean);
+
+    if (!password) {
+      setEstimate(null);
+      props.onEstimate?.(null);
+      return;
+    }
+
+    const timer = setTimeout(async () => {
+      try {
+        const result = await PasswordVaultAPI.estimateStrength(password, userInputs);
+        setEstimate(result);
+        props.onEstimate?.(result);
+      } catch (err) {
+        console.error("Strength estimate error:", err);
+      }
+    }, ESTIMATE_DELAY_MS);
+    onCleanup(() => clearTimeout(timer));
+  });
+
+  return (
+    <Show when={estimate()}>
+      {(current) => (
+        <div class={`strength-meter score-${current().score}`}>
+          <div class="strength-bar">
+            <For each={[0, 1, 2, 3]}>
+              {(i) => <span classList={{ filled: current().score > i }} />}
+            </For>
+          </div>
+          <div class="strength-label">
+            {LABELS[current().score]} · cracked in {current().crack_time_display}
+          </div>
+          <Show when={current().warning}>
+            <div class="strength-warning">{current().warning}</div>
+          </Show>
+          <Show when={current().suggestions.length > 0}>
+            <ul class="strength-suggestions">
+              <For each={current().suggestions}>{(suggestion) => <li>{suggestion}</li>}</For>
+            </ul>
+          </Show>
+        </div>
+      )}
+    </Show>
+  );
+};
+
+export default StrengthMeter;
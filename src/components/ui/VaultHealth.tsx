@@ -0,0 +1,179 @@
+import { createSignal, onMount, For, Show } from "solid-js";
+import { PasswordVaultAPI } from "../../services/api";
+import { entries } from "../../store/passwords";
+import { showToast } from "../StateFeedback/Toast";
+import type { AppError, BreachedPassword, HealthReport } from "../../types";
+
+// Settings tab listing reused, weak and old passwords and entries without a URL
+const VaultHealth = () => {
+  const [report, setReport] = createSignal<HealthReport | null>(null);
+  const [maxAgeDays, setMaxAgeDays] = createSignal(365);
+  const [isChecking, setIsChecking] = createSignal(false);
+  const [datasetPath, setDatasetPath] = createSignal("");
+  const [breached, setBreached] = createSignal<BreachedPassword[] | null>(null);
+
+  const titleOf = (id: string) => entries().find(e => e.id === id)?.title ?? "Unknown entry";
+
+  const runReport = async () => {
+    setIsChecking(true);
+    try {
+      setReport(await PasswordVaultAPI.vaultHealthReport());
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to check vault health", "error");
+    } finally {
+      setIsChecking(false);
+    }
+  };
+
+  onMount(async () => {
+    try {
+      const settings = await PasswordVaultAPI.getHealthSettings();
+      setMaxAgeDays(settings.max_password_age_days);
+    } catch (err) {
+      console.error("Load health settings error:", err);
+    }
+    await runReport();
+  });
+
+  const handleMaxAgeChange = async (days: number) => {
+    try {
+      await PasswordVaultAPI.setHealthSettings({ max_password_age_days: days });
+      setMaxAgeDays(days);
+      await runReport();
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to save setting", "error");
+    }
+  };
+
+  const handleBreachCheck = async () => {
+    setIsChecking(true);
+    try {
+      setBreached(await PasswordVaultAPI.checkBreachedPasswords(datasetPath().trim()));
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to check breaches", "error");
+    } finally {
+      setIsChecking(false);
+    }
+  };
+
+  return (
+    <div class="settings-section">
+      <h3>Vault Health</h3>
+
+      <div class="setting-item">
+        <label class="setting-label">Old Password Age</label>
+        <select
+          value={maxAgeDays()}
+          onChange={(e) => handleMaxAgeChange(parseInt(e.currentTarget.value))}
+          class="setting-select"
+        >
+          <option value={90}>3 months</option>
+          <option value={180}>6 months</option>
+          <option value={365}>1 year</option>
+          <option value={730}>2 years</option>
+        </select>
+        <p class="setting-description">Passwords unchanged for longer are reported as old</p>
+      </div>
+
+      <div class="setting-item">
+        <button class="secondary-button" onClick={runReport} disabled={isChecking()}>
+          {isChecking() ? "Checking..." : "Check Again"}
+        </button>
+      </div>
+
+      <Show when={report()}>
+        {(current) => (
+          <div class="health-report">
+            <div class="health-score">
+              <span class="health-score-value">{current().score}</span>
+              <span class="setting-description">
+                out of 100 across {current().total_entries} entries
+              </span>
+            </div>
+
+            <h4>Reused Passwords ({current().reused.length})</h4>
+            <ul class="health-findings">
+              <For each={current().reused} fallback={<li class="setting-description">None</li>}>
+                {(group) => <li>{group.entry_ids.map(titleOf).join(", ")}</li>}
+              </For>
+            </ul>
+
+            <h4>Weak Passwords ({current().weak.length})</h4>
+            <ul class="health-findings">
+              <For each={current().weak} fallback={<li class="setting-description">None</li>}>
+                {(finding) => (
+                  <li>
+                    {titleOf(finding.entry_id)}
+                    <Show when={finding.warning}>
+                      <span class="setting-description"> · {finding.warning}</span>
+                    </Show>
+                  </li>
+                )}
+              </For>
+            </ul>
+
+            <h4>Old Passwords ({current().old.length})</h4>
+            <ul class="health-findings">
+              <For each={current().old} fallback={<li class="setting-description">None</li>}>
+                {(finding) => (
+                  <li>
+                    {titleOf(finding.entry_id)}
+                    <span class="setting-description"> · {finding.age_days} days old</span>
+                  </li>
+                )}
+              </For>
+            </ul>
+
+            <h4>Missing URL ({current().missing_url.length})</h4>
+            <ul class="health-findings">
+              <For each={current().missing_url} fallback={<li class="setting-description">None</li>}>
+                {(id) => <li>{titleOf(id)}</li>}
+              </For>
+            </ul>
+          </div>
+        )}
+      </Show>
+
+      <h3>Breached Passwords</h3>
+      <div class="setting-item">
+        <label class="setting-label">Have I Been Pwned Dataset</label>
+        <div class="setting-actions">
+          <input
+            type="text"
+            value={datasetPath()}
+            onInput={(e) => setDatasetPath(e.currentTarget.value)}
+            placeholder="Sorted SHA-1 hash file or range directory"
+            class="setting-input"
+          />
+          <button
+            class="secondary-button"
+            onClick={handleBreachCheck}
+            disabled={isChecking() || !datasetPath().trim()}
+          >
+            Check
+          </button>
+        </div>
+        <p class="setting-description">Checked entirely offline against a local copy of the dataset</p>
+      </div>
+
+      <Show when={breached()}>
+        {(found) => (
+          <ul class="health-findings">
+            <For each={found()} fallback={<li class="setting-description">No vault password appears in the dataset</li>}>
+              {(finding) => (
+                <li>
+                  {titleOf(finding.entry_id)}
+                  <span class="setting-description">
+                    {" "}· seen {finding.count.toLocaleString()} times
+                  </span>
+                </li>
+              )}
+            </For>
+          </ul>
+        )}
+      </Show>
+    </div>
+  );
+};
+
+export default VaultHealth;
@@ -3,6 +3,27 @@ import type {
   PasswordEntry,
   CreatePasswordEntry,
   UpdatePasswordEntry,
+  SavedEntry,
+  KdfParams,
+  AutoLockSettings,
+  LockoutSettings,
+  SetupResult,
+  PasswordPolicy,
+  SavedPasswordPolicy,
+  PassphraseOptions,
+  Passphrase,
+  StrengthEstimate,
+  HealthSettings,
+  TrashSettings,
+  HealthReport,
+  BreachedPassword,
+  TotpCode,
+  EntryHistory,
+  Attachment,
+  Category,
+  CreateCategory,
+  UpdateCategory,
+  CategoryDeletion,
 } from "../types";
 
 // Check if we're in a Tauri environment (Tauri v2 uses __TAURI_INTERNALS__)
@@ -47,8 +68,10 @@ export class PasswordVaultAPI {
   }
 
   // Enhanced error handling
+  // Arguments and results carry master passwords, recovery codes and
+  // decrypted entries, so only the command name is ever logged
   private static async safeInvoke<T>(command: string, args?: any): Promise<T> {
-    console.log(`Attempting to invoke: ${command}`, args);
+    console.log(`Attempting to invoke: ${command}`);
 
     // Check if we're in a Tauri environment
     if (!isTauriEnvironment()) {
@@ -65,7 +88,7 @@ export class PasswordVaultAPI {
 
     try {
       const result = await invoke<T>(command, args);
-      console.log(`Successfully invoked ${command}:`, result);
+      console.log(`Successfully invoked ${command}`);
       return result;
     } catch (error) {
       console.error(`Failed to invoke ${command}:`, error);
@@ -74,18 +97,126 @@ export class PasswordVaultAPI {
   }
 
   // Master password management
-  static async setupMasterPassword(password: string): Promise<boolean> {
-    return this.safeInvoke<boolean>("setup_master_password", { password });
+  // keyFilePath is only passed for vaults that use a key file
+  static async setupMasterPassword(
+    password: string,
+    createRecoveryCode: boolean,
+    keyFilePath?: string,
+  ): Promise<SetupResult> {
+    return this.safeInvoke<SetupResult>("setup_master_password", {
+      password,
+      keyFilePath,
+      createRecoveryCode,
+    });
   }
 
-  static async verifyMasterPassword(password: string): Promise<boolean> {
-    return this.safeInvoke<boolean>("verify_master_password", { password });
+  static async verifyMasterPassword(
+    password: string,
+    keyFilePath?: string,
+  ): Promise<boolean> {
+    return this.safeInvoke<boolean>("verify_master_password", {
+      password,
+      keyFilePath,
+    });
   }
 
   static async hasMasterPassword(): Promise<boolean> {
     return this.safeInvoke<boolean>("has_master_password");
   }
 
+  static async changeMasterPassword(
+    oldPassword: string,
+    newPassword: string,
+    keyFilePath?: string,
+  ): Promise<void> {
+    return this.safeInvoke<void>("change_master_password", {
+      oldPassword,
+      newPassword,
+      keyFilePath,
+    });
+  }
+
+  // Key file
+  static async requiresKeyFile(): Promise<boolean> {
+    return this.safeInvoke<boolean>("requires_key_file");
+  }
+
+  // Titles and categories left unencrypted by an older version
+  static async hasPlaintextMetadata(): Promise<boolean> {
+    return this.safeInvoke<boolean>("has_plaintext_metadata");
+  }
+
+  static async addKeyFile(password: string, keyFilePath: string): Promise<void> {
+    return this.safeInvoke<void>("add_key_file", { password, keyFilePath });
+  }
+
+  static async removeKeyFile(password: string, keyFilePath: string): Promise<void> {
+    return this.safeInvoke<void>("remove_key_file", { password, keyFilePath });
+  }
+
+  // Recovery code
+  static async recoverWithKey(
+    recoveryCode: string,
+    newPassword: string,
+  ): Promise<void> {
+    return this.safeInvoke<void>("recover_with_key", {
+      recoveryCode,
+      newPassword,
+    });
+  }
+
+  static async hasRecoveryKey(): Promise<boolean> {
+    return this.safeInvoke<boolean>("has_recovery_key");
+  }
+
+  static async regenerateRecoveryKey(
+    password: string,
+    keyFilePath?: string,
+  ): Promise<string> {
+    return this.safeInvoke<string>("regenerate_recovery_key", {
+      password,
+      keyFilePath,
+    });
+  }
+
+  static async revokeRecoveryKey(): Promise<void> {
+    return this.safeInvoke<void>("revoke_recovery_key");
+  }
+
+  static async getKdfParams(): Promise<KdfParams> {
+    return this.safeInvoke<KdfParams>("get_kdf_params");
+  }
+
+  static async upgradeKdfParams(
+    password: string,
+    keyFilePath?: string,
+    targetMs?: number,
+  ): Promise<KdfParams> {
+    return this.safeInvoke<KdfParams>("upgrade_kdf_params", {
+      password,
+      keyFilePath,
+      targetMs,
+    });
+  }
+
+  // Backend-enforced auto-lock
+  static async getAutoLockSettings(): Promise<AutoLockSettings> {
+    return this.safeInvoke<AutoLockSettings>("get_auto_lock_settings");
+  }
+
+  static async setAutoLockSettings(settings: AutoLockSettings): Promise<void> {
+    return this.safeInvoke<void>("set_auto_lock_settings", { settings });
+  }
+
+  // Brute-force protection
+  static async getLockoutSettings(): Promise<LockoutSettings> {
+    return this.safeInvoke<LockoutSettings>("get_lockout_settings");
+  }
+
+  static async setLockoutSettings(settings: LockoutSettings): Promise<void> {
+    return this.safeInvoke<void>("set_lockout_settings", { settings });
+  }
+
   static async logout(): Promise<void> {
     return this.safeInvoke<void>("logout");
   }
@@ -93,14 +224,18 @@ export class PasswordVaultAPI {
   // Password entry management
   static async createPasswordEntry(
     entry: CreatePasswordEntry,
-  ): Promise<string> {
-    return this.safeInvoke<string>("create_password_entry", { entry });
+  ): Promise<SavedEntry> {
+    return this.safeInvoke<SavedEntry>("create_password_entry", { entry });
   }
 
   static async getAllEntries(): Promise<PasswordEntry[]> {
     return this.safeInvoke<PasswordEntry[]>("get_all_entries");
   }
 
+  static async getFavoriteEntries(): Promise<PasswordEntry[]> {
+    return this.safeInvoke<PasswordEntry[]>("get_favorite_entries");
+  }
+
   static async getEntryById(id: string): Promise<PasswordEntry> {
     return this.safeInvoke<PasswordEntry>("get_entry_by_id", { id });
   }
@@ -108,18 +243,95 @@ export class PasswordVaultAPI {
   static async updatePasswordEntry(
     id: string,
     update: UpdatePasswordEntry,
-  ): Promise<void> {
-    return this.safeInvoke<void>("update_password_entry", { id, update });
+  ): Promise<SavedEntry> {
+    return this.safeInvoke<SavedEntry>("update_password_entry", { id, update });
+  }
+
+  static async getTotpCode(id: string): Promise<TotpCode> {
+    return this.safeInvoke<TotpCode>("get_totp_code", { id });
+  }
+
+  // Password history
+  static async getEntryHistory(id: string): Promise<EntryHistory[]> {
+    return this.safeInvoke<EntryHistory[]>("get_entry_history", { id });
+  }
+
+  static async restoreEntryVersion(id: string, historyId: string): Promise<void> {
+    return this.safeInvoke<void>("restore_entry_version", { id, historyId });
+  }
+
+  static async purgeEntryHistory(keep: number): Promise<number> {
+    return this.safeInvoke<number>("purge_entry_history", { keep });
   }
 
   static async deletePasswordEntry(id: string): Promise<void> {
     return this.safeInvoke<void>("delete_password_entry", { id });
   }
 
+  // Trash
+  static async getTrashedEntries(): Promise<PasswordEntry[]> {
+    return this.safeInvoke<PasswordEntry[]>("get_trashed_entries");
+  }
+
+  static async restoreEntry(id: string): Promise<void> {
+    return this.safeInvoke<void>("restore_entry", { id });
+  }
+
+  static async emptyTrash(): Promise<number> {
+    return this.safeInvoke<number>("empty_trash");
+  }
+
+  static async getTrashSettings(): Promise<TrashSettings> {
+    return this.safeInvoke<TrashSettings>("get_trash_settings");
+  }
+
+  static async setTrashSettings(settings: TrashSettings): Promise<void> {
+    return this.safeInvoke<void>("set_trash_settings", { settings });
+  }
+
+  // Attachments; paths are on the local file system
+  static async addAttachment(entryId: string, path: string): Promise<Attachment> {
+    return this.safeInvoke<Attachment>("add_attachment", { entryId, path });
+  }
+
+  static async getAttachments(entryId: string): Promise<Attachment[]> {
+    return this.safeInvoke<Attachment[]>("get_attachments", { entryId });
+  }
+
+  static async exportAttachment(id: string, path: string): Promise<void> {
+    return this.safeInvoke<void>("export_attachment", { id, path });
+  }
+
+  static async deleteAttachment(id: string): Promise<void> {
+    return this.safeInvoke<void>("delete_attachment", { id });
+  }
+
+  // Writes every entry, unencrypted, to a JSON file; returns the number written
+  static async exportVault(password: string, path: string, keyFilePath?: string): Promise<number> {
+    return this.safeInvoke<number>("export_vault", { password, keyFilePath, path });
+  }
+
   static async searchEntries(query: string): Promise<PasswordEntry[]> {
     return this.safeInvoke<PasswordEntry[]>("search_entries", { query });
   }
 
+  // Categories
+  static async getCategories(): Promise<Category[]> {
+    return this.safeInvoke<Category[]>("get_categories");
+  }
+
+  static async createCategory(category: CreateCategory): Promise<string> {
+    return this.safeInvoke<string>("create_category", { category });
+  }
+
+  static async updateCategory(id: string, update: UpdateCategory): Promise<void> {
+    return this.safeInvoke<void>("update_category", { id, update });
+  }
+
+  static async deleteCategory(id: string, deletion: CategoryDeletion): Promise<void> {
+    return this.safeInvoke<void>("delete_category", { id, deletion });
+  }
+
   // Utility functions
   static async generatePassword(
     length: number = 16,
@@ -130,4 +342,46 @@ export class PasswordVaultAPI {
       includeSymbols,
     });
   }
+
+  static async generatePasswordWithPolicy(policy: PasswordPolicy): Promise<string> {
+    return this.safeInvoke<string>("generate_password_with_policy", { policy });
+  }
+
+  // Vault health
+  static async getHealthSettings(): Promise<HealthSettings> {
+    return this.safeInvoke<HealthSettings>("get_health_settings");
+  }
+
+  static async setHealthSettings(settings: HealthSettings): Promise<void> {
+    return this.safeInvoke<void>("set_health_settings", { settings });
+  }
+
+  static async vaultHealthReport(): Promise<HealthReport> {
+    return this.safeInvoke<HealthReport>("vault_health_report");
+  }
+
+  static async checkBreachedPasswords(datasetPath: string): Promise<BreachedPassword[]> {
+    return this.safeInvoke<BreachedPassword[]>("check_breached_passwords", { datasetPath });
+  }
+
+  static async estimateStrength(password: string, userInputs: string[] = []): Promise<StrengthEstimate> {
+    return this.safeInvoke<StrengthEstimate>("estimate_strength", { password, userInputs });
+  }
+
+  static async generatePassphrase(options: PassphraseOptions): Promise<Passphrase> {
+    return this.safeInvoke<Passphrase>("generate_passphrase", { options });
+  }
+
+  // Saved generator policies
+  static async savePasswordPolicy(name: string, policy: PasswordPolicy): Promise<string> {
+    return this.safeInvoke<string>("save_password_policy", { name, policy });
+  }
+
+  static async getPasswordPolicies(): Promise<SavedPasswordPolicy[]> {
+    return this.safeInvoke<SavedPasswordPolicy[]>("get_password_policies");
+  }
+
+  static async deletePasswordPolicy(id: string): Promise<void> {
+    return this.safeInvoke<void>("delete_password_policy", { id });
+  }
 }
@@ -1,16 +1,40 @@
 import { createSignal, createEffect } from "solid-js";
+import { listen } from "@tauri-apps/api/event";
 import { PasswordVaultAPI } from "../services/api";
-import type { AuthState } from "../types";
+import type { AppError, AuthState } from "../types";
 
 // Auth state signals
 const [authState, setAuthState] = createSignal<AuthState>({
   isAuthenticated: false,
   hasMasterPassword: false,
+  requiresKeyFile: false,
 });
 
 const [isLoading, setIsLoading] = createSignal(false);
 const [error, setError] = createSignal<string | null>(null);
 
+// Recovery code waiting to be shown to the user; it is never stored
+const [recoveryCode, setRecoveryCode] = createSignal<string | null>(null);
+
+// Seconds until the backend accepts another unlock attempt
+const [retryAfter, setRetryAfter] = createSignal(0);
+let retryTimer: ReturnType<typeof setInterval> | undefined;
+
+const startRetryCountdown = (seconds: number) => {
+  clearInterval(retryTimer);
+  setRetryAfter(seconds);
+
+  retryTimer = setInterval(() => {
+    setRetryAfter(prev => {
+      if (prev <= 1) {
+        clearInterval(retryTimer);
+        return 0;
+      }
+      return prev - 1;
+    });
+  }, 1000);
+};
+
 // Wait for Tauri to be ready (Tauri v2 uses __TAURI_INTERNALS__)
 const waitForTauri = (): Promise<void> => {
   return new Promise((resolve) => {
@@ -34,6 +58,21 @@ const waitForTauri = (): Promise<void> => {
   });
 };
 
+// Emitted by the backend idle timer after it has wiped the vault key
+const VAULT_LOCKED_EVENT = "vault-locked";
+let vaultLockedListener: Promise<unknown> | null = null;
+
+const listenForVaultLocked = () => {
+  if (vaultLockedListener) return;
+
+  vaultLockedListener = listen(VAULT_LOCKED_EVENT, () => {
+    setAuthState(prev => ({
+      ...prev,
+      isAuthenticated: false,
+    }));
+  });
+};
+
 // Initialize auth state on app start
 export const initializeAuth = async () => {
   setIsLoading(true);
@@ -42,11 +81,14 @@ export const initializeAuth = async () => {
   try {
     // Wait for Tauri to be ready
     await waitForTauri();
+    listenForVaultLocked();
     
     const hasMasterPassword = await PasswordVaultAPI.hasMasterPassword();
+    const requiresKeyFile = hasMasterPassword && await PasswordVaultAPI.requiresKeyFile();
     setAuthState({
       isAuthenticated: false,
       hasMasterPassword,
+      requiresKeyFile,
     });
   } catch (err) {
     console.error("Auth initialization error:", err);
@@ -57,21 +99,25 @@ export const initializeAuth = async () => {
 };
 
 // Setup master password (first time setup)
-export const setupMasterPassword = async (password: string): Promise<boolean> => {
+export const setupMasterPassword = async (
+  password: string,
+  createRecoveryCode: boolean = true,
+  keyFilePath?: string,
+): Promise<boolean> => {
   setIsLoading(true);
   setError(null);
   
   try {
-    const success = await PasswordVaultAPI.setupMasterPassword(password);
-    if (success) {
-      setAuthState({
-        isAuthenticated: true,
-        hasMasterPassword: true,
-      });
-    }
-    return success;
+    const result = await PasswordVaultAPI.setupMasterPassword(password, createRecoveryCode, keyFilePath);
+    setRecoveryCode(result.recovery_code);
+    setAuthState({
+      isAuthenticated: true,
+      hasMasterPassword: true,
+      requiresKeyFile: keyFilePath !== undefined,
+    });
+    return true;
   } catch (err) {
-    setError(err instanceof Error ? err.message : "Failed to setup master password");
+    setError((err as AppError)?.message ?? "Failed to setup master password");
     return false;
   } finally {
     setIsLoading(false);
@@ -79,12 +125,12 @@ export const setupMasterPassword = async (password: string): Promise<boolean> =>
 };
 
 // Login with master password
-export const login = async (password: string): Promise<boolean> => {
+export const login = async (password: string, keyFilePath?: string): Promise<boolean> => {
   setIsLoading(true);
   setError(null);
   
   try {
-    const success = await PasswordVaultAPI.verifyMasterPassword(password);
+    const success = await PasswordVaultAPI.verifyMasterPassword(password, keyFilePath);
     if (success) {
       setAuthState(prev => ({
         ...prev,
@@ -95,13 +141,111 @@ export const login = async (password: string): Promise<boolean> => {
     }
     return success;
   } catch (err) {
-    setError(err instanceof Error ? err.message : "Failed to login");
+    const appError = err as AppError;
+
+    if (appError?.code === "too_many_attempts" && appError.retry_after_secs) {
+      startRetryCountdown(appError.retry_after_secs);
+    } else if (appError?.code === "vault_wiped") {
+      // The vault no longer exists; fall through to first-time setup
+      setAuthState({
+        isAuthenticated: false,
+        hasMasterPassword: false,
+        requiresKeyFile: false,
+      });
+    }
+
+    setError(appError?.message ?? "Failed to login");
     return false;
   } finally {
     setIsLoading(false);
   }
 };
 
+// Unlock with the recovery code and set a new master password
+// Does not toggle isLoading, which would unmount the recovery form on failure
+export const recoverWithKey = async (
+  code: string,
+  newPassword: string,
+): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.recoverWithKey(code, newPassword);
+    // Recovery drops the key file requirement
+    setAuthState(prev => ({
+      ...prev,
+      isAuthenticated: true,
+      requiresKeyFile: false,
+    }));
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to recover vault");
+    return false;
+  }
+};
+
+// Replace the recovery code; the new one is shown through recoveryCode()
+export const regenerateRecoveryCode = async (
+  password: string,
+  keyFilePath?: string,
+): Promise<boolean> => {
+  setError(null);
+
+  try {
+    setRecoveryCode(await PasswordVaultAPI.regenerateRecoveryKey(password, keyFilePath));
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to regenerate recovery code");
+    return false;
+  }
+};
+
+// Forget the recovery code once the user has saved it
+export const dismissRecoveryCode = () => setRecoveryCode(null);
+
+// Change master password (re-wraps the vault key)
+// Does not toggle isLoading, which would unmount the dashboard mid-change
+export const changeMasterPassword = async (
+  oldPassword: string,
+  newPassword: string,
+  keyFilePath?: string,
+): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.changeMasterPassword(oldPassword, newPassword, keyFilePath);
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to change master password");
+    return false;
+  }
+};
+
+// Add or remove the key file as a second unlock factor
+export const setKeyFileRequired = async (
+  required: boolean,
+  password: string,
+  keyFilePath: string,
+): Promise<boolean> => {
+  setError(null);
+
+  try {
+    if (required) {
+      await PasswordVaultAPI.addKeyFile(password, keyFilePath);
+    } else {
+      await PasswordVaultAPI.removeKeyFile(password, keyFilePath);
+    }
+    setAuthState(prev => ({
+      ...prev,
+      requiresKeyFile: required,
+    }));
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to update key file");
+    return false;
+  }
+};
+
 // Logout
 export const logout = async () => {
   setIsLoading(true);
@@ -124,4 +268,4 @@ export const logout = async () => {
 export const clearError = () => setError(null);
 
 // Export reactive signals
-export { authState, isLoading, error };
+export { authState, isLoading, error, retryAfter, recoveryCode };
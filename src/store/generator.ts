@@ -0,0 +1,102 @@
+import { createSignal } from "solid-js";
+import { PasswordVaultAPI } from "../services/api";
+import type { AppError, Passphrase, PassphraseOptions, PasswordPolicy, SavedPasswordPolicy } from "../types";
+
+// Mirrors PasswordPolicy::default() in the backend
+export const defaultPolicy: PasswordPolicy = {
+  length: 16,
+  lowercase: true,
+  uppercase: true,
+  digits: true,
+  symbols: "!@#$%^&*()_+-=[]{}|;:,.<>?",
+  min_lowercase: 1,
+  min_uppercase: 1,
+  min_digits: 1,
+  min_symbols: 1,
+  exclude_ambiguous: false,
+  no_repeats: false,
+};
+
+// Mirrors PassphraseOptions::default() in the backend
+export const defaultPassphraseOptions: PassphraseOptions = {
+  word_count: 6,
+  separator: "-",
+  capitalize: false,
+  include_digit: false,
+};
+
+// Saved policies state
+const [policies, setPolicies] = createSignal<SavedPasswordPolicy[]>([]);
+const [error, setError] = createSignal<string | null>(null);
+
+// Load saved policies
+export const loadPolicies = async () => {
+  setError(null);
+
+  try {
+    setPolicies(await PasswordVaultAPI.getPasswordPolicies());
+  } catch (err) {
+    console.error("Load policies error:", err);
+    setError((err as AppError)?.message ?? "Failed to load policies");
+  }
+};
+
+// Save a named policy
+export const savePolicy = async (name: string, policy: PasswordPolicy): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.savePasswordPolicy(name, policy);
+    await loadPolicies();
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to save policy");
+    return false;
+  }
+};
+
+// Delete a saved policy
+export const deletePolicy = async (id: string): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.deletePasswordPolicy(id);
+    setPolicies(prev => prev.filter(p => p.id !== id));
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to delete policy");
+    return false;
+  }
+};
+
+// Generate a password that satisfies the policy
+export const generateWithPolicy = async (policy: PasswordPolicy): Promise<string> => {
+  setError(null);
+
+  try {
+    return await PasswordVaultAPI.generatePasswordWithPolicy(policy);
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to generate password");
+    return "";
+  }
+};
+
+// Generate a passphrase from the EFF wordlist
+export const generatePassphrase = async (
+  options: PassphraseOptions = defaultPassphraseOptions,
+): Promise<Passphrase | null> => {
+  setError(null);
+
+  try {
+    return await PasswordVaultAPI.generatePassphrase(options);
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to generate passphrase");
+    return null;
+  }
+};
+
+// Clear error
+export const clearError = () => setError(null);
+
+// Export reactive signals
+export { policies, error };
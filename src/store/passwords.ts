@@ -1,6 +1,6 @@
 import { createSignal } from "solid-js";
 import { PasswordVaultAPI } from "../services/api";
-import type { PasswordEntry, CreatePasswordEntry, UpdatePasswordEntry } from "../types";
+import type { AppError, PasswordEntry, CreatePasswordEntry, UpdatePasswordEntry, SavedEntry } from "../types";
 
 // Password entries state
 const [entries, setEntries] = createSignal<PasswordEntry[]>([]);
@@ -51,37 +51,37 @@ export const loadEntries = async () => {
   }
 };
 
-// Create new entry
-export const createEntry = async (entry: CreatePasswordEntry): Promise<boolean> => {
+// Create new entry; null when it failed
+export const createEntry = async (entry: CreatePasswordEntry): Promise<SavedEntry | null> => {
   setIsLoading(true);
   setError(null);
   
   try {
-    const id = await PasswordVaultAPI.createPasswordEntry(entry);
+    const saved = await PasswordVaultAPI.createPasswordEntry(entry);
     // Reload entries to get the new one with full data
     await loadEntries();
-    return true;
+    return saved;
   } catch (err) {
     setError(err instanceof Error ? err.message : "Failed to create entry");
-    return false;
+    return null;
   } finally {
     setIsLoading(false);
   }
 };
 
-// Update entry
-export const updateEntry = async (id: string, update: UpdatePasswordEntry): Promise<boolean> => {
+// Update entry; null when it failed
+export const updateEntry = async (id: string, update: UpdatePasswordEntry): Promise<SavedEntry | null> => {
   setIsLoading(true);
   setError(null);
   
   try {
-    await PasswordVaultAPI.updatePasswordEntry(id, update);
+    const saved = await PasswordVaultAPI.updatePasswordEntry(id, update);
     // Reload entries to get updated data
     await loadEntries();
-    return true;
+    return saved;
   } catch (err) {
     setError(err instanceof Error ? err.message : "Failed to update entry");
-    return false;
+    return null;
   } finally {
     setIsLoading(false);
   }
@@ -109,6 +109,24 @@ export const deleteEntry = async (id: string): Promise<boolean> => {
   }
 };
 
+// Put an old username and password back; the replaced values join the history
+export const restoreEntryVersion = async (id: string, historyId: string): Promise<boolean> => {
+  setIsLoading(true);
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.restoreEntryVersion(id, historyId);
+    await loadEntries();
+    setSelectedEntry(entries().find(entry => entry.id === id) ?? null);
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to restore entry");
+    return false;
+  } finally {
+    setIsLoading(false);
+  }
+};
+
 // Toggle favorite status
 export const toggleFavorite = async (id: string): Promise<boolean> => {
   const entry = entries().find(e => e.id === id);
@@ -140,10 +158,8 @@ export const searchEntries = async (query: string) => {
 };
 
 // Filter entries by category
-export const getEntriesByCategory = (categoryName: string): PasswordEntry[] => {
-  return entries().filter(entry => 
-    entry.category?.toLowerCase() === categoryName.toLowerCase()
-  );
+export const getEntriesByCategory = (categoryId: string): PasswordEntry[] => {
+  return entries().filter(entry => entry.category_id === categoryId);
 };
 
 // Get favorite entries
@@ -153,7 +169,7 @@ export const getFavoriteEntries = (): PasswordEntry[] => {
 
 // Get entries without category
 export const getUncategorizedEntries = (): PasswordEntry[] => {
-  return entries().filter(entry => !entry.category || entry.category.trim() === '');
+  return entries().filter(entry => !entry.category_id);
 };
 
 // Get all unique categories from entries
@@ -21,6 +21,11 @@ export const navigateToCategory = (categoryId: string) => {
   setSelectedCategory(categoryId);
 };
 
+export const navigateToTrash = () => {
+  setCurrentView('trash');
+  setSelectedCategory(null);
+};
+
 export const navigateToSettings = () => {
   setCurrentView('settings');
   setSelectedCategory(null);
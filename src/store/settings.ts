@@ -1,5 +1,7 @@
 import { createSignal, createEffect } from "solid-js";
-import type { AppSettings } from "../types";
+import { PasswordVaultAPI } from "../services/api";
+import { authState } from "./auth";
+import type { AppError, AppSettings } from "../types";
 
 // Default settings
 const defaultSettings: AppSettings = {
@@ -41,6 +43,34 @@ const saveSettingsToStorage = (settings: AppSettings) => {
   }
 };
 
+// Auto-lock is enforced by the backend; push changes to it
+const syncAutoLock = async (settings: AppSettings) => {
+  await PasswordVaultAPI.setAutoLockSettings({
+    enabled: settings.autoLockEnabled,
+    idle_minutes: settings.autoLockTime,
+  });
+};
+
+const touchesAutoLock = (updates: Partial<AppSettings>) =>
+  "autoLockEnabled" in updates || "autoLockTime" in updates;
+
+// The backend owns the auto-lock settings; they are encrypted, so it only
+// has them once the vault is unlocked
+const loadAutoLock = async () => {
+  try {
+    const autoLock = await PasswordVaultAPI.getAutoLockSettings();
+    const merged = {
+      ...settings(),
+      autoLockEnabled: autoLock.enabled,
+      autoLockTime: autoLock.idle_minutes,
+    };
+    setSettings(merged);
+    saveSettingsToStorage(merged);
+  } catch (err) {
+    console.error("Failed to load auto-lock settings from backend:", err);
+  }
+};
+
 // Initialize settings
 export const initializeSettings = async () => {
   setIsLoading(true);
@@ -51,10 +81,6 @@ export const initializeSettings = async () => {
     const storedSettings = loadSettingsFromStorage();
     setSettings(storedSettings);
     
-    // TODO: In the future, sync with backend
-    // const backendSettings = await PasswordVaultAPI.getSettings();
-    // setSettings({ ...storedSettings, ...backendSettings });
-    
   } catch (err) {
     console.error("Settings initialization error:", err);
     setError(err instanceof Error ? err.message : "Failed to initialize settings");
@@ -73,16 +99,17 @@ export const updateSetting = async <K extends keyof AppSettings>(
   
   try {
     const newSettings = { ...settings(), [key]: value };
+    if (touchesAutoLock({ [key]: value })) {
+      await syncAutoLock(newSettings);
+    }
+
     setSettings(newSettings);
     saveSettingsToStorage(newSettings);
     
-    // TODO: Sync with backend
-    // await PasswordVaultAPI.updateSettings({ [key]: value });
-    
     return true;
   } catch (err) {
     console.error("Update setting error:", err);
-    setError(err instanceof Error ? err.message : "Failed to update setting");
+    setError((err as AppError)?.message ?? "Failed to update setting");
     return false;
   } finally {
     setIsLoading(false);
@@ -96,16 +123,17 @@ export const updateSettings = async (updates: Partial<AppSettings>): Promise<boo
   
   try {
     const newSettings = { ...settings(), ...updates };
+    if (touchesAutoLock(updates)) {
+      await syncAutoLock(newSettings);
+    }
+
     setSettings(newSettings);
     saveSettingsToStorage(newSettings);
     
-    // TODO: Sync with backend
-    // await PasswordVaultAPI.updateSettings(updates);
-    
     return true;
   } catch (err) {
     console.error("Update settings error:", err);
-    setError(err instanceof Error ? err.message : "Failed to update settings");
+    setError((err as AppError)?.message ?? "Failed to update settings");
     return false;
   } finally {
     setIsLoading(false);
@@ -118,22 +146,28 @@ export const resetSettings = async (): Promise<boolean> => {
   setError(null);
   
   try {
+    await syncAutoLock(defaultSettings);
+
     setSettings(defaultSettings);
     saveSettingsToStorage(defaultSettings);
     
-    // TODO: Sync with backend
-    // await PasswordVaultAPI.resetSettings();
-    
     return true;
   } catch (err) {
     console.error("Reset settings error:", err);
-    setError(err instanceof Error ? err.message : "Failed to reset settings");
+    setError((err as AppError)?.message ?? "Failed to reset settings");
     return false;
   } finally {
     setIsLoading(false);
   }
 };
 
+// Pick up the vault's auto-lock settings after each unlock
+createEffect(() => {
+  if (authState().isAuthenticated) {
+    loadAutoLock();
+  }
+});
+
 // Apply dark mode to document
 createEffect(() => {
   if (settings().darkMode) {
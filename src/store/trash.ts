@@ -0,0 +1,56 @@
+import { createSignal } from "solid-js";
+import { PasswordVaultAPI } from "../services/api";
+import { loadEntries } from "./passwords";
+import type { AppError, PasswordEntry } from "../types";
+
+// Trash state
+const [trashedEntries, setTrashedEntries] = createSignal<PasswordEntry[]>([]);
+const [isLoading, setIsLoading] = createSignal(false);
+const [error, setError] = createSignal<string | null>(null);
+
+// Load the entries in the trash, most recently deleted first
+export const loadTrash = async () => {
+  setIsLoading(true);
+  setError(null);
+
+  try {
+    setTrashedEntries(await PasswordVaultAPI.getTrashedEntries());
+  } catch (err) {
+    console.error("Load trash error:", err);
+    setError((err as AppError)?.message ?? "Failed to load trash");
+  } finally {
+    setIsLoading(false);
+  }
+};
+
+// Move an entry back into the vault
+export const restoreEntry = async (id: string): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.restoreEntry(id);
+    setTrashedEntries(prev => prev.filter(entry => entry.id !== id));
+    await loadEntries();
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to restore entry");
+    return false;
+  }
+};
+
+// Permanently delete everything in the trash
+export const emptyTrash = async (): Promise<boolean> => {
+  setError(null);
+
+  try {
+    await PasswordVaultAPI.emptyTrash();
+    setTrashedEntries([]);
+    return true;
+  } catch (err) {
+    setError((err as AppError)?.message ?? "Failed to empty trash");
+    return false;
+  }
+};
+
+// Export reactive signals
+export { trashedEntries, isLoading, error };
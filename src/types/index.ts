@@ -2,25 +2,84 @@
 
 export interface PasswordEntry {
   id: string;
+  item_type: ItemType;
+  item_data?: ItemData; // absent for logins
   title: string;
   username: string;
   password: string;
   url?: string;
   notes?: string;
-  category?: string;
+  category_id?: string;
+  category?: string; // name of the referenced category
+  totp?: string; // otpauth:// URI or base32 secret
   created_at: string;
   updated_at: string;
-  is_favorite?: boolean;
+  deleted_at?: string; // set while the entry is in the trash
+  is_favorite: boolean;
+  custom_fields: CustomField[];
+}
+
+// Booleans are stored as "true" or "false" and dates as YYYY-MM-DD;
+// hidden values are masked until revealed and never matched by search
+export type CustomFieldType = "text" | "hidden" | "boolean" | "url" | "date";
+
+export interface CustomField {
+  name: string;
+  value: string;
+  field_type: CustomFieldType;
+}
+
+// Logins and Wi-Fi networks keep their secret in the entry's password;
+// a secure note keeps its text in the entry's notes
+export type ItemType = "login" | "card" | "secure_note" | "identity" | "wifi" | "license";
+
+export interface CardData {
+  cardholder_name: string;
+  number: string;
+  expiry: string; // MM/YY or MM/YYYY
+  security_code?: string;
 }
 
+export interface IdentityData {
+  full_name: string;
+  email?: string;
+  phone?: string;
+  address?: string;
+  birth_date?: string; // YYYY-MM-DD
+}
+
+export type WifiSecurity = "open" | "wep" | "wpa_personal" | "wpa3_personal" | "enterprise";
+
+export interface WifiData {
+  ssid: string;
+  security: WifiSecurity;
+}
+
+export interface LicenseData {
+  license_key: string;
+  licensed_to?: string;
+  version?: string;
+  expires?: string; // YYYY-MM-DD
+}
+
+export type ItemData =
+  | ({ type: "card" } & CardData)
+  | { type: "secure_note" }
+  | ({ type: "identity" } & IdentityData)
+  | ({ type: "wifi" } & WifiData)
+  | ({ type: "license" } & LicenseData);
+
 export interface CreatePasswordEntry {
   title: string;
   username: string;
   password: string;
   url?: string;
   notes?: string;
-  category?: string;
+  category_id?: string;
+  totp?: string;
   is_favorite?: boolean;
+  custom_fields?: CustomField[];
+  item_data?: ItemData;
 }
 
 export interface UpdatePasswordEntry {
@@ -29,8 +88,11 @@ export interface UpdatePasswordEntry {
   password?: string;
   url?: string;
   notes?: string;
-  category?: string;
+  category_id?: string; // "" removes the entry from its category
+  totp?: string;
   is_favorite?: boolean;
+  custom_fields?: CustomField[]; // replaces the whole list
+  item_data?: ItemData | null; // null turns the item back into a login
 }
 
 export interface Category {
@@ -47,13 +109,152 @@ export interface CreateCategory {
   icon?: string;
 }
 
+export type UpdateCategory = Partial<CreateCategory>;
+
+// What happens to a deleted category's entries
+export type CategoryDeletion =
+  | { action: "reassign"; category_id: string }
+  | { action: "clear" };
+
+export interface KdfParams {
+  algorithm: string;
+  memory_kib: number;
+  iterations: number;
+  parallelism: number;
+}
+
+export interface PasswordPolicy {
+  length: number;
+  lowercase: boolean;
+  uppercase: boolean;
+  digits: boolean;
+  symbols: string | null;
+  min_lowercase: number;
+  min_uppercase: number;
+  min_digits: number;
+  min_symbols: number;
+  exclude_ambiguous: boolean;
+  no_repeats: boolean;
+}
+
+export interface SavedPasswordPolicy {
+  id: string;
+  name: string;
+  policy: PasswordPolicy;
+  created_at: string;
+}
+
+export interface PassphraseOptions {
+  word_count: number;
+  separator: string;
+  capitalize: boolean;
+  include_digit: boolean;
+}
+
+export interface Passphrase {
+  passphrase: string;
+  entropy_bits: number;
+}
+
+export interface StrengthEstimate {
+  score: number; // 0 (too guessable) to 4 (very unguessable)
+  guesses_log10: number;
+  crack_time_seconds: number;
+  crack_time_display: string;
+  warning: string | null;
+  suggestions: string[];
+}
+
+export interface SavedEntry {
+  id: string;
+  strength: StrengthEstimate | null; // set when the save changed a password
+  warning: "weak_password" | null;
+}
+
+export interface TotpCode {
+  code: string;
+  seconds_remaining: number;
+  period: number;
+}
+
+// A username and password an entry held before an edit replaced them
+export interface EntryHistory {
+  id: string;
+  entry_id: string;
+  username: string;
+  password: string;
+  created_at: string;
+}
+
+export interface HealthSettings {
+  max_password_age_days: number;
+}
+
+// A file kept with an entry; its contents stay encrypted until exported
+export interface Attachment {
+  id: string;
+  entry_id: string;
+  name: string;
+  size: number; // bytes
+  created_at: string;
+}
+
+export interface TrashSettings {
+  retention_days: number;
+}
+
+export interface HealthReport {
+  score: number; // 0 to 100
+  total_entries: number;
+  reused: { entry_ids: string[] }[];
+  weak: { entry_id: string; score: number; warning: string | null }[];
+  old: { entry_id: string; updated_at: string; age_days: number }[];
+  missing_url: string[];
+  generated_at: string;
+}
+
+export interface BreachedPassword {
+  entry_id: string;
+  count: number;
+}
+
+export interface SetupResult {
+  recovery_code: string | null;
+}
+
+export interface AutoLockSettings {
+  enabled: boolean;
+  idle_minutes: number;
+}
+
+export interface LockoutSettings {
+  wipe_after_failures: number | null;
+}
+
 export interface AppError {
   message: string;
+  code?:
+    | "invalid_master_password"
+    | "too_many_attempts"
+    | "vault_wiped"
+    | "invalid_recovery_code"
+    | "key_file_required"
+    | "weak_password"
+    | "invalid_totp"
+    | "no_totp"
+    | "category_not_found"
+    | "duplicate_category"
+    | "invalid_custom_field"
+    | "invalid_item"
+    | "attachment_not_found"
+    | "attachment_too_large";
+  retry_after_secs?: number;
 }
 
 export interface AuthState {
   isAuthenticated: boolean;
   hasMasterPassword: boolean;
+  requiresKeyFile: boolean;
 }
 
 export interface AppSettings {
@@ -64,4 +265,4 @@ export interface AppSettings {
   defaultCategory?: string;
 }
 
-export type ViewMode = 'all' | 'favorites' | 'category' | 'settings';
+export type ViewMode = 'all' | 'favorites' | 'category' | 'trash' | 'settings';
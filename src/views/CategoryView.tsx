@@ -23,8 +23,7 @@ const CategoryView = () => {
       return getUncategorizedEntries();
     }
     
-    const cat = getCategoryById(categoryId);
-    return cat ? getEntriesByCategory(cat.name) : [];
+    return getEntriesByCategory(categoryId);
   };
 
   const getCategoryTitle = () => {
@@ -152,7 +151,7 @@ const CategoryView = () => {
         <PasswordForm 
           onClose={handleCloseForm}
           onSave={handleCloseForm}
-          defaultCategory={selectedCategoryId() !== 'uncategorized' ? category()?.name : undefined}
+          defaultCategory={selectedCategoryId() !== 'uncategorized' ? category()?.id : undefined}
         />
       </Show>
 
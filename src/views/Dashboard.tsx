@@ -8,6 +8,7 @@ import {
   error,
 } from "../store/passwords";
 import { logout } from "../store/auth";
+import { loadCategories } from "../store/categories";
 import { currentView } from "../store/router";
 import Sidebar from "../components/Navigation/Sidebar";
 import Header from "../components/Navigation/Header";
@@ -15,6 +16,7 @@ import ItemCard from "../components/ui/ItemCard";
 import PasswordForm from "../components/ui/PasswordForm";
 import PasswordDetails from "../components/ui/PasswordDetails";
 import FavoritesView from "./FavoritesView";
+import TrashView from "./TrashView";
 import CategoryView from "./CategoryView";
 import SettingsView from "./SettingsView";
 import type { PasswordEntry } from "../types";
@@ -25,9 +27,10 @@ const Dashboard = () => {
     null,
   );
 
-  // Load entries on component mount
+  // Load entries and categories on component mount
   createEffect(() => {
     loadEntries();
+    loadCategories();
   });
 
   const handleSearch = (query: string) => {
@@ -160,6 +163,10 @@ const Dashboard = () => {
           <CategoryView />
         </Show>
 
+        <Show when={currentView() === "trash"}>
+          <TrashView />
+        </Show>
+
         <Show when={currentView() === "settings"}>
           <SettingsView />
         </Show>
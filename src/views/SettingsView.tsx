@@ -1,4 +1,4 @@
-import { createSignal, Show, For } from "solid-js";
+import { createSignal, onMount, Show, For } from "solid-js";
 import { 
   settings, 
   updateSetting, 
@@ -7,19 +7,59 @@ import {
   isLoading as settingsLoading,
   error as settingsError 
 } from "../store/settings";
-import { categories, createCategory, updateCategory, deleteCategory } from "../store/categories";
-import { logout } from "../store/auth";
+import {
+  categories,
+  createCategory,
+  updateCategory,
+  deleteCategory,
+  error as categoriesError
+} from "../store/categories";
+import {
+  authState,
+  logout,
+  changeMasterPassword,
+  regenerateRecoveryCode,
+  setKeyFileRequired,
+  error as authError
+} from "../store/auth";
 import { showToast } from "../components/StateFeedback/Toast";
+import GeneratorSettings from "../components/ui/GeneratorSettings";
+import VaultHealth from "../components/ui/VaultHealth";
 import { useConfirmDialog } from "../components/StateFeedback/ConfirmDialog";
-import type { CreateCategory } from "../types";
+import { PasswordVaultAPI } from "../services/api";
+import type { AppError, CreateCategory } from "../types";
 
 const SettingsView = () => {
-  const [activeTab, setActiveTab] = createSignal<'general' | 'security' | 'categories' | 'about'>('general');
+  const [activeTab, setActiveTab] = createSignal<'general' | 'security' | 'generator' | 'health' | 'categories' | 'about'>('general');
   const [showCategoryForm, setShowCategoryForm] = createSignal(false);
   const [editingCategory, setEditingCategory] = createSignal<string | null>(null);
   const [newCategoryName, setNewCategoryName] = createSignal("");
   const [newCategoryColor, setNewCategoryColor] = createSignal("#3b82f6");
   const [newCategoryIcon, setNewCategoryIcon] = createSignal("📁");
+  const [deletingCategory, setDeletingCategory] = createSignal<string | null>(null);
+  // Category that receives the deleted one's entries; "" leaves them uncategorized
+  const [reassignTo, setReassignTo] = createSignal("");
+  const [showPasswordForm, setShowPasswordForm] = createSignal(false);
+  const [currentPassword, setCurrentPassword] = createSignal("");
+  const [newPassword, setNewPassword] = createSignal("");
+  const [confirmNewPassword, setConfirmNewPassword] = createSignal("");
+  const [isChangingPassword, setIsChangingPassword] = createSignal(false);
+  const [wipeAfterFailures, setWipeAfterFailures] = createSignal<number | null>(null);
+  const [hasRecoveryCode, setHasRecoveryCode] = createSignal(false);
+  const [showRecoveryForm, setShowRecoveryForm] = createSignal(false);
+  const [recoveryPassword, setRecoveryPassword] = createSignal("");
+  const [isRegenerating, setIsRegenerating] = createSignal(false);
+  const [keyFilePath, setKeyFilePath] = createSignal("");
+  const [showKeyFileForm, setShowKeyFileForm] = createSignal(false);
+  const [keyFilePassword, setKeyFilePassword] = createSignal("");
+  const [isUpdatingKeyFile, setIsUpdatingKeyFile] = createSignal(false);
+  const [showExportForm, setShowExportForm] = createSignal(false);
+  const [exportPassword, setExportPassword] = createSignal("");
+  const [exportPath, setExportPath] = createSignal("");
+  const [isExporting, setIsExporting] = createSignal(false);
+
+  // Key file path to send along with the master password, if the vault uses one
+  const currentKeyFile = () => authState().requiresKeyFile ? keyFilePath() : undefined;
 
   const { dialogState, showConfirm, ConfirmDialog } = useConfirmDialog();
 
@@ -32,6 +72,97 @@ const SettingsView = () => {
     }
   };
 
+  onMount(async () => {
+    try {
+      const lockout = await PasswordVaultAPI.getLockoutSettings();
+      setWipeAfterFailures(lockout.wipe_after_failures);
+    } catch (err) {
+      console.error("Failed to load lockout settings:", err);
+    }
+
+    try {
+      setHasRecoveryCode(await PasswordVaultAPI.hasRecoveryKey());
+    } catch (err) {
+      console.error("Failed to check recovery code:", err);
+    }
+  });
+
+  const handleRegenerateRecoveryCode = async () => {
+    setIsRegenerating(true);
+    const success = await regenerateRecoveryCode(recoveryPassword(), currentKeyFile());
+    setIsRegenerating(false);
+
+    if (success) {
+      // The new code is shown by the recovery code dialog
+      setHasRecoveryCode(true);
+      setRecoveryPassword("");
+      setShowRecoveryForm(false);
+    } else {
+      showToast(authError() ?? "Failed to generate recovery code", "error");
+    }
+  };
+
+  const openKeyFileForm = () => {
+    setKeyFilePassword("");
+    setKeyFilePath("");
+    setShowKeyFileForm(true);
+  };
+
+  const handleSaveKeyFile = async () => {
+    const required = !authState().requiresKeyFile;
+
+    setIsUpdatingKeyFile(true);
+    const success = await setKeyFileRequired(required, keyFilePassword(), keyFilePath());
+    setIsUpdatingKeyFile(false);
+
+    if (success) {
+      showToast(required ? "Key file added" : "Key file removed", "success");
+      setKeyFilePassword("");
+      setShowKeyFileForm(false);
+    } else {
+      showToast(authError() ?? "Failed to update key file", "error");
+    }
+  };
+
+  const handleRevokeRecoveryCode = async () => {
+    const confirmed = await showConfirm({
+      title: "Revoke Recovery Code",
+      message: "Your current recovery code will stop working. If you forget your master password, your vault cannot be recovered. Continue?",
+      confirmText: "Revoke",
+      type: "danger"
+    });
+
+    if (!confirmed) return;
+
+    try {
+      await PasswordVaultAPI.revokeRecoveryKey();
+      setHasRecoveryCode(false);
+      showToast("Recovery code revoked", "success");
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to revoke recovery code", "error");
+    }
+  };
+
+  const handleWipeAfterFailuresChange = async (value: number | null) => {
+    if (value !== null) {
+      const confirmed = await showConfirm({
+        title: "Wipe After Failed Attempts",
+        message: `Your vault and every entry will be permanently erased after ${value} failed unlock attempts in a row. Continue?`,
+        confirmText: "Enable",
+        type: "danger"
+      });
+      if (!confirmed) return;
+    }
+
+    try {
+      await PasswordVaultAPI.setLockoutSettings({ wipe_after_failures: value });
+      setWipeAfterFailures(value);
+      showToast("Lockout policy updated", "success");
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to update lockout policy", "error");
+    }
+  };
+
   const handleLogout = async () => {
     const confirmed = await showConfirm({
       title: "Logout",
@@ -64,8 +195,24 @@ const SettingsView = () => {
   };
 
   const handleExportData = () => {
-    // TODO: Implement data export
-    showToast("Export functionality coming soon", "info");
+    setExportPassword("");
+    setExportPath("");
+    setKeyFilePath("");
+    setShowExportForm(true);
+  };
+
+  const handleSaveExport = async () => {
+    setIsExporting(true);
+    try {
+      const count = await PasswordVaultAPI.exportVault(exportPassword(), exportPath(), currentKeyFile());
+      showToast(`Exported ${count} ${count === 1 ? "entry" : "entries"}`, "success");
+      setExportPassword("");
+      setShowExportForm(false);
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to export vault", "error");
+    } finally {
+      setIsExporting(false);
+    }
   };
 
   const handleImportData = () => {
@@ -74,8 +221,34 @@ const SettingsView = () => {
   };
 
   const handleChangeMasterPassword = () => {
-    // TODO: Implement master password change
-    showToast("Change master password functionality coming soon", "info");
+    setKeyFilePath("");
+    setCurrentPassword("");
+    setNewPassword("");
+    setConfirmNewPassword("");
+    setShowPasswordForm(true);
+  };
+
+  const handleSaveMasterPassword = async () => {
+    if (newPassword().length < 8) {
+      showToast("Password must be at least 8 characters long", "error");
+      return;
+    }
+
+    if (newPassword() !== confirmNewPassword()) {
+      showToast("Passwords do not match", "error");
+      return;
+    }
+
+    setIsChangingPassword(true);
+    const success = await changeMasterPassword(currentPassword(), newPassword(), currentKeyFile());
+    setIsChangingPassword(false);
+
+    if (success) {
+      showToast("Master password changed successfully", "success");
+      setShowPasswordForm(false);
+    } else {
+      showToast(authError() ?? "Failed to change master password", "error");
+    }
   };
 
   const handleAddCategory = () => {
@@ -121,28 +294,29 @@ const SettingsView = () => {
       showToast(`Category ${editingCategory() ? 'updated' : 'created'} successfully`, "success");
       setShowCategoryForm(false);
     } else {
-      showToast(`Failed to ${editingCategory() ? 'update' : 'create'} category`, "error");
+      showToast(categoriesError() ?? `Failed to ${editingCategory() ? 'update' : 'create'} category`, "error");
     }
   };
 
-  const handleDeleteCategory = async (categoryId: string) => {
-    const category = categories().find(c => c.id === categoryId);
-    if (!category) return;
+  const handleDeleteCategory = (categoryId: string) => {
+    setReassignTo("");
+    setDeletingCategory(categoryId);
+  };
 
-    const confirmed = await showConfirm({
-      title: "Delete Category",
-      message: `Are you sure you want to delete the "${category.name}" category? This action cannot be undone.`,
-      confirmText: "Delete",
-      type: "danger"
-    });
+  const confirmDeleteCategory = async () => {
+    const categoryId = deletingCategory();
+    if (!categoryId) return;
 
-    if (confirmed) {
-      const success = await deleteCategory(categoryId);
-      if (success) {
-        showToast("Category deleted successfully", "success");
-      } else {
-        showToast("Failed to delete category", "error");
-      }
+    const target = reassignTo();
+    const success = await deleteCategory(
+      categoryId,
+      target ? { action: "reassign", category_id: target } : { action: "clear" }
+    );
+    if (success) {
+      showToast("Category deleted successfully", "success");
+      setDeletingCategory(null);
+    } else {
+      showToast(categoriesError() ?? "Failed to delete category", "error");
     }
   };
 
@@ -175,6 +349,18 @@ const SettingsView = () => {
           >
             Security
           </button>
+          <button 
+            class={`tab ${activeTab() === 'generator' ? 'active' : ''}`}
+            onClick={() => setActiveTab('generator')}
+          >
+            Generator
+          </button>
+          <button 
+            class={`tab ${activeTab() === 'health' ? 'active' : ''}`}
+            onClick={() => setActiveTab('health')}
+          >
+            Health
+          </button>
           <button 
             class={`tab ${activeTab() === 'categories' ? 'active' : ''}`}
             onClick={() => setActiveTab('categories')}
@@ -277,6 +463,26 @@ const SettingsView = () => {
                 </div>
               </Show>
 
+              <div class="setting-item">
+                <label class="setting-label">Wipe After Failed Attempts</label>
+                <select
+                  value={wipeAfterFailures() ?? ""}
+                  onChange={async (e) => {
+                    const select = e.currentTarget;
+                    await handleWipeAfterFailuresChange(select.value ? parseInt(select.value) : null);
+                    // Snap back if the change was cancelled or rejected
+                    select.value = String(wipeAfterFailures() ?? "");
+                  }}
+                  class="setting-select"
+                >
+                  <option value="">Never</option>
+                  <option value={10}>10 attempts</option>
+                  <option value={20}>20 attempts</option>
+                  <option value={50}>50 attempts</option>
+                </select>
+                <p class="setting-description">Permanently erase the vault after this many failed unlock attempts in a row</p>
+              </div>
+
               <div class="setting-item">
                 <label class="setting-label">Master Password</label>
                 <button class="secondary-button" onClick={handleChangeMasterPassword}>
@@ -285,6 +491,42 @@ const SettingsView = () => {
                 <p class="setting-description">Update your master password</p>
               </div>
 
+              <div class="setting-item">
+                <label class="setting-label">Key File</label>
+                <button class="secondary-button" onClick={openKeyFileForm}>
+                  {authState().requiresKeyFile ? "Remove Key File" : "Add Key File"}
+                </button>
+                <p class="setting-description">
+                  {authState().requiresKeyFile
+                    ? "Unlocking requires your key file in addition to the master password"
+                    : "Require a file, such as one kept on a USB stick, in addition to the master password"}
+                </p>
+              </div>
+
+              <div class="setting-item">
+                <label class="setting-label">Recovery Code</label>
+                <button
+                  class="secondary-button"
+                  onClick={() => {
+                    setRecoveryPassword("");
+                    setKeyFilePath("");
+                    setShowRecoveryForm(true);
+                  }}
+                >
+                  {hasRecoveryCode() ? "Regenerate Recovery Code" : "Create Recovery Code"}
+                </button>
+                <Show when={hasRecoveryCode()}>
+                  <button class="danger-button" onClick={handleRevokeRecoveryCode}>
+                    Revoke
+                  </button>
+                </Show>
+                <p class="setting-description">
+                  {hasRecoveryCode()
+                    ? "A recovery code can unlock your vault if you forget your master password. Regenerating it invalidates the old one."
+                    : "No recovery code is set. Without one, a forgotten master password cannot be recovered."}
+                </p>
+              </div>
+
               <div class="setting-item danger-zone">
                 <label class="setting-label">Session</label>
                 <button class="danger-button" onClick={handleLogout}>
@@ -295,6 +537,16 @@ const SettingsView = () => {
             </div>
           </Show>
 
+          {/* Generator Tab */}
+          <Show when={activeTab() === 'generator'}>
+            <GeneratorSettings />
+          </Show>
+
+          {/* Health Tab */}
+          <Show when={activeTab() === 'health'}>
+            <VaultHealth />
+          </Show>
+
           {/* Categories Tab */}
           <Show when={activeTab() === 'categories'}>
             <div class="settings-section">
@@ -385,6 +637,43 @@ const SettingsView = () => {
       </div>
 
       {/* Category Form Modal */}
+      <Show when={deletingCategory()}>
+        {(categoryId) => (
+          <div class="modal-overlay">
+            <div class="modal-content">
+              <div class="modal-header">
+                <h3>Delete Category</h3>
+              </div>
+
+              <div class="modal-body">
+                <p>
+                  Delete the "{categories().find(c => c.id === categoryId())?.name}" category?
+                  Choose what happens to its entries.
+                </p>
+                <div class="form-group">
+                  <label>Move entries to</label>
+                  <select value={reassignTo()} onChange={(e) => setReassignTo(e.currentTarget.value)}>
+                    <option value="">Leave uncategorized</option>
+                    <For each={categories().filter(c => c.id !== categoryId())}>
+                      {(category) => <option value={category.id}>{category.icon} {category.name}</option>}
+                    </For>
+                  </select>
+                </div>
+              </div>
+
+              <div class="modal-actions">
+                <button class="secondary-button" onClick={() => setDeletingCategory(null)}>
+                  Cancel
+                </button>
+                <button class="danger-button" onClick={confirmDeleteCategory}>
+                  Delete
+                </button>
+              </div>
+            </div>
+          </div>
+        )}
+      </Show>
+
       <Show when={showCategoryForm()}>
         <div class="modal-overlay">
           <div class="modal-content">
@@ -446,6 +735,258 @@ const SettingsView = () => {
         </div>
       </Show>
 
+      {/* Change Master Password Modal */}
+      <Show when={showPasswordForm()}>
+        <div class="modal-overlay">
+          <div class="modal-content">
+            <div class="modal-header">
+              <h3>Change Master Password</h3>
+              <button class="close-button" onClick={() => setShowPasswordForm(false)}>
+                <svg width="24" height="24" viewBox="0 0 24 24" fill="currentColor">
+                  <path d="M19,6.41L17.59,5L12,10.59L6.41,5L5,6.41L10.59,12L5,17.59L6.41,19L12,13.41L17.59,19L19,17.59L13.41,12L19,6.41Z" />
+                </svg>
+              </button>
+            </div>
+
+            <div class="modal-body">
+              <div class="form-group">
+                <label>Current Password</label>
+                <input
+                  type="password"
+                  value={currentPassword()}
+                  onInput={(e) => setCurrentPassword(e.currentTarget.value)}
+                  placeholder="Enter current master password"
+                />
+              </div>
+
+              <Show when={authState().requiresKeyFile}>
+                <div class="form-group">
+                  <label>Key File</label>
+                  <input
+                    type="text"
+                    value={keyFilePath()}
+                    onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                    placeholder="Path to your key file"
+                  />
+                </div>
+              </Show>
+
+              <div class="form-group">
+                <label>New Password</label>
+                <input
+                  type="password"
+                  value={newPassword()}
+                  onInput={(e) => setNewPassword(e.currentTarget.value)}
+                  placeholder="Enter new master password"
+                />
+              </div>
+
+              <div class="form-group">
+                <label>Confirm New Password</label>
+                <input
+                  type="password"
+                  value={confirmNewPassword()}
+                  onInput={(e) => setConfirmNewPassword(e.currentTarget.value)}
+                  placeholder="Confirm new master password"
+                />
+              </div>
+            </div>
+
+            <div class="modal-actions">
+              <button class="secondary-button" onClick={() => setShowPasswordForm(false)}>
+                Cancel
+              </button>
+              <button
+                class="primary-button"
+                onClick={handleSaveMasterPassword}
+                disabled={isChangingPassword() || !currentPassword() || !newPassword()}
+              >
+                {isChangingPassword() ? 'Changing...' : 'Change Password'}
+              </button>
+            </div>
+          </div>
+        </div>
+      </Show>
+
+      {/* Recovery Code Modal */}
+      <Show when={showRecoveryForm()}>
+        <div class="modal-overlay">
+          <div class="modal-content">
+            <div class="modal-header">
+              <h3>{hasRecoveryCode() ? "Regenerate Recovery Code" : "Create Recovery Code"}</h3>
+              <button class="close-button" onClick={() => setShowRecoveryForm(false)}>
+                <svg width="24" height="24" viewBox="0 0 24 24" fill="currentColor">
+                  <path d="M19,6.41L17.59,5L12,10.59L6.41,5L5,6.41L10.59,12L5,17.59L6.41,19L12,13.41L17.59,19L19,17.59L13.41,12L19,6.41Z" />
+                </svg>
+              </button>
+            </div>
+
+            <div class="modal-body">
+              <div class="form-group">
+                <label>Master Password</label>
+                <input
+                  type="password"
+                  value={recoveryPassword()}
+                  onInput={(e) => setRecoveryPassword(e.currentTarget.value)}
+                  placeholder="Confirm your master password"
+                />
+              </div>
+
+              <Show when={authState().requiresKeyFile}>
+                <div class="form-group">
+                  <label>Key File</label>
+                  <input
+                    type="text"
+                    value={keyFilePath()}
+                    onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                    placeholder="Path to your key file"
+                  />
+                </div>
+              </Show>
+            </div>
+
+            <div class="modal-actions">
+              <button class="secondary-button" onClick={() => setShowRecoveryForm(false)}>
+                Cancel
+              </button>
+              <button
+                class="primary-button"
+                onClick={handleRegenerateRecoveryCode}
+                disabled={isRegenerating() || !recoveryPassword()}
+              >
+                {isRegenerating() ? 'Generating...' : 'Generate Code'}
+              </button>
+            </div>
+          </div>
+        </div>
+      </Show>
+
+      {/* Key File Modal */}
+      <Show when={showKeyFileForm()}>
+        <div class="modal-overlay">
+          <div class="modal-content">
+            <div class="modal-header">
+              <h3>{authState().requiresKeyFile ? "Remove Key File" : "Add Key File"}</h3>
+              <button class="close-button" onClick={() => setShowKeyFileForm(false)}>
+                <svg width="24" height="24" viewBox="0 0 24 24" fill="currentColor">
+                  <path d="M19,6.41L17.59,5L12,10.59L6.41,5L5,6.41L10.59,12L5,17.59L6.41,19L12,13.41L17.59,19L19,17.59L13.41,12L19,6.41Z" />
+                </svg>
+              </button>
+            </div>
+
+            <div class="modal-body">
+              <Show when={!authState().requiresKeyFile}>
+                <p>
+                  Any file works, but it must never change. Keep a backup: without it,
+                  only your recovery code can unlock the vault.
+                </p>
+              </Show>
+
+              <div class="form-group">
+                <label>Master Password</label>
+                <input
+                  type="password"
+                  value={keyFilePassword()}
+                  onInput={(e) => setKeyFilePassword(e.currentTarget.value)}
+                  placeholder="Confirm your master password"
+                />
+              </div>
+
+              <div class="form-group">
+                <label>Key File</label>
+                <input
+                  type="text"
+                  value={keyFilePath()}
+                  onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                  placeholder="Path to the key file"
+                />
+              </div>
+            </div>
+
+            <div class="modal-actions">
+              <button class="secondary-button" onClick={() => setShowKeyFileForm(false)}>
+                Cancel
+              </button>
+              <button
+                class="primary-button"
+                onClick={handleSaveKeyFile}
+                disabled={isUpdatingKeyFile() || !keyFilePassword() || !keyFilePath()}
+              >
+                {isUpdatingKeyFile() ? 'Saving...' : authState().requiresKeyFile ? 'Remove' : 'Add'}
+              </button>
+            </div>
+          </div>
+        </div>
+      </Show>
+
+      {/* Export Modal */}
+      <Show when={showExportForm()}>
+        <div class="modal-overlay">
+          <div class="modal-content">
+            <div class="modal-header">
+              <h3>Export Data</h3>
+              <button class="close-button" onClick={() => setShowExportForm(false)}>
+                <svg width="24" height="24" viewBox="0 0 24 24" fill="currentColor">
+                  <path d="M19,6.41L17.59,5L12,10.59L6.41,5L5,6.41L10.59,12L5,17.59L6.41,19L12,13.41L17.59,19L19,17.59L13.41,12L19,6.41Z" />
+                </svg>
+              </button>
+            </div>
+
+            <div class="modal-body">
+              <p>
+                Every entry, including passwords and hidden custom fields, is written
+                to a JSON file that is not encrypted. Delete it once you are done.
+              </p>
+
+              <div class="form-group">
+                <label>Master Password</label>
+                <input
+                  type="password"
+                  value={exportPassword()}
+                  onInput={(e) => setExportPassword(e.currentTarget.value)}
+                  placeholder="Confirm your master password"
+                />
+              </div>
+
+              <Show when={authState().requiresKeyFile}>
+                <div class="form-group">
+                  <label>Key File</label>
+                  <input
+                    type="text"
+                    value={keyFilePath()}
+                    onInput={(e) => setKeyFilePath(e.currentTarget.value)}
+                    placeholder="Path to your key file"
+                  />
+                </div>
+              </Show>
+
+              <div class="form-group">
+                <label>Save To</label>
+                <input
+                  type="text"
+                  value={exportPath()}
+                  onInput={(e) => setExportPath(e.currentTarget.value)}
+                  placeholder="Path of the file to write, e.g. passvault-export.json"
+                />
+              </div>
+            </div>
+
+            <div class="modal-actions">
+              <button class="secondary-button" onClick={() => setShowExportForm(false)}>
+                Cancel
+              </button>
+              <button
+                class="primary-button"
+                onClick={handleSaveExport}
+                disabled={isExporting() || !exportPassword() || !exportPath()}
+              >
+                {isExporting() ? 'Exporting...' : 'Export'}
+              </button>
+            </div>
+          </div>
+        </div>
+      </Show>
+
       <ConfirmDialog />
     </div>
   );
@@ -0,0 +1,152 @@
+import { createSignal, onMount, For, Show } from "solid-js";
+import { trashedEntries, loadTrash, restoreEntry, emptyTrash, isLoading, error } from "../store/trash";
+import { PasswordVaultAPI } from "../services/api";
+import { showToast } from "../components/StateFeedback/Toast";
+import type { AppError } from "../types";
+
+const TrashView = () => {
+  const [retentionDays, setRetentionDays] = createSignal(30);
+  const [showEmptyConfirm, setShowEmptyConfirm] = createSignal(false);
+
+  onMount(async () => {
+    await loadTrash();
+    try {
+      const settings = await PasswordVaultAPI.getTrashSettings();
+      setRetentionDays(settings.retention_days);
+    } catch (err) {
+      console.error("Load trash settings error:", err);
+    }
+  });
+
+  const handleRetentionChange = async (days: number) => {
+    try {
+      await PasswordVaultAPI.setTrashSettings({ retention_days: days });
+      setRetentionDays(days);
+    } catch (err) {
+      showToast((err as AppError)?.message ?? "Failed to save setting", "error");
+    }
+  };
+
+  const handleRestore = async (id: string) => {
+    if (await restoreEntry(id)) {
+      showToast("Entry restored", "success");
+    } else {
+      showToast("Failed to restore entry", "error");
+    }
+  };
+
+  const handleEmptyTrash = async () => {
+    if (await emptyTrash()) {
+      setShowEmptyConfirm(false);
+      showToast("Trash emptied", "success");
+    } else {
+      showToast("Failed to empty trash", "error");
+    }
+  };
+
+  const formatDate = (dateString: string) => {
+    return new Date(dateString).toLocaleDateString();
+  };
+
+  return (
+    <div class="view-container">
+      {/* Header */}
+      <div class="view-header">
+        <div class="view-title">
+          <svg class="view-icon" width="24" height="24" viewBox="0 0 24 24" fill="currentColor">
+            <path d="M19,4H15.5L14.5,3H9.5L8.5,4H5V6H19M6,19A2,2 0 0,0 8,21H16A2,2 0 0,0 18,19V7H6V19Z" />
+          </svg>
+          <h1>Trash</h1>
+          <span class="entry-count">({trashedEntries().length})</span>
+        </div>
+
+        <button
+          class="delete-button"
+          onClick={() => setShowEmptyConfirm(true)}
+          disabled={trashedEntries().length === 0}
+        >
+          Empty Trash
+        </button>
+      </div>
+
+      {/* Content */}
+      <div class="view-content">
+        <div class="setting-item">
+          <label class="setting-label">Keep Deleted Entries For</label>
+          <select
+            value={retentionDays()}
+            onChange={(e) => handleRetentionChange(parseInt(e.currentTarget.value))}
+            class="setting-select"
+          >
+            <option value={7}>1 week</option>
+            <option value={30}>30 days</option>
+            <option value={90}>90 days</option>
+            <option value={365}>1 year</option>
+          </select>
+          <p class="setting-description">Older entries are permanently deleted the next time the vault is unlocked</p>
+        </div>
+
+        <Show when={isLoading()}>
+          <div class="loading-state">
+            <div class="loading-spinner"></div>
+            <p>Loading trash...</p>
+          </div>
+        </Show>
+
+        <Show when={error()}>
+          <div class="error-state">
+            <h3>Error Loading Trash</h3>
+            <p>{error()}</p>
+          </div>
+        </Show>
+
+        <Show when={!isLoading() && trashedEntries().length === 0}>
+          <div class="empty-state">
+            <h3>Trash Is Empty</h3>
+            <p>Deleted entries stay here until they are restored or the trash is emptied.</p>
+          </div>
+        </Show>
+
+        <Show when={!isLoading() && trashedEntries().length > 0}>
+          <ul class="trash-list">
+            <For each={trashedEntries()}>
+              {(entry) => (
+                <li class="trash-item">
+                  <div class="trash-item-info">
+                    <span class="trash-item-title">{entry.title}</span>
+                    <span class="trash-item-meta">
+                      {entry.username || "No username"} · Deleted {formatDate(entry.deleted_at!)}
+                    </span>
+                  </div>
+                  <button class="secondary-button" onClick={() => handleRestore(entry.id)}>
+                    Restore
+                  </button>
+                </li>
+              )}
+            </For>
+          </ul>
+        </Show>
+      </div>
+
+      {/* Empty Trash Confirmation Modal */}
+      <Show when={showEmptyConfirm()}>
+        <div class="modal-overlay">
+          <div class="modal">
+            <h3>Empty Trash</h3>
+            <p>Permanently delete {trashedEntries().length} entries? This action cannot be undone.</p>
+            <div class="modal-actions">
+              <button class="cancel-button" onClick={() => setShowEmptyConfirm(false)}>
+                Cancel
+              </button>
+              <button class="delete-confirm-button" onClick={handleEmptyTrash}>
+                Empty Trash
+              </button>
+            </div>
+          </div>
+        </div>
+      </Show>
+    </div>
+  );
+};
+
+export default TrashView;